@@ -13,7 +13,8 @@ use glutin::{GlProfile, GlRequest, Api};
 use crate::geometry::{Position, Size, Vector};
 use crate::image::Color;
 use crate::draw::get_or_create_context;
-use crate::draw::Surface;
+use crate::draw::{Surface, Options};
+use crate::draw::configure_context;
 use crate::controller::keyboard;
 use crate::controller::mouse;
 use crate::widget::*;
@@ -256,18 +257,27 @@ impl<States> Window<States> {
     /// The **new() function** is not documented yet. Pull requests are welcome.
     ///
     pub fn new(size: Size<i32>) -> Window<States> {
+        Window::with_options(size, Options::default())
+    }
+
+    /// Constructs a window with options.
+    ///
+    /// This function constructs a window whose surface is created with given options (see
+    /// `Options`).
+    ///
+    pub fn with_options(size: Size<i32>, options: Options) -> Window<States> {
         let event_loop = get_or_create_event_loop();
         let shared_context = get_or_create_context();
 
         let window_builder = winit::window::WindowBuilder::new()
             .with_inner_size(winit::dpi::LogicalSize::new(size.width, size.height));
 
-        let windowed_context = glutin::ContextBuilder::new()
+        let context_builder = glutin::ContextBuilder::new()
             .with_gl(GlRequest::Specific(Api::OpenGlEs, (3, 2)))
             .with_gl_profile(GlProfile::Core)
-            .with_srgb(false)
-            .with_multisampling(0)
-            .with_shared_lists(shared_context)
+            .with_shared_lists(shared_context);
+
+        let windowed_context = configure_context(context_builder, &options)
             .build_windowed(window_builder, &event_loop)
             .unwrap();
 
@@ -275,7 +285,7 @@ impl<States> Window<States> {
             windowed_context.split()
         };
 
-        let surface = Surface::from_window(raw_context, size);
+        let surface = Surface::from_window(raw_context, size, options);
 
         Window {
             title: String::from(""),
@@ -0,0 +1,25 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+/// The horizontal alignments of a text
+///
+/// An alignment specifies how the lines of a text are positioned horizontally, relative to the
+/// maximum width of the text (or the width of the longest line if it has no maximum width).
+///
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum Alignment {
+    /// Lines start on the left edge.
+    #[default]
+    Left,
+    /// Lines are centered.
+    Center,
+    /// Lines end on the right edge.
+    Right,
+    /// Lines are stretched to fill the entire width, except the last line of a paragraph.
+    Justify
+}
+
@@ -0,0 +1,211 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::collections::BTreeMap;
+use crate::geometry::{Position, Size, Box};
+use crate::draw::Texture;
+
+// The regions of an atlas, by name; they're the index of the page and the area in the page.
+pub(crate) type Regions = BTreeMap<String, (usize, Box<i32>)>;
+
+fn invalid_data(line: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid atlas description line '{}'", line))
+}
+
+// Write the description of an atlas; there's one line per page (with the file name of its image)
+// followed by one line per region (the name is last so it can contain spaces).
+fn write_description<W: Write>(writer: &mut W, pages: &[String], regions: &Regions) -> io::Result<()> {
+    for page in pages {
+        writeln!(writer, "page {}", page)?;
+    }
+
+    for (name, (page, area)) in regions {
+        writeln!(writer, "region {} {} {} {} {} {}",
+                 page, area.left(), area.top(), area.size.width, area.size.height, name)?;
+    }
+
+    Ok(())
+}
+
+// Read the description of an atlas (see write_description()).
+fn read_description<R: Read>(reader: &mut R) -> io::Result<(Vec<String>, Regions)> {
+    let mut pages = Vec::new();
+    let mut regions = Regions::new();
+
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue
+        }
+
+        if let Some(page) = line.strip_prefix("page ") {
+            pages.push(page.to_string());
+        }
+        else if let Some(region) = line.strip_prefix("region ") {
+            let fields: Vec<&str> = region.splitn(6, ' ').collect();
+            if fields.len() != 6 {
+                return Err(invalid_data(&line))
+            }
+
+            let numbers = fields[..5].iter()
+                .map(|field| field.parse::<i32>())
+                .collect::<Result<Vec<i32>, _>>()
+                .map_err(|_| invalid_data(&line))?;
+
+            if numbers[0] < 0 || numbers[0] as usize >= pages.len() {
+                return Err(invalid_data(&line))
+            }
+
+            let area = Box::new(Position::new(numbers[1], numbers[2]), Size::new(numbers[3], numbers[4]));
+            regions.insert(fields[5].to_string(), (numbers[0] as usize, area));
+        }
+        else {
+            return Err(invalid_data(&line))
+        }
+    }
+
+    Ok((pages, regions))
+}
+
+/// A set of images packed into textures.
+///
+/// An atlas is a set of named images (the regions) which are packed into one or more textures
+/// (the pages); drawing many regions of the same page can be batched together (see `Batch`),
+/// whereas drawing many separate textures can't. Atlases are constructed with an `AtlasBuilder`,
+/// or loaded from files that were previously saved with `save()`.
+///
+/// ```no_run
+/// # use byteplug::draw::Atlas;
+/// # use byteplug::game::Sprite;
+/// let atlas = Atlas::open("resources/characters.atlas").unwrap();
+///
+/// let (texture, area) = atlas.region("hero").unwrap();
+/// let mut sprite = Sprite::with_texture(texture);
+/// sprite.set_texture_rect(Some(area));
+/// ```
+///
+/// **Implementation notes**
+///
+/// - The description of an atlas is a text file listing the file names of its pages (relative to
+///   the description) and its regions; the pages are saved as PNG files next to it.
+/// - The regions are kept sorted by name so the saved description is stable.
+///
+pub struct Atlas {
+    textures: Vec<Texture>,
+    regions: Regions
+}
+
+impl Atlas {
+    pub(crate) fn new(textures: Vec<Texture>, regions: Regions) -> Atlas {
+        Atlas {
+            textures: textures,
+            regions: regions
+        }
+    }
+
+    /// Loads an atlas from files.
+    ///
+    /// This function reads the description of an atlas, then loads its pages (see `save()`). An
+    /// error is returned if a file can't be read or if the description is invalid.
+    ///
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Atlas> {
+        let path = path.as_ref();
+        let (pages, regions) = read_description(&mut File::open(path)?)?;
+
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        let textures = pages.iter()
+            .map(|page| Texture::open(directory.join(page)))
+            .collect::<io::Result<Vec<Texture>>>()?;
+
+        Ok(Atlas::new(textures, regions))
+    }
+
+    /// Saves the atlas to files.
+    ///
+    /// This function writes the description of the atlas at a given path, and its pages next to
+    /// it; they're named after the description with the index of the page (for instance,
+    /// `characters.0.png` for `characters.atlas`).
+    ///
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+
+        let mut pages = Vec::new();
+        for (index, texture) in self.textures.iter().enumerate() {
+            let page = format!("{}.{}.png", stem, index);
+            let page_path: PathBuf = path.with_file_name(&page);
+
+            texture.to_image().save(&mut File::create(page_path)?)?;
+            pages.push(page);
+        }
+
+        write_description(&mut File::create(path)?, &pages, &self.regions)
+    }
+
+    /// Returns the number of pages.
+    pub fn page_count(&self) -> usize {
+        self.textures.len()
+    }
+
+    /// Returns the texture of a page.
+    ///
+    /// This function returns the texture of a page given its index; it panics if the index is out
+    /// of bounds.
+    ///
+    pub fn texture(&self, page: usize) -> &Texture {
+        &self.textures[page]
+    }
+
+    /// Returns a region.
+    ///
+    /// This function returns the texture of the page of a region and its area in the texture (in
+    /// pixels), which is the texture rectangle to use with a sprite. It returns `None` if there is
+    /// no region with this name.
+    ///
+    pub fn region(&self, name: &str) -> Option<(&Texture, Box<i32>)> {
+        self.regions.get(name).map(|(page, area)| (&self.textures[*page], *area))
+    }
+
+    /// Returns the names of the regions.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.regions.keys().map(|name| name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn atlas_description() {
+        let pages = vec!["sheet.0.png".to_string(), "sheet.1.png".to_string()];
+
+        let mut regions = Regions::new();
+        regions.insert("hero".to_string(), (0, Box::new(Position::new(2, 2), Size::new(16, 24))));
+        regions.insert("big tree".to_string(), (1, Box::new(Position::new(0, 30), Size::new(64, 64))));
+
+        let mut description = Vec::new();
+        write_description(&mut description, &pages, &regions).unwrap();
+        assert_eq!(String::from_utf8(description.clone()).unwrap(), "\
+            page sheet.0.png\n\
+            page sheet.1.png\n\
+            region 1 0 30 64 64 big tree\n\
+            region 0 2 2 16 24 hero\n");
+
+        assert_eq!(read_description(&mut description.as_slice()).unwrap(), (pages, regions));
+
+        let invalid = ["region 0 2 2 16 24 hero", "page a.png\nregion 0 2 x 16 24 hero", "pages a.png"];
+        for description in invalid.iter() {
+            let error = read_description(&mut description.as_bytes()).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
@@ -0,0 +1,353 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+use crate::geometry::{Position, Size, Box};
+use crate::image::{Color, Image};
+use crate::draw::{Texture, Atlas};
+use crate::draw::atlas::Regions;
+
+/// A segment of the skyline.
+///
+/// The skyline is the upper contour of the rectangles packed in a page; it's a list of horizontal
+/// segments, sorted from left to right, which cover the whole width of the page.
+///
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+struct Segment {
+    x: i32,
+    y: i32,
+    width: i32
+}
+
+/// A page being packed.
+///
+/// The packing algorithm is the 'skyline bottom-left' algorithm; a rectangle is put on the
+/// skyline where its bottom would be the lowest (then the leftmost), and the skyline is raised
+/// accordingly. The space below the skyline is never reused, but it's fast and it gives good
+/// results when the rectangles are sorted by height first.
+///
+struct Skyline {
+    size: Size<i32>,
+    segments: Vec<Segment>
+}
+
+impl Skyline {
+    fn new(size: Size<i32>) -> Skyline {
+        Skyline {
+            size: size,
+            segments: vec![Segment { x: 0, y: 0, width: size.width }]
+        }
+    }
+
+    // Compute the top of a rectangle put on the skyline at the left of a segment (none if it
+    // doesn't fit).
+    fn fit(&self, index: usize, size: Size<i32>) -> Option<i32> {
+        let x = self.segments[index].x;
+        if x + size.width > self.size.width {
+            return None
+        }
+
+        let mut y = 0;
+        let mut remaining = size.width;
+
+        for segment in &self.segments[index..] {
+            if remaining <= 0 {
+                break
+            }
+
+            y = y.max(segment.y);
+            if y + size.height > self.size.height {
+                return None
+            }
+
+            remaining -= segment.width;
+        }
+
+        Some(y)
+    }
+
+    fn insert(&mut self, size: Size<i32>) -> Option<Position<i32>> {
+        let (y, x, index) = (0..self.segments.len())
+            .filter_map(|index| self.fit(index, size).map(|y| (y, self.segments[index].x, index)))
+            .min()?;
+
+        // Insert the top of the rectangle, then shrink or remove the segments below it.
+        self.segments.insert(index, Segment { x: x, y: y + size.height, width: size.width });
+
+        let right = x + size.width;
+        while index + 1 < self.segments.len() {
+            let segment = &mut self.segments[index + 1];
+            if segment.x >= right {
+                break
+            }
+
+            if segment.x + segment.width <= right {
+                self.segments.remove(index + 1);
+            }
+            else {
+                segment.width -= right - segment.x;
+                segment.x = right;
+                break
+            }
+        }
+
+        // Merge the neighbor segments of the same height.
+        self.segments.dedup_by(|next, previous| {
+            let merge = next.y == previous.y;
+            if merge {
+                previous.width += next.width;
+            }
+
+            merge
+        });
+
+        Some(Position::new(x, y))
+    }
+}
+
+// Copy an image into a page, and extrude its edges (the pixels of the edges are repeated around
+// the image so the texture filtering doesn't sample the neighbor regions).
+fn copy_extruded(page: &mut Image, image: &Image, position: Position<i32>, extrusion: i32) {
+    let size = image.size();
+    let (width, height) = (size.width as i32, size.height as i32);
+
+    if width == 0 || height == 0 {
+        return
+    }
+
+    for y in -extrusion..height + extrusion {
+        for x in -extrusion..width + extrusion {
+            let source = Position::new(x.max(0).min(width - 1) as isize, y.max(0).min(height - 1) as isize);
+            let destination = Position::new((position.x + x) as isize, (position.y + y) as isize);
+
+            *page.pixel_mut(destination).unwrap() = *image.pixel(source).unwrap();
+        }
+    }
+}
+
+/// A builder of atlases.
+///
+/// An atlas builder collects named images, then packs them into the pages of an atlas (see
+/// `Atlas`). The pages have a fixed size and as many pages as needed are created.
+///
+/// ```no_run
+/// # use byteplug::geometry::Size;
+/// # use byteplug::image::Image;
+/// # use byteplug::draw::AtlasBuilder;
+/// let mut builder = AtlasBuilder::new(Size::new(1024, 1024));
+/// builder.set_extrusion(1);
+///
+/// for name in ["hero", "enemy", "tree"].iter() {
+///     builder.add(name, Image::open(format!("resources/{}.png", name)).unwrap());
+/// }
+///
+/// let atlas = builder.build();
+/// atlas.save("resources/characters.atlas").unwrap();
+/// ```
+///
+/// The regions can be separated by transparent pixels (the padding), and their edges can be
+/// extruded (repeated around them); both prevent the texture filtering from bleeding the
+/// neighbor regions into the drawn region when a sprite is smooth or scaled.
+///
+/// **Implementation notes**
+///
+/// - The images are packed by decreasing height, and each image is put on the first page it fits
+///   in; the result is deterministic.
+/// - The padding and the extrusion are added on all sides of each image; the areas of the regions
+///   exclude them.
+///
+pub struct AtlasBuilder {
+    page_size: Size<i32>,
+    padding: i32,
+    extrusion: i32,
+    images: Vec<(String, Image)>
+}
+
+impl AtlasBuilder {
+    /// Constructs an atlas builder.
+    ///
+    /// This function constructs an atlas builder whose pages have a given size, with no padding
+    /// and no extrusion.
+    ///
+    pub fn new(page_size: Size<i32>) -> AtlasBuilder {
+        AtlasBuilder {
+            page_size: page_size,
+            padding: 0,
+            extrusion: 0,
+            images: Vec::new()
+        }
+    }
+
+    /// Returns the padding.
+    pub fn padding(&self) -> i32 {
+        self.padding
+    }
+
+    /// Changes the padding.
+    ///
+    /// This function changes the number of transparent pixels around each region.
+    ///
+    pub fn set_padding(&mut self, padding: i32) {
+        assert!(padding >= 0, "the padding must be positive");
+        self.padding = padding;
+    }
+
+    /// Returns the extrusion.
+    pub fn extrusion(&self) -> i32 {
+        self.extrusion
+    }
+
+    /// Changes the extrusion.
+    ///
+    /// This function changes the number of times the edges of each region are repeated around it.
+    ///
+    pub fn set_extrusion(&mut self, extrusion: i32) {
+        assert!(extrusion >= 0, "the extrusion must be positive");
+        self.extrusion = extrusion;
+    }
+
+    /// Adds an image.
+    ///
+    /// This function adds an image which becomes a region of the atlas of a given name; an image
+    /// that was previously added with the same name is replaced. It panics if the image (with its
+    /// padding and its extrusion) doesn't fit in a page.
+    ///
+    pub fn add(&mut self, name: &str, image: Image) {
+        let size = self.cell_size(&image);
+        assert!(size.width <= self.page_size.width && size.height <= self.page_size.height,
+                "the image doesn't fit in a page");
+
+        self.images.retain(|(other, _)| other != name);
+        self.images.push((name.to_string(), image));
+    }
+
+    /// Builds the atlas.
+    ///
+    /// This function packs the images into the pages and uploads them to textures.
+    ///
+    pub fn build(&self) -> Atlas {
+        let (pages, regions) = self.pack();
+        let textures = pages.iter().map(Texture::with_image).collect();
+
+        Atlas::new(textures, regions)
+    }
+
+    // The size taken by an image in a page.
+    fn cell_size(&self, image: &Image) -> Size<i32> {
+        let margin = 2 * (self.padding + self.extrusion);
+        let size = image.size();
+
+        Size::new(size.width as i32 + margin, size.height as i32 + margin)
+    }
+
+    // Pack the images into the images of the pages.
+    fn pack(&self) -> (Vec<Image>, Regions) {
+        let mut order: Vec<&(String, Image)> = self.images.iter().collect();
+        order.sort_by(|(name, image), (other_name, other_image)| {
+            let (size, other_size) = (image.size(), other_image.size());
+            other_size.height.cmp(&size.height)
+                .then(other_size.width.cmp(&size.width))
+                .then(name.cmp(other_name))
+        });
+
+        let page_size = Size::new(self.page_size.width as usize, self.page_size.height as usize);
+
+        let mut skylines: Vec<Skyline> = Vec::new();
+        let mut pages: Vec<Image> = Vec::new();
+        let mut regions = Regions::new();
+
+        for (name, image) in order {
+            let size = self.cell_size(image);
+
+            let mut placement = skylines.iter_mut()
+                .enumerate()
+                .find_map(|(page, skyline)| skyline.insert(size).map(|position| (page, position)));
+
+            if placement.is_none() {
+                let mut skyline = Skyline::new(self.page_size);
+                let position = skyline.insert(size).unwrap();
+
+                skylines.push(skyline);
+                pages.push(Image::with_size(page_size, Color::TRANSPARENT));
+
+                placement = Some((pages.len() - 1, position));
+            }
+
+            let (page, position) = placement.unwrap();
+            let offset = self.padding + self.extrusion;
+            let position = Position::new(position.x + offset, position.y + offset);
+
+            copy_extruded(&mut pages[page], image, position, self.extrusion);
+
+            let image_size = image.size();
+            let area = Box::new(position, Size::new(image_size.width as i32, image_size.height as i32));
+            regions.insert(name.clone(), (page, area));
+        }
+
+        (pages, regions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn atlas_builder_skyline() {
+        let mut skyline = Skyline::new(Size::new(10, 10));
+
+        assert_eq!(skyline.insert(Size::new(4, 6)), Some(Position::new(0, 0)));
+        assert_eq!(skyline.insert(Size::new(4, 3)), Some(Position::new(4, 0)));
+        assert_eq!(skyline.insert(Size::new(4, 3)), Some(Position::new(4, 3)));
+        assert_eq!(skyline.segments, vec![
+            Segment { x: 0, y: 6, width: 8 },
+            Segment { x: 8, y: 0, width: 2 }
+        ]);
+
+        assert_eq!(skyline.insert(Size::new(2, 10)), Some(Position::new(8, 0)));
+        assert_eq!(skyline.insert(Size::new(8, 4)), Some(Position::new(0, 6)));
+        assert_eq!(skyline.insert(Size::new(1, 1)), None);
+    }
+
+    #[test]
+    fn atlas_builder_copy_extruded() {
+        let image = Image::with_pixels(Size::new(2, 1), vec![Color::RED, Color::BLUE]).unwrap();
+
+        let mut page = Image::with_size(Size::new(5, 4), Color::TRANSPARENT);
+        copy_extruded(&mut page, &image, Position::new(1, 1), 1);
+
+        let row = |y: usize| page.pixels()[y * 5..(y + 1) * 5].to_vec();
+        assert_eq!(row(0), vec![Color::RED, Color::RED, Color::BLUE, Color::BLUE, Color::TRANSPARENT]);
+        assert_eq!(row(1), vec![Color::RED, Color::RED, Color::BLUE, Color::BLUE, Color::TRANSPARENT]);
+        assert_eq!(row(2), vec![Color::RED, Color::RED, Color::BLUE, Color::BLUE, Color::TRANSPARENT]);
+        assert_eq!(row(3), vec![Color::TRANSPARENT; 5]);
+    }
+
+    #[test]
+    fn atlas_builder_pack() {
+        let mut builder = AtlasBuilder::new(Size::new(16, 16));
+        builder.set_padding(1);
+        builder.set_extrusion(1);
+
+        builder.add("small", Image::with_size(Size::new(4, 4), Color::RED));
+        builder.add("big", Image::with_size(Size::new(10, 10), Color::GREEN));
+        builder.add("wide", Image::with_size(Size::new(12, 4), Color::BLUE));
+
+        let (pages, regions) = builder.pack();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(regions["big"], (0, Box::new(Position::new(2, 2), Size::new(10, 10))));
+        assert_eq!(regions["wide"], (1, Box::new(Position::new(2, 2), Size::new(12, 4))));
+        assert_eq!(regions["small"], (1, Box::new(Position::new(2, 10), Size::new(4, 4))));
+
+        assert_eq!(*pages[0].pixel(Position::new(1, 1)).unwrap(), Color::GREEN);
+        assert_eq!(*pages[0].pixel(Position::new(0, 0)).unwrap(), Color::TRANSPARENT);
+
+        builder.add("big", Image::with_size(Size::new(2, 2), Color::GREEN));
+        let (pages, regions) = builder.pack();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(regions.len(), 3);
+    }
+}
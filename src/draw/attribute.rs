@@ -0,0 +1,39 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+use crate::draw::AttributeFormat;
+
+/// An attribute of a vertex layout.
+///
+/// An attribute is a field of a vertex type which is passed to the vertex shader; it's described
+/// by the location of the shader input it's bound to, its format, and its offset in the vertex (in
+/// bytes). See `VertexLayout`.
+///
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Attribute {
+    /// The location of the shader input.
+    pub location: u32,
+    /// The format of the attribute.
+    pub format: AttributeFormat,
+    /// The offset of the attribute in the vertex.
+    pub offset: usize
+}
+
+impl Attribute {
+    /// Constructs an attribute.
+    ///
+    /// This function constructs an attribute from its location, its format and its offset; it
+    /// can be used in constants.
+    ///
+    pub const fn new(location: u32, format: AttributeFormat, offset: usize) -> Attribute {
+        Attribute {
+            location: location,
+            format: format,
+            offset: offset
+        }
+    }
+}
@@ -0,0 +1,44 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+/// The formats of a vertex attribute
+///
+/// An attribute format specifies how an attribute is stored in a vertex, and the GLSL type it's
+/// received as by the shaders.
+///
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum AttributeFormat {
+    /// A `f32`, received as a `float`.
+    Float,
+    /// Two `f32`, received as a `vec2`.
+    Vector2,
+    /// Three `f32`, received as a `vec3`.
+    Vector3,
+    /// Four `f32`, received as a `vec4`.
+    Vector4,
+    /// Four `u8`, received as a `vec4` of normalized values (for instance, a compact color).
+    NormalizedBytes,
+    /// Four `u8`, received as a `uvec4` (for instance, the indices of bones).
+    Bytes,
+    /// A `i32`, received as an `int`.
+    Integer
+}
+
+impl AttributeFormat {
+    /// Returns the size of the attribute in bytes.
+    pub fn size(&self) -> usize {
+        match self {
+            AttributeFormat::Float           => 4,
+            AttributeFormat::Vector2         => 8,
+            AttributeFormat::Vector3         => 12,
+            AttributeFormat::Vector4         => 16,
+            AttributeFormat::NormalizedBytes => 4,
+            AttributeFormat::Bytes           => 4,
+            AttributeFormat::Integer         => 4
+        }
+    }
+}
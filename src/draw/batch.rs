@@ -0,0 +1,333 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+use std::ops::Range;
+use crate::geometry::Position;
+use crate::geometry::{Matrix, Transformer};
+use crate::draw::{Surface, Texture};
+use crate::draw::BlendMode;
+use crate::draw::{Primitive, Usage};
+use crate::draw::{Vertex, VertexArray};
+
+struct Item<'a> {
+    texture: Option<&'a Texture>,
+    key: usize, // identifies the texture (its address, zero if there is none)
+    layer: i32,
+    start: usize, // the range of its indices
+    count: usize
+}
+
+// Sort the items by layer and by texture, and merge the consecutive items sharing the same texture.
+// It returns the sorted indices and, for each merged group, the index of its first item and the
+// range of its indices (the vertices don't move).
+fn compute_groups(items: &mut Vec<Item>, indices: &[u32]) -> (Vec<u32>, Vec<(usize, Range<usize>)>) {
+    // The sort is stable; within a layer and a texture, items are drawn in the order they were
+    // added.
+    items.sort_by_key(|item| (item.layer, item.key));
+
+    let mut sorted_indices = Vec::with_capacity(indices.len());
+    let mut groups: Vec<(usize, Range<usize>)> = Vec::new();
+
+    for (index, item) in items.iter().enumerate() {
+        let start = sorted_indices.len();
+        sorted_indices.extend_from_slice(&indices[item.start..item.start + item.count]);
+
+        match groups.last_mut() {
+            Some((first, range)) if items[*first].key == item.key => {
+                range.end = sorted_indices.len();
+            },
+            _ => {
+                groups.push((index, start..sorted_indices.len()));
+            }
+        }
+    }
+
+    (sorted_indices, groups)
+}
+
+/// A batch of drawing operations.
+///
+/// A batch accumulates the vertices of many drawable entities in system memory, then draws them
+/// all at once with as few drawing calls as possible. Drawing entities individually (with
+/// `Surface::draw_vertices()`) re-binds the shader and re-uploads its uniforms every time, which
+/// quickly becomes the bottleneck when there are thousands of them; a batch is the solution.
+///
+/// Vertices are added with a texture, a matrix and a layer; they're transformed immediately (on
+/// the CPU) so entities with different transformations can be merged. When the batch is flushed,
+/// the vertices are uploaded into a single vertex array, their indices are sorted by layer and by
+/// texture, and each group of vertices sharing the same texture is drawn with one call.
+///
+/// ```no_run
+/// # use byteplug::geometry::Size;
+/// # use byteplug::draw::{Surface, Options, Batch};
+/// # use byteplug::game::Sprite;
+/// # struct Entity<'a> { sprite: Sprite<'a> }
+/// # let mut surface = Surface::new(Size::new(640, 480), Options::default()).unwrap();
+/// # let enemies: Vec<Entity> = Vec::new();
+/// # let player = Entity { sprite: Sprite::new() };
+/// let mut batch = Batch::new();
+///
+/// for enemy in enemies.iter() {
+///     enemy.sprite.add_to_batch(&mut batch, 1);
+/// }
+/// player.sprite.add_to_batch(&mut batch, 2);
+///
+/// batch.flush(&mut surface);
+/// ```
+///
+/// Layers are drawn in ascending order; entities of a lower layer are drawn below entities of a
+/// higher layer. Within a layer, the entities are re-ordered by texture, therefore, the order in
+/// which overlapping entities of the same layer are drawn is only preserved if they share the same
+/// texture. Put them in different layers if it matters.
+///
+/// **Implementation notes**
+///
+/// - Vertices are always drawn as indexed triangles; quads are added with `add_quad()` which
+///   splits them into two triangles sharing two vertices. Only the indices are re-ordered when the
+///   batch is flushed.
+/// - Textures are identified by their address; a texture must not move while it's in the batch
+///   (the borrow checker guarantees it).
+/// - The vertex array is kept between the flushes to avoid re-creating an OpenGL object buffer
+///   every frame.
+///
+pub struct Batch<'a> {
+    items: Vec<Item<'a>>,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    blend_mode: BlendMode,
+    vertex_array: Option<VertexArray>
+}
+
+impl<'a> Batch<'a> {
+    /// Constructs an empty batch.
+    ///
+    /// This function is the default constructor. It creates a batch with no vertices.
+    ///
+    pub fn new() -> Batch<'a> {
+        Batch {
+            items: Vec::new(),
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            blend_mode: BlendMode::ALPHA,
+            vertex_array: None
+        }
+    }
+
+    /// Returns the blending mode of the batch.
+    ///
+    /// This function returns the blending mode the vertices are drawn with.
+    ///
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    /// Changes the blending mode of the batch.
+    ///
+    /// This function changes the blending mode the vertices are drawn with; it applies to all the
+    /// vertices of the batch. The default blending mode is the alpha blending mode.
+    ///
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
+    /// Adds triangles to the batch.
+    ///
+    /// This function adds a list of triangles (3 vertices per triangle) with the texture they're
+    /// drawn with, the matrix they're transformed with, and the layer they're drawn in.
+    ///
+    pub fn add(&mut self, vertices: &[Vertex], texture: Option<&'a Texture>, matrix: Option<Matrix>, layer: i32) {
+        if vertices.is_empty() {
+            return
+        }
+
+        let base = self.push_vertices(vertices, matrix);
+        let start = self.indices.len();
+        self.indices.extend(base..base + vertices.len() as u32);
+
+        self.push_item(texture, layer, start);
+    }
+
+    /// Adds indexed triangles to the batch.
+    ///
+    /// This function is the same as `add()` except that the triangles are formed by the vertices
+    /// the indices refer to (3 indices per triangle); the indices are relative to the given list
+    /// of vertices.
+    ///
+    pub fn add_indexed(&mut self, vertices: &[Vertex], indices: &[u32], texture: Option<&'a Texture>, matrix: Option<Matrix>, layer: i32) {
+        if indices.is_empty() {
+            return
+        }
+
+        assert!(indices.iter().all(|index| (*index as usize) < vertices.len()), "an index is out of bounds");
+
+        let base = self.push_vertices(vertices, matrix);
+        let start = self.indices.len();
+        self.indices.extend(indices.iter().map(|index| base + index));
+
+        self.push_item(texture, layer, start);
+    }
+
+    /// Adds a quad to the batch.
+    ///
+    /// This function adds a quad whose vertices are ordered like a triangle strip (top-left,
+    /// top-right, bottom-left and bottom-right); it's split into two triangles.
+    ///
+    pub fn add_quad(&mut self, quad: &[Vertex; 4], texture: Option<&'a Texture>, matrix: Option<Matrix>, layer: i32) {
+        self.add_indexed(quad, &[0, 1, 2, 2, 1, 3], texture, matrix, layer);
+    }
+
+    /// Returns the number of vertices in the batch.
+    ///
+    /// This function returns the number of vertices which were added since the last flush.
+    ///
+    pub fn size(&self) -> usize {
+        self.vertices.len()
+    }
+
+    // Transform and append vertices; it returns the index of the first one.
+    fn push_vertices(&mut self, vertices: &[Vertex], matrix: Option<Matrix>) -> u32 {
+        let base = self.vertices.len() as u32;
+
+        match matrix {
+            Some(matrix) => {
+                let transformer = Transformer::with_matrix(matrix);
+                self.vertices.extend(vertices.iter().map(|vertex| {
+                    let position = transformer.transform_position(&Position::new(vertex.x, vertex.y));
+
+                    let mut vertex = *vertex;
+                    vertex.x = position.x;
+                    vertex.y = position.y;
+
+                    vertex
+                }));
+            },
+            None => self.vertices.extend_from_slice(vertices)
+        }
+
+        base
+    }
+
+    // Append an item whose indices start at a given position and end with the indices.
+    fn push_item(&mut self, texture: Option<&'a Texture>, layer: i32, start: usize) {
+        self.items.push(Item {
+            texture: texture,
+            key: texture.map_or(0, |texture| texture as *const Texture as usize),
+            layer: layer,
+            start: start,
+            count: self.indices.len() - start
+        });
+    }
+
+    /// Removes all vertices from the batch.
+    ///
+    /// This function discards the vertices which were added since the last flush, without drawing
+    /// them.
+    ///
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.vertices.clear();
+        self.indices.clear();
+    }
+
+    /// Draws the batch on a surface.
+    ///
+    /// This function draws all the vertices which were added since the last flush, then it clears
+    /// the batch so it can be re-used for the next frame.
+    ///
+    pub fn flush(&mut self, surface: &mut Surface) {
+        if self.items.is_empty() {
+            return
+        }
+
+        let (indices, groups) = compute_groups(&mut self.items, &self.indices);
+
+        let vertex_array = self.vertex_array.get_or_insert_with(|| {
+            let mut vertex_array = VertexArray::new();
+            vertex_array.set_primitive(Primitive::Triangles);
+            vertex_array.set_usage(Usage::Stream);
+
+            vertex_array
+        });
+        vertex_array.update_vertices(&self.vertices);
+        vertex_array.update_indices(&indices);
+
+        let items = &self.items;
+        let ranges: Vec<(Option<&Texture>, Range<usize>)> = groups.into_iter()
+            .map(|(index, range)| (items[index].texture, range))
+            .collect();
+        surface.draw_vertex_ranges(vertex_array, self.blend_mode, &ranges);
+
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image::Color;
+
+    fn item(key: usize, layer: i32, start: usize, count: usize) -> Item<'static> {
+        Item { texture: None, key: key, layer: layer, start: start, count: count }
+    }
+
+    #[test]
+    fn batch_compute_groups() {
+        let indices: Vec<u32> = (0..12).collect();
+
+        let mut items = vec![
+            item(2, 0, 0, 3),
+            item(1, 0, 3, 3),
+            item(2, 0, 6, 3),
+            item(1, -1, 9, 3)
+        ];
+
+        let (sorted, groups) = compute_groups(&mut items, &indices);
+
+        // The lower layer goes first, then the items of the same texture are merged (keeping the
+        // order they were added in); consecutive items of different layers are merged too.
+        assert_eq!(sorted, [9, 10, 11, 3, 4, 5, 0, 1, 2, 6, 7, 8]);
+
+        let groups: Vec<(usize, Range<usize>)> = groups.into_iter()
+            .map(|(index, range)| (items[index].key, range))
+            .collect();
+        assert_eq!(groups, [(1, 0..6), (2, 6..12)]);
+    }
+
+    #[test]
+    fn batch_add() {
+        let mut batch = Batch::new();
+        assert_eq!(batch.size(), 0);
+
+        let quad = [
+            Vertex::with_position_and_color(Position::new(0.0, 0.0), Color::WHITE),
+            Vertex::with_position_and_color(Position::new(1.0, 0.0), Color::WHITE),
+            Vertex::with_position_and_color(Position::new(0.0, 1.0), Color::WHITE),
+            Vertex::with_position_and_color(Position::new(1.0, 1.0), Color::WHITE)
+        ];
+
+        let matrix = Transformer::new().translate(Position::new(10.0, 20.0)).matrix;
+        batch.add_quad(&quad, None, Some(matrix), 0);
+        assert_eq!(batch.size(), 4);
+
+        // The vertices are transformed when they're added.
+        let positions: Vec<(f32, f32)> = batch.vertices.iter().map(|vertex| (vertex.x, vertex.y)).collect();
+        assert_eq!(positions, [(10.0, 20.0), (11.0, 20.0), (10.0, 21.0), (11.0, 21.0)]);
+        assert_eq!(batch.indices, [0, 1, 2, 2, 1, 3]);
+
+        // The indices are offset by the vertices which were already added.
+        batch.add(&quad[..3], None, None, 0);
+        assert_eq!(batch.size(), 7);
+        assert_eq!(batch.indices[6..], [4, 5, 6]);
+        assert_eq!((batch.items[1].start, batch.items[1].count), (6, 3));
+
+        batch.add(&[], None, None, 0);
+        assert_eq!(batch.items.len(), 2);
+
+        batch.clear();
+        assert_eq!(batch.size(), 0);
+    }
+}
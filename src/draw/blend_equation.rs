@@ -0,0 +1,29 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+/// The blending equations
+///
+/// A blending equation specifies how the weighted source color (the color of the pixel being
+/// drawn) and the weighted destination color (the color of the pixel already on the surface) are
+/// combined (see `BlendMode`).
+///
+/// Note that the minimum and maximum equations ignore the blending factors; the colors are
+/// combined as they are.
+///
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum BlendEquation {
+    /// source * source factor + destination * destination factor
+    Add,
+    /// source * source factor - destination * destination factor
+    Subtract,
+    /// destination * destination factor - source * source factor
+    ReverseSubtract,
+    /// min(source, destination)
+    Min,
+    /// max(source, destination)
+    Max
+}
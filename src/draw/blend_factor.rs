@@ -0,0 +1,36 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+/// The blending factors
+///
+/// A blending factor specifies how the source color (the color of the pixel being drawn) or the
+/// destination color (the color of the pixel already on the surface) is weighted before they're
+/// combined by the blending equation (see `BlendMode`).
+///
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum BlendFactor {
+    /// (0, 0, 0, 0)
+    Zero,
+    /// (1, 1, 1, 1)
+    One,
+    /// (source.r, source.g, source.b, source.a)
+    SourceColor,
+    /// (1, 1, 1, 1) - (source.r, source.g, source.b, source.a)
+    OneMinusSourceColor,
+    /// (destination.r, destination.g, destination.b, destination.a)
+    DestinationColor,
+    /// (1, 1, 1, 1) - (destination.r, destination.g, destination.b, destination.a)
+    OneMinusDestinationColor,
+    /// (source.a, source.a, source.a, source.a)
+    SourceAlpha,
+    /// (1, 1, 1, 1) - (source.a, source.a, source.a, source.a)
+    OneMinusSourceAlpha,
+    /// (destination.a, destination.a, destination.a, destination.a)
+    DestinationAlpha,
+    /// (1, 1, 1, 1) - (destination.a, destination.a, destination.a, destination.a)
+    OneMinusDestinationAlpha
+}
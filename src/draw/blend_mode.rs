@@ -0,0 +1,139 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+use crate::draw::{BlendFactor, BlendEquation};
+
+/// The blending mode of a drawing operation.
+///
+/// A blending mode specifies how the pixels being drawn (the source) are combined with the pixels
+/// already on the surface (the destination). The source and destination colors are weighted by
+/// their blending factors, then combined by the blending equation; the color channels and the
+/// alpha channel have their own factors and equation.
+///
+/// The usual modes are available as constants; `ALPHA` is the default one.
+///
+/// ```no_run
+/// # use byteplug::geometry::Size;
+/// # use byteplug::draw::{Surface, Options, Shape, RenderStates, BlendMode};
+/// # let mut surface = Surface::new(Size::new(640, 480), Options::default()).unwrap();
+/// # let particles = Shape::new();
+/// let states = RenderStates {
+///     blend_mode: BlendMode::ADDITIVE,
+///     ..RenderStates::default()
+/// };
+/// surface.draw_with_states(&particles, &states);
+/// ```
+///
+/// Custom modes are constructed with `new()` when the color and the alpha channels are blended
+/// the same way, or with `with_separate_alpha()` otherwise.
+///
+/// **Implementation notes**
+///
+/// - The `PREMULTIPLIED_ALPHA` mode expects the colors of the textures and the vertices to be
+///   already multiplied by their alpha; it's the correct mode to composite images that were
+///   rendered with the `ALPHA` mode onto a transparent surface.
+///
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct BlendMode {
+    /// The factor of the source color channels.
+    pub color_source: BlendFactor,
+    /// The factor of the destination color channels.
+    pub color_destination: BlendFactor,
+    /// The equation of the color channels.
+    pub color_equation: BlendEquation,
+    /// The factor of the source alpha channel.
+    pub alpha_source: BlendFactor,
+    /// The factor of the destination alpha channel.
+    pub alpha_destination: BlendFactor,
+    /// The equation of the alpha channel.
+    pub alpha_equation: BlendEquation
+}
+
+impl BlendMode {
+    /// The source is drawn over the destination according to its opacity (the default mode).
+    pub const ALPHA: BlendMode = BlendMode::with_separate_alpha(
+        BlendFactor::SourceAlpha, BlendFactor::OneMinusSourceAlpha, BlendEquation::Add,
+        BlendFactor::One, BlendFactor::OneMinusSourceAlpha, BlendEquation::Add
+    );
+
+    /// The source is added to the destination according to its opacity.
+    pub const ADDITIVE: BlendMode = BlendMode::with_separate_alpha(
+        BlendFactor::SourceAlpha, BlendFactor::One, BlendEquation::Add,
+        BlendFactor::One, BlendFactor::One, BlendEquation::Add
+    );
+
+    /// The source is multiplied with the destination.
+    pub const MULTIPLY: BlendMode = BlendMode::new(
+        BlendFactor::DestinationColor, BlendFactor::Zero, BlendEquation::Add
+    );
+
+    /// The inverse of the source is multiplied with the inverse of the destination.
+    pub const SCREEN: BlendMode = BlendMode::new(
+        BlendFactor::One, BlendFactor::OneMinusSourceColor, BlendEquation::Add
+    );
+
+    /// The source, whose color is premultiplied by its alpha, is drawn over the destination.
+    pub const PREMULTIPLIED_ALPHA: BlendMode = BlendMode::new(
+        BlendFactor::One, BlendFactor::OneMinusSourceAlpha, BlendEquation::Add
+    );
+
+    /// The source replaces the destination.
+    pub const NONE: BlendMode = BlendMode::new(
+        BlendFactor::One, BlendFactor::Zero, BlendEquation::Add
+    );
+
+    /// Constructs a blending mode.
+    ///
+    /// This function constructs a blending mode whose color and alpha channels are blended with
+    /// the same factors and equation.
+    ///
+    pub const fn new(source: BlendFactor, destination: BlendFactor, equation: BlendEquation) -> BlendMode {
+        BlendMode::with_separate_alpha(source, destination, equation, source, destination, equation)
+    }
+
+    /// Constructs a blending mode with a separate alpha blending.
+    ///
+    /// This function constructs a blending mode whose color and alpha channels are blended with
+    /// their own factors and equation.
+    ///
+    pub const fn with_separate_alpha(
+        color_source: BlendFactor, color_destination: BlendFactor, color_equation: BlendEquation,
+        alpha_source: BlendFactor, alpha_destination: BlendFactor, alpha_equation: BlendEquation
+    ) -> BlendMode {
+        BlendMode {
+            color_source: color_source,
+            color_destination: color_destination,
+            color_equation: color_equation,
+            alpha_source: alpha_source,
+            alpha_destination: alpha_destination,
+            alpha_equation: alpha_equation
+        }
+    }
+}
+
+impl Default for BlendMode {
+    fn default() -> BlendMode {
+        BlendMode::ALPHA
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blend_mode_new() {
+        let mode = BlendMode::new(BlendFactor::One, BlendFactor::Zero, BlendEquation::Max);
+        assert_eq!(mode, BlendMode::with_separate_alpha(
+            BlendFactor::One, BlendFactor::Zero, BlendEquation::Max,
+            BlendFactor::One, BlendFactor::Zero, BlendEquation::Max
+        ));
+
+        assert_eq!(BlendMode::default(), BlendMode::ALPHA);
+        assert_eq!(BlendMode::NONE.alpha_destination, BlendFactor::Zero);
+    }
+}
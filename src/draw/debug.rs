@@ -0,0 +1,363 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+//! The debug output of the draw module
+//!
+//! This module routes the messages of the graphics driver to a logger. In debug builds, the errors
+//! of the OpenGL calls of the draw module are always reported; and when a surface is created with
+//! the debug option (see `Options`), the messages of the debug output of its OpenGL context are
+//! reported too (they're more detailed, and they include warnings about performance and
+//! portability).
+//!
+//! ```
+//! # use byteplug::draw::debug::{self, Severity};
+//! debug::set_minimum_severity(Severity::Medium);
+//! debug::set_logger(|message| {
+//!     eprintln!("{:?}: {}", message.severity, message.text);
+//! });
+//! ```
+//!
+//! By default, the messages are written to the standard error. Use `set_panic_on_error()` to panic
+//! when an error is reported, which is useful to make tests fail on OpenGL errors.
+//!
+//! **Implementation notes**
+//!
+//! - The logger is global (the OpenGL contexts are shared) and it may be called from any thread.
+//! - The debug output is synchronous so the messages are reported from the thread that caused
+//!   them, in the middle of the faulty call.
+//! - The debug callback is called by the graphics driver, so it must not unwind; it only logs the
+//!   messages and records the errors. The errors are checked after each OpenGL call (see
+//!   `gl_check!`), which is where the panics on errors happen; an error reported by the debug
+//!   output isn't reported a second time by `GetError()`.
+//!
+use std::cell::RefCell;
+use std::ffi::{c_void, CStr, CString};
+use std::sync::RwLock;
+use crate::draw::gl;
+
+/// The severity of a debug message
+///
+/// The severities are ordered; notifications are the least severe messages and errors are always
+/// of high severity.
+///
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Severity {
+    /// Informational message.
+    Notification,
+    /// Minor issue, for instance, a redundant state change.
+    Low,
+    /// Major issue, for instance, a significant performance warning.
+    Medium,
+    /// Error, or undefined behavior.
+    High
+}
+
+/// The source of a debug message
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Source {
+    /// The OpenGL API (including the errors of the OpenGL calls).
+    Api,
+    /// The window system.
+    WindowSystem,
+    /// The shader compiler.
+    ShaderCompiler,
+    /// A third-party tool (for instance, a debugger).
+    ThirdParty,
+    /// The application itself.
+    Application,
+    /// Any other source.
+    Other
+}
+
+/// The kind of a debug message
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Kind {
+    /// An error.
+    Error,
+    /// Use of a deprecated behavior.
+    DeprecatedBehavior,
+    /// Use of an undefined behavior.
+    UndefinedBehavior,
+    /// Use of a non-portable functionality.
+    Portability,
+    /// A performance issue.
+    Performance,
+    /// A marker of a debugging tool.
+    Marker,
+    /// Any other kind.
+    Other
+}
+
+/// A debug message
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Message {
+    /// The source of the message.
+    pub source: Source,
+    /// The kind of the message.
+    pub kind: Kind,
+    /// The severity of the message.
+    pub severity: Severity,
+    /// The identifier of the message (specific to the graphics driver).
+    pub id: u32,
+    /// The text of the message.
+    pub text: String
+}
+
+type Logger = Box<dyn Fn(&Message) + Send + Sync>;
+
+struct Settings {
+    logger: Option<Logger>, // the default logger if it's none
+    minimum_severity: Severity,
+    panic_on_error: bool
+}
+
+static SETTINGS: RwLock<Settings> = RwLock::new(Settings {
+    logger: None,
+    minimum_severity: Severity::Low,
+    panic_on_error: false
+});
+
+thread_local! {
+    // The last error reported by the debug output of the current thread, until it's checked.
+    static PENDING_ERROR: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Changes the logger.
+///
+/// This function changes the function the debug messages are passed to. The default logger writes
+/// them to the standard error.
+///
+pub fn set_logger<F: Fn(&Message) + Send + Sync + 'static>(logger: F) {
+    SETTINGS.write().unwrap().logger = Some(Box::new(logger));
+}
+
+/// Restores the default logger.
+///
+/// This function restores the logger which writes the debug messages to the standard error.
+///
+pub fn reset_logger() {
+    SETTINGS.write().unwrap().logger = None;
+}
+
+/// Changes the minimum severity.
+///
+/// This function changes the severity below which the debug messages are ignored. The default
+/// minimum severity is `Severity::Low` (notifications are ignored).
+///
+pub fn set_minimum_severity(severity: Severity) {
+    SETTINGS.write().unwrap().minimum_severity = severity;
+}
+
+/// Changes whether errors panic.
+///
+/// This function changes whether an error message panics after it's passed to the logger. It's
+/// disabled by default.
+///
+pub fn set_panic_on_error(panic: bool) {
+    SETTINGS.write().unwrap().panic_on_error = panic;
+}
+
+/// Reports a debug message.
+///
+/// This function passes a message to the logger if it's severe enough, then panics if it's an
+/// error and the panic on error option is enabled. It's used by the draw module to report its
+/// messages, but it can also be used to report messages of the application.
+///
+pub fn report(message: Message) {
+    log(&message);
+
+    if message.kind == Kind::Error && is_panic_on_error() {
+        panic!("OpenGL error: {}", message.text);
+    }
+}
+
+// Pass a message to the logger if it's severe enough.
+fn log(message: &Message) {
+    let settings = SETTINGS.read().unwrap();
+
+    if message.severity >= settings.minimum_severity {
+        match &settings.logger {
+            Some(logger) => logger(message),
+            None => eprintln!("OpenGL {:?} ({:?}, {:?} severity): {}",
+                              message.kind, message.source, message.severity, message.text)
+        }
+    }
+}
+
+fn is_panic_on_error() -> bool {
+    SETTINGS.read().unwrap().panic_on_error
+}
+
+/// Checks the errors of an OpenGL call.
+///
+/// This function is used by the `gl_check!` macro after an OpenGL call; it reports the error
+/// returned by `GetError()` unless the debug output already reported it, then panics if there was
+/// an error and the panic on error option is enabled.
+///
+#[doc(hidden)]
+pub fn check_error(file: &str, line: u32, call: &str, error: gl::types::GLenum) {
+    let pending = PENDING_ERROR.with(|pending| pending.borrow_mut().take());
+    if error == gl::NO_ERROR && pending.is_none() {
+        return
+    }
+
+    let text = match pending {
+        Some(text) => text,
+        None => {
+            let message = error_message(file, line, call, error);
+            log(&message);
+
+            message.text
+        }
+    };
+
+    if is_panic_on_error() {
+        panic!("OpenGL error in {}:{} ({}): {}", file, line, call, text);
+    }
+}
+
+fn error_message(file: &str, line: u32, call: &str, error: gl::types::GLenum) -> Message {
+    let error = match error {
+        gl::INVALID_ENUM => "GL_INVALID_ENUM",
+        gl::INVALID_VALUE => "GL_INVALID_VALUE",
+        gl::INVALID_OPERATION => "GL_INVALID_OPERATION",
+        gl::INVALID_FRAMEBUFFER_OPERATION => "GL_INVALID_FRAMEBUFFER_OPERATION",
+        gl::OUT_OF_MEMORY => "GL_OUT_OF_MEMORY",
+        gl::STACK_UNDERFLOW => "GL_STACK_UNDERFLOW",
+        gl::STACK_OVERFLOW => "GL_STACK_OVERFLOW",
+        _ => "unknown error"
+    };
+
+    Message {
+        source: Source::Api,
+        kind: Kind::Error,
+        severity: Severity::High,
+        id: 0,
+        text: format!("{}:{} - {} caused {}", file, line, call, error)
+    }
+}
+
+fn to_source(source: gl::types::GLenum) -> Source {
+    match source {
+        gl::DEBUG_SOURCE_API             => Source::Api,
+        gl::DEBUG_SOURCE_WINDOW_SYSTEM   => Source::WindowSystem,
+        gl::DEBUG_SOURCE_SHADER_COMPILER => Source::ShaderCompiler,
+        gl::DEBUG_SOURCE_THIRD_PARTY     => Source::ThirdParty,
+        gl::DEBUG_SOURCE_APPLICATION     => Source::Application,
+        _                                => Source::Other
+    }
+}
+
+fn to_kind(kind: gl::types::GLenum) -> Kind {
+    match kind {
+        gl::DEBUG_TYPE_ERROR               => Kind::Error,
+        gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => Kind::DeprecatedBehavior,
+        gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR  => Kind::UndefinedBehavior,
+        gl::DEBUG_TYPE_PORTABILITY         => Kind::Portability,
+        gl::DEBUG_TYPE_PERFORMANCE         => Kind::Performance,
+        gl::DEBUG_TYPE_MARKER              => Kind::Marker,
+        _                                  => Kind::Other
+    }
+}
+
+fn to_severity(severity: gl::types::GLenum) -> Severity {
+    match severity {
+        gl::DEBUG_SEVERITY_HIGH   => Severity::High,
+        gl::DEBUG_SEVERITY_MEDIUM => Severity::Medium,
+        gl::DEBUG_SEVERITY_LOW    => Severity::Low,
+        _                         => Severity::Notification
+    }
+}
+
+extern "system" fn debug_callback(
+    source: gl::types::GLenum,
+    kind: gl::types::GLenum,
+    id: gl::types::GLuint,
+    severity: gl::types::GLenum,
+    _length: gl::types::GLsizei,
+    text: *const gl::types::GLchar,
+    _user_data: *mut c_void
+) {
+    let text = unsafe { CStr::from_ptr(text) }.to_string_lossy().into_owned();
+
+    let message = Message {
+        source: to_source(source),
+        kind: to_kind(kind),
+        severity: to_severity(severity),
+        id: id,
+        text: text
+    };
+
+    // A panic must not unwind into the graphics driver (the logger may panic too).
+    let _ = std::panic::catch_unwind(|| log(&message));
+
+    if message.kind == Kind::Error {
+        PENDING_ERROR.with(|pending| *pending.borrow_mut() = Some(message.text));
+    }
+}
+
+/// Enable the debug output of the current OpenGL context.
+///
+/// This function installs the debug callback on the current OpenGL context (it must be a debug
+/// context); the messages are reported synchronously.
+///
+pub(crate) fn enable_debug_output() {
+    unsafe {
+        gl_check!(gl::Enable(gl::DEBUG_OUTPUT));
+        gl_check!(gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS));
+        gl_check!(gl::DebugMessageCallback(debug_callback, std::ptr::null()));
+    }
+}
+
+/// Label an OpenGL object.
+///
+/// This function attaches a label to an OpenGL object; the label is used in the debug messages
+/// and by the debugging tools. The null characters of the label are removed.
+///
+pub(crate) fn set_object_label(identifier: gl::types::GLenum, name: gl::types::GLuint, label: &str) {
+    let label = CString::new(label.replace('\0', "")).unwrap();
+    unsafe {
+        gl_check!(gl::ObjectLabel(identifier, name, -1, label.as_ptr()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::draw::testing::lock_rendering;
+
+    #[test]
+    fn debug_conversions() {
+        assert_eq!(to_source(gl::DEBUG_SOURCE_SHADER_COMPILER), Source::ShaderCompiler);
+        assert_eq!(to_kind(gl::DEBUG_TYPE_PERFORMANCE), Kind::Performance);
+        assert_eq!(to_severity(gl::DEBUG_SEVERITY_NOTIFICATION), Severity::Notification);
+
+        assert!(Severity::High > Severity::Medium);
+        assert!(Severity::Low > Severity::Notification);
+    }
+
+    #[test]
+    fn debug_check_error() {
+        // The rendering lock enables the panic on error option.
+        let _lock = lock_rendering();
+        check_error("file.rs", 1, "call", gl::NO_ERROR);
+        assert!(std::panic::catch_unwind(|| check_error("file.rs", 1, "call", gl::INVALID_ENUM)).is_err());
+
+        // Without the panic on error option, the errors are only logged.
+        set_panic_on_error(false);
+        check_error("file.rs", 1, "call", gl::NO_ERROR);
+        check_error("file.rs", 2, "call", gl::INVALID_ENUM);
+
+        PENDING_ERROR.with(|pending| *pending.borrow_mut() = Some(String::from("recorded")));
+        check_error("file.rs", 3, "call", gl::INVALID_VALUE);
+        assert!(PENDING_ERROR.with(|pending| pending.borrow().is_none()));
+
+        let message = error_message("file.rs", 4, "call", gl::INVALID_OPERATION);
+        assert_eq!(message.text, "file.rs:4 - call caused GL_INVALID_OPERATION");
+    }
+}
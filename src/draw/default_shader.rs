@@ -5,10 +5,12 @@
 //
 // Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, May 2020
 
+use std::ptr::{addr_of, addr_of_mut};
 use crate::draw::context::{get_or_create_context, make_context_current};
 use crate::draw::shader::Shader;
 
 static mut DEFAULT_SHADER: Option<Shader> = None;
+static mut INSTANCED_SHADER: Option<Shader> = None;
 
 const VERTEX_SHADER_SRC: &'static [u8] = b"
 #version 300 es
@@ -33,6 +35,39 @@ void main() {
 }
 \0";
 
+// The instanced variant of the vertex shader; the vertices are transformed by the matrix of the
+// instance first (its rows are passed as two attributes), and their color and texture coordinates
+// are adjusted by the instance.
+const INSTANCED_VERTEX_SHADER_SRC: &'static [u8] = b"
+#version 300 es
+precision mediump float;
+
+layout(location = 0) in vec2 vertex_position;
+layout(location = 1) in vec4 vertex_color;
+layout(location = 2) in vec2 vertex_texture;
+
+layout(location = 3) in vec3 instance_matrix_x;
+layout(location = 4) in vec3 instance_matrix_y;
+layout(location = 5) in vec4 instance_color;
+layout(location = 6) in vec4 instance_texture_rect;
+
+out vec4 fragment_color;
+out vec2 fragment_texture;
+
+uniform mat3 model;
+uniform mat4 viewport;
+
+void main() {
+    vec3 position = vec3(vertex_position, 1.0);
+    vec2 instance_position = vec2(dot(instance_matrix_x, position), dot(instance_matrix_y, position));
+
+    gl_Position = viewport * vec4((model * vec3(instance_position, 1.0)), 1.0);
+
+    fragment_color = vertex_color * instance_color;
+    fragment_texture = instance_texture_rect.xy + vertex_texture * instance_texture_rect.zw;
+}
+\0";
+
 const FRAGMENT_SHADER_SRC: &'static [u8] = b"
 #version 300 es
 precision mediump float;
@@ -51,14 +86,15 @@ void main() {
 fn ensure_default_shader() {
     unsafe {
         // todo: there must be a more elegant way to 'initialize only if the content is None
-        match &DEFAULT_SHADER {
+        match &*addr_of!(DEFAULT_SHADER) {
             Some(_) => (),
             None => {
                 // Make sure context is created
                 let _context = get_or_create_context();
                 make_context_current();
 
-                let default_shader = Shader::new(VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC);
+                let default_shader = Shader::new(VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC)
+                    .expect("failed to create the default shader");
                 DEFAULT_SHADER = Some(default_shader);
             }
         }
@@ -73,6 +109,25 @@ fn ensure_default_shader() {
 pub(crate) fn get_or_create_default_shader() -> &'static mut Shader {
     ensure_default_shader();
     unsafe {
-        DEFAULT_SHADER.as_mut().unwrap()
+        (*addr_of_mut!(DEFAULT_SHADER)).as_mut().unwrap()
+    }
+}
+
+/// Get or create the instanced variant of the default OpenGL shader program.
+///
+/// This function returns the shader program used instead of the default one to draw the vertex
+/// arrays which have instances (see `Instance`); it shares the fragment shader of the default one.
+///
+pub(crate) fn get_or_create_instanced_shader() -> &'static mut Shader {
+    unsafe {
+        if (*addr_of!(INSTANCED_SHADER)).is_none() {
+            let _context = get_or_create_context();
+            make_context_current();
+
+            INSTANCED_SHADER = Some(Shader::new(INSTANCED_VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC)
+                .expect("failed to create the instanced shader"));
+        }
+
+        (*addr_of_mut!(INSTANCED_SHADER)).as_mut().unwrap()
     }
 }
@@ -0,0 +1,123 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+use std::ptr::{addr_of, addr_of_mut};
+use crate::draw::context::{get_or_create_context, make_context_current};
+use crate::draw::shader::Shader;
+
+static mut DISTANCE_FIELD_SHADER: Option<Shader> = None;
+
+const VERTEX_SHADER_SRC: &'static [u8] = b"
+#version 300 es
+precision mediump float;
+
+layout(location = 0) in vec2 vertex_position;
+layout(location = 1) in vec4 vertex_color;
+layout(location = 2) in vec2 vertex_texture;
+
+out vec4 fragment_color;
+out vec2 fragment_texture;
+
+uniform mat3 model;
+uniform mat4 viewport;
+
+void main() {
+
+    gl_Position = viewport * vec4((model * vec3(vertex_position, 1.0)), 1.0);
+
+    fragment_color = vertex_color;
+    fragment_texture = vertex_texture;
+}
+\0";
+
+// The distances are stored in the alpha channel of the texture; the edge of the glyphs is at 0.5
+// and the distance field is saturated at the spread. The thickness of the effects is therefore
+// expressed in the same unit (a thickness of 0.5 is the spread).
+const FRAGMENT_SHADER_SRC: &'static [u8] = b"
+#version 300 es
+precision mediump float;
+
+in vec4 fragment_color;
+in vec2 fragment_texture;
+out vec4 out_color;
+
+uniform sampler2D current_texture;
+
+uniform vec4 outline_color;
+uniform float outline_thickness;
+
+uniform vec4 glow_color;
+uniform float glow_radius;
+
+uniform vec4 shadow_color;
+uniform vec2 shadow_offset;
+
+vec4 blend(vec4 top, vec4 bottom) {
+    float alpha = top.a + bottom.a * (1.0 - top.a);
+    vec3 color = (top.rgb * top.a + bottom.rgb * bottom.a * (1.0 - top.a)) / max(alpha, 0.0001);
+
+    return vec4(color, alpha);
+}
+
+void main() {
+    float distance = texture(current_texture, fragment_texture).a;
+    float smoothing = max(fwidth(distance) * 0.7, 0.0001);
+
+    vec4 color = vec4(0.0);
+
+    if (shadow_color.a > 0.0) {
+        float shadow_distance = texture(current_texture, fragment_texture - shadow_offset).a;
+        float alpha = smoothstep(0.5 - smoothing, 0.5 + smoothing, shadow_distance);
+        color = blend(vec4(shadow_color.rgb, shadow_color.a * alpha), color);
+    }
+
+    if (glow_radius > 0.0) {
+        float alpha = smoothstep(0.5 - glow_radius, 0.5, distance);
+        color = blend(vec4(glow_color.rgb, glow_color.a * alpha), color);
+    }
+
+    if (outline_thickness > 0.0) {
+        float edge = 0.5 - outline_thickness;
+        float alpha = smoothstep(edge - smoothing, edge + smoothing, distance);
+        color = blend(vec4(outline_color.rgb, outline_color.a * alpha), color);
+    }
+
+    float alpha = smoothstep(0.5 - smoothing, 0.5 + smoothing, distance);
+    out_color = blend(vec4(fragment_color.rgb, fragment_color.a * alpha), color);
+}
+\0";
+
+fn ensure_distance_field_shader() {
+    unsafe {
+        match &*addr_of!(DISTANCE_FIELD_SHADER) {
+            Some(_) => (),
+            None => {
+                // Make sure context is created
+                let _context = get_or_create_context();
+                make_context_current();
+
+                let shader = Shader::new(VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC)
+                    .expect("failed to create the distance field shader");
+                DISTANCE_FIELD_SHADER = Some(shader);
+            }
+        }
+    }
+}
+
+/// Get or create the distance field OpenGL shader program.
+///
+/// This function returns the shader program used to draw the texts whose font is in the distance
+/// field rendering mode. It reconstructs the edges of the glyphs from the distance field and
+/// computes the outline, the glow and the shadow; they're controlled with the `outline_color`,
+/// `outline_thickness`, `glow_color`, `glow_radius`, `shadow_color` and `shadow_offset` uniforms.
+///
+pub(crate) fn get_or_create_distance_field_shader() -> &'static mut Shader {
+    ensure_distance_field_shader();
+    unsafe {
+        (*addr_of_mut!(DISTANCE_FIELD_SHADER)).as_mut().unwrap()
+    }
+}
@@ -5,12 +5,15 @@
 //
 // Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
 
-use crate::draw::Surface;
+use crate::draw::{Surface, RenderStates};
 
 /// Brief description.
 ///
 /// The **Drawable struct** is not documented yet. Pull requests are welcome.
 ///
+/// The render states passed to `draw()` are combined with the ones of the drawable; typically, the
+/// transformation is combined with its own matrix and the texture is replaced by its own.
+///
 pub trait Drawable {
-    fn draw(&self, surface: &mut Surface);
+    fn draw(&self, surface: &mut Surface, states: &RenderStates);
 }
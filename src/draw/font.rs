@@ -5,8 +5,1084 @@
 //
 // Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, January 2020
 
+use std::io;
+use std::io::Read;
+use std::fs::File;
+use std::path::Path;
+use std::cell::{RefCell, Ref};
+use std::collections::HashMap;
+use rusttype;
+use owned_ttf_parser::AsFaceRef;
+use crate::geometry::{Position, Size, Box};
+use crate::image::{Color, Image};
+use crate::draw::{Glyph, Texture, Rendering};
+use crate::draw::debug::{self, Message, Source, Kind, Severity};
+
+// The initial size of the texture of a page; it grows as glyphs are added to it.
+const PAGE_INITIAL_SIZE: usize = 128;
+
+// The maximum size of the texture of a page (it's the minimum texture size that OpenGL ES 3.2
+// guarantees).
+const PAGE_MAXIMUM_SIZE: usize = 2048;
+
+// The number of transparent pixels around each glyph to prevent bleeding when glyphs are sampled.
+const GLYPH_PADDING: i32 = 1;
+
+// The size of the block of white pixels reserved in the top-left corner of each page; it's used to
+// draw the decorations of texts (underlines and strikethroughs) with the texture of the font.
+const WHITE_BLOCK_SIZE: i32 = 4;
+
+// The factor by which glyphs are magnified before their distance field is computed.
+const DISTANCE_FIELD_SUPERSAMPLING: i32 = 4;
+
+/// A glyph positioned by the shaper.
+///
+/// The cluster is the byte offset (in the shaped string) of the first character the glyph belongs
+/// to. The offset is relative to the pen position on the baseline.
+///
+pub(crate) struct ShapedGlyph {
+    pub index: u16,
+    pub cluster: usize,
+    pub advance: f32,
+    pub offset: Position<f32>
+}
+
+/// A row of glyphs in a page.
+struct Row {
+    top: i32,
+    width: i32,
+    height: i32
+}
+
+/// The glyphs of a font rasterized at a given character size.
+///
+/// A page holds the glyphs that were rasterized so far, and the image in which they're packed. The
+/// texture is created (or updated) lazily from the image, only when it's requested.
+///
+struct Page {
+    glyphs: HashMap<(u16, u32), Glyph>, // indexed by glyph identifier and outline thickness
+    image: Image,
+    rows: Vec<Row>,
+    next_row: i32,
+    texture: Option<Texture>,
+    outdated: bool
+}
+
+impl Page {
+    fn new() -> Page {
+        let mut page = Page {
+            glyphs: HashMap::new(),
+            image: Image::with_size(Size::new(PAGE_INITIAL_SIZE, PAGE_INITIAL_SIZE), Color::TRANSPARENT),
+            rows: Vec::new(),
+            next_row: 0,
+            texture: None,
+            outdated: true
+        };
+
+        // Reserve the block of white pixels (it's always the first area of the page and therefore
+        // located in the top-left corner).
+        let area = page.find_area(Size::new(WHITE_BLOCK_SIZE, WHITE_BLOCK_SIZE)).unwrap();
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                *page.image.pixel_mut(Position::new(x as isize, y as isize)).unwrap() = Color::WHITE;
+            }
+        }
+
+        page
+    }
+
+    fn find_area(&mut self, size: Size<i32>) -> Option<Box<i32>> {
+        // The packing algorithm is a simple 'shelf' algorithm; glyphs are put in rows of similar
+        // height, and a new row is created when no existing row fits the glyph. It's not optimal,
+        // but glyphs have similar height so it does a decent job. There's no area if the image
+        // can't grow anymore.
+        loop {
+            let image_size = self.image.size();
+            let image_width = image_size.width as i32;
+            let image_height = image_size.height as i32;
+
+            let mut best_row: Option<usize> = None;
+            let mut best_ratio = 0.0;
+
+            for (index, row) in self.rows.iter().enumerate() {
+                let ratio = size.height as f32 / row.height as f32;
+
+                // Ignore rows that are either too small or too high.
+                if !(0.7..=1.0).contains(&ratio) {
+                    continue
+                }
+
+                // Ignore rows that don't have enough horizontal space left.
+                if size.width > image_width - row.width {
+                    continue
+                }
+
+                if ratio > best_ratio {
+                    best_ratio = ratio;
+                    best_row = Some(index);
+                }
+            }
+
+            // Create a new row if no existing row fits the glyph (with a little bit of extra space
+            // for glyphs that are slightly taller).
+            if best_row.is_none() {
+                let row_height = size.height + size.height / 10;
+
+                if self.next_row + row_height <= image_height && size.width <= image_width {
+                    self.rows.push(Row {
+                        top: self.next_row,
+                        width: 0,
+                        height: row_height
+                    });
+                    self.next_row += row_height;
+
+                    best_row = Some(self.rows.len() - 1);
+                }
+            }
+
+            match best_row {
+                Some(index) => {
+                    let row = &mut self.rows[index];
+                    let area = Box::new(Position::new(row.width, row.top), size);
+                    row.width += size.width;
+
+                    return Some(area)
+                },
+                None => {
+                    // There is no space left; make the image bigger and try again.
+                    if !self.grow() {
+                        return None
+                    }
+                }
+            }
+        }
+    }
+
+    fn grow(&mut self) -> bool {
+        let size = self.image.size();
+        if size.width * 2 > PAGE_MAXIMUM_SIZE {
+            return false
+        }
+
+        // Copy the existing glyphs in the top-left corner of the new image; the texture rectangles
+        // of the glyphs remain valid.
+        let mut image = Image::with_size(Size::new(size.width * 2, size.height * 2), Color::TRANSPARENT);
+        for y in 0..size.height as isize {
+            for x in 0..size.width as isize {
+                let position = Position::new(x, y);
+                *image.pixel_mut(position).unwrap() = *self.image.pixel(position).unwrap();
+            }
+        }
+
+        self.image = image;
+        self.outdated = true;
+
+        true
+    }
+
+    fn update_texture(&mut self, rendering: Rendering) {
+        if !self.outdated {
+            return
+        }
+
+        match self.texture {
+            Some(ref mut texture) => texture.update_image(&self.image),
+            None => {
+                // Distance fields must be interpolated.
+                let mut texture = Texture::with_image(&self.image);
+                texture.set_smooth(rendering == Rendering::DistanceField);
+
+                self.texture = Some(texture);
+            }
+        }
+
+        self.outdated = false;
+    }
+}
+
 /// A texture and a set of glyphs to draw text.
 ///
-/// The **Font struct** is not documented yet. Pull requests are welcome.
+/// A font is loaded from a TrueType or OpenType file with `open()`, or from any reader with
+/// `load()`. The glyphs are rasterized on demand, at the requested character size (in pixels), and
+/// packed into a texture which grows as more glyphs are requested. Each character size has its own
+/// texture, therefore, you should avoid using too many different character sizes.
+///
+/// ```no_run
+/// # use byteplug::draw::Font;
+/// let font = Font::open("resources/DejaVuSans.ttf").unwrap();
 ///
-pub struct Font {}
+/// let glyph = font.glyph('A', 24);
+/// let texture = font.texture(24);
+/// ```
+///
+/// The `glyph()` method returns the metrics of a character (advance, bearing and bounds) and its
+/// location in the texture. The `kerning()` method returns the additional offset to apply between
+/// two characters and `line_spacing()` returns the vertical offset between two lines of text.
+///
+/// **Implementation notes**
+///
+/// - The font files are parsed and the glyphs are rasterized with the `rusttype` crate; the text
+///   shaping is done with the `rustybuzz` crate (a port of HarfBuzz).
+/// - The glyphs are rasterized lazily, therefore the methods take a non-mutable reference and the
+///   pages are stored in a `RefCell`. It allows texts to share the same font.
+/// - Glyphs are white, and the coverage is stored in the alpha channel; they're colored with the
+///   color of the vertices.
+/// - The texture is only created (or updated) when it's requested with `texture()`. It allows to
+///   use fonts without an OpenGL context (to compute the layout of a text for instance).
+/// - The texture grows up to 2048x2048 pixels; the glyphs which don't fit anymore are left blank
+///   (they only have an advance) and a message is reported (see the `debug` module).
+/// - The character size is the size of the 'em square' in pixels, just like in most text editors.
+/// - Bold and italic styles aren't synthesized; use the bold or italic font files instead.
+/// - Outline glyphs are obtained by dilating the coverage of the regular glyphs. It's not as
+///   accurate as stroking the outline of the glyphs, but it's good enough for common thicknesses.
+/// - The underline and strikethrough metrics are read from the font tables (with a fallback if the
+///   font doesn't define them).
+/// - In the distance field mode, the glyphs are rasterized at a bigger scale and the distance
+///   field is computed with an exact euclidean distance transform, then sampled down. The glyphs
+///   are bigger than their bitmap counterpart by the spread of the distance field (which is
+///   returned by `distance_field_spread()`) on each side.
+///
+pub struct Font {
+    font: rusttype::Font<'static>,
+    rendering: Rendering,
+    pages: RefCell<HashMap<u32, Page>>
+}
+
+impl Font {
+    /// Loads a font from a file.
+    ///
+    /// This function loads a TrueType or OpenType font file. It fails if the file can't be read or
+    /// if it's not a valid font file.
+    ///
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Font> {
+        let mut reader = File::open(path)?;
+        Font::load(&mut reader)
+    }
+
+    /// Loads a font from a reader.
+    ///
+    /// This function reads all the bytes of the reader and parses them as a TrueType or OpenType
+    /// font. It fails if the font can't be parsed.
+    ///
+    pub fn load<R: Read>(reader: &mut R) -> io::Result<Font> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+
+        let font = rusttype::Font::try_from_vec(bytes)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid font data"))?;
+
+        Ok(Font {
+            font: font,
+            rendering: Rendering::Bitmap,
+            pages: RefCell::new(HashMap::new())
+        })
+    }
+
+    /// Returns the rendering mode of the font.
+    ///
+    /// This function returns how the glyphs are stored in the textures of the font.
+    ///
+    pub fn rendering(&self) -> Rendering {
+        self.rendering
+    }
+
+    /// Changes the rendering mode of the font.
+    ///
+    /// This function changes how the glyphs are stored in the textures of the font. The default
+    /// rendering mode is bitmap. Changing it discards all the glyphs that were rasterized; texts
+    /// using the font must be updated.
+    ///
+    pub fn set_rendering(&mut self, rendering: Rendering) {
+        if self.rendering != rendering {
+            self.rendering = rendering;
+            self.pages.borrow_mut().clear();
+        }
+    }
+
+    /// Returns the spread of the distance field.
+    ///
+    /// This function returns the distance (in pixels) from the edge of the glyphs at which the
+    /// distance field is saturated, for a given character size. It's the maximum thickness of the
+    /// effects (outline, glow and shadow) drawn by the distance field shader.
+    ///
+    pub fn distance_field_spread(&self, size: u32) -> f32 {
+        (size / 8).max(4) as f32
+    }
+
+    /// Returns a glyph of the font.
+    ///
+    /// This function returns the glyph of a character at a given character size. If the glyph
+    /// wasn't requested before, it's rasterized and added to the texture of the character size.
+    /// Characters that are not in the font are mapped to the 'missing glyph'.
+    ///
+    pub fn glyph(&self, character: char, size: u32) -> Glyph {
+        self.glyph_by_identifier(self.glyph_index(character), size, 0.0)
+    }
+
+    /// Returns the index of the glyph of a character.
+    ///
+    /// This function returns the index of the glyph which represents a character in the font (it's
+    /// zero, the 'missing glyph', if the font doesn't have the character).
+    ///
+    pub fn glyph_index(&self, character: char) -> u16 {
+        self.font.glyph(character).id().0
+    }
+
+    /// Returns a glyph of the font from its index.
+    ///
+    /// This function is the same as `glyph()` except that the glyph is identified by its index in
+    /// the font. It's useful when the glyphs are the result of text shaping; a glyph doesn't
+    /// always correspond to a single character (for instance, with ligatures).
+    ///
+    pub fn glyph_by_index(&self, index: u16, size: u32) -> Glyph {
+        self.glyph_by_identifier(index, size, 0.0)
+    }
+
+    /// Returns an outlined glyph of the font from its index.
+    ///
+    /// This function is the same as `outline_glyph()` except that the glyph is identified by its
+    /// index in the font.
+    ///
+    pub fn outline_glyph_by_index(&self, index: u16, size: u32, thickness: f32) -> Glyph {
+        self.glyph_by_identifier(index, size, thickness)
+    }
+
+    /// Returns an outlined glyph of the font.
+    ///
+    /// This function returns the outline of the glyph of a character, at a given character size
+    /// and outline thickness (in pixels). The outline is meant to be drawn below the regular glyph;
+    /// its bounds are bigger than the bounds of the regular glyph by the outline thickness.
+    ///
+    /// In the distance field mode, the outline is computed by the shader and this function returns
+    /// the regular glyph.
+    ///
+    pub fn outline_glyph(&self, character: char, size: u32, thickness: f32) -> Glyph {
+        self.glyph_by_identifier(self.glyph_index(character), size, thickness)
+    }
+
+    /// Returns the kerning offset between two characters.
+    ///
+    /// This function returns the additional horizontal offset to apply between two consecutive
+    /// characters (on top of the advance of the first character). It's usually negative.
+    ///
+    pub fn kerning(&self, first: char, second: char, size: u32) -> f32 {
+        self.font.pair_kerning(self.scale(size), first, second)
+    }
+
+    /// Returns the ascent of the font.
+    ///
+    /// This function returns the distance from the baseline to the highest point of the font.
+    ///
+    pub fn ascent(&self, size: u32) -> f32 {
+        self.font.v_metrics(self.scale(size)).ascent
+    }
+
+    /// Returns the descent of the font.
+    ///
+    /// This function returns the distance from the baseline to the lowest point of the font. It's
+    /// a positive value.
+    ///
+    pub fn descent(&self, size: u32) -> f32 {
+        -self.font.v_metrics(self.scale(size)).descent
+    }
+
+    /// Returns the line spacing of the font.
+    ///
+    /// This function returns the vertical offset between two consecutive lines of text.
+    ///
+    pub fn line_spacing(&self, size: u32) -> f32 {
+        let metrics = self.font.v_metrics(self.scale(size));
+        metrics.ascent - metrics.descent + metrics.line_gap
+    }
+
+    /// Returns the position of the underline.
+    ///
+    /// This function returns the vertical offset from the baseline to the center of the underline
+    /// (it's positive because the underline is below the baseline).
+    ///
+    pub fn underline_position(&self, size: u32) -> f32 {
+        // The position in the font tables is the top of the line.
+        match self.face().underline_metrics() {
+            Some(metrics) => -self.units_to_pixels(metrics.position, size) + self.units_to_pixels(metrics.thickness, size) / 2.0,
+            None => size as f32 / 10.0
+        }
+    }
+
+    /// Returns the thickness of the underline.
+    ///
+    /// This function returns the thickness of the underline; it's never less than 1 pixel.
+    ///
+    pub fn underline_thickness(&self, size: u32) -> f32 {
+        match self.face().underline_metrics() {
+            Some(metrics) => self.units_to_pixels(metrics.thickness, size).max(1.0),
+            None => (size as f32 / 14.0).max(1.0)
+        }
+    }
+
+    /// Returns the position of the strikethrough.
+    ///
+    /// This function returns the vertical offset from the baseline to the center of the
+    /// strikethrough (it's negative because the strikethrough is above the baseline).
+    ///
+    pub fn strikethrough_position(&self, size: u32) -> f32 {
+        // The position in the font tables is the top of the line.
+        match self.face().strikeout_metrics() {
+            Some(metrics) => -self.units_to_pixels(metrics.position, size) + self.units_to_pixels(metrics.thickness, size) / 2.0,
+            None => -self.ascent(size) / 3.0
+        }
+    }
+
+    /// Returns the thickness of the strikethrough.
+    ///
+    /// This function returns the thickness of the strikethrough; it's never less than 1 pixel.
+    ///
+    pub fn strikethrough_thickness(&self, size: u32) -> f32 {
+        match self.face().strikeout_metrics() {
+            Some(metrics) => self.units_to_pixels(metrics.thickness, size).max(1.0),
+            None => self.underline_thickness(size)
+        }
+    }
+
+    /// Returns the image of a character size.
+    ///
+    /// This function returns a copy of the image in which the glyphs of a character size are
+    /// packed. It's mostly useful for debugging purpose.
+    ///
+    pub fn image(&self, size: u32) -> Image {
+        let mut pages = self.pages.borrow_mut();
+        let page = pages.entry(size).or_insert_with(Page::new);
+
+        page.image.clone()
+    }
+
+    /// Returns the texture of a character size.
+    ///
+    /// This function returns the texture in which the glyphs of a character size are packed. It's
+    /// updated with the glyphs that were rasterized since the last call. Note that the texture
+    /// grows as glyphs are added and its size may change.
+    ///
+    pub fn texture(&self, size: u32) -> Ref<'_, Texture> {
+        {
+            let mut pages = self.pages.borrow_mut();
+            let page = pages.entry(size).or_insert_with(Page::new);
+
+            page.update_texture(self.rendering);
+        }
+
+        Ref::map(self.pages.borrow(), |pages| pages[&size].texture.as_ref().unwrap())
+    }
+
+    /// Returns the size of the texture of a character size.
+    ///
+    /// This function returns the size of the texture in which the glyphs of a character size are
+    /// packed (without updating the texture). It's needed to compute the texture coordinates of
+    /// the glyphs.
+    ///
+    pub fn texture_size(&self, size: u32) -> Size<i32> {
+        let mut pages = self.pages.borrow_mut();
+        let page = pages.entry(size).or_insert_with(Page::new);
+
+        let image_size = page.image.size();
+        Size::new(image_size.width as i32, image_size.height as i32)
+    }
+
+    /// Returns the area of white pixels of a character size.
+    ///
+    /// This function returns an area of the texture that only contains opaque white pixels. It's
+    /// used to draw solid quads (such as underlines) with the texture of the font.
+    ///
+    pub(crate) fn white_rect(&self, size: u32) -> Box<i32> {
+        let mut pages = self.pages.borrow_mut();
+        pages.entry(size).or_insert_with(Page::new);
+
+        // The inner part of the white block is used so smooth textures don't sample the pixels
+        // surrounding the block.
+        Box::new(Position::new(1, 1), Size::new(WHITE_BLOCK_SIZE - 2, WHITE_BLOCK_SIZE - 2))
+    }
+
+    /// Shapes a string.
+    ///
+    /// This function converts a string into positioned glyphs, according to the rules of its
+    /// script (ligatures, contextual forms, mark positioning, etc.) and the OpenType features of
+    /// the font. The string must be a run of a single direction; the glyphs are returned in visual
+    /// order (from left to right).
+    ///
+    pub(crate) fn shape(&self, string: &str, size: u32, right_to_left: bool) -> Vec<ShapedGlyph> {
+        let face = match rustybuzz::Face::from_face(self.face().clone()) {
+            Some(face) => face,
+            None => return Vec::new()
+        };
+
+        let mut buffer = rustybuzz::UnicodeBuffer::new();
+        buffer.push_str(string);
+        buffer.set_direction(if right_to_left {
+            rustybuzz::Direction::RightToLeft
+        } else {
+            rustybuzz::Direction::LeftToRight
+        });
+
+        let glyphs = rustybuzz::shape(&face, &[], buffer);
+
+        // The positions are in font units; the Y axis of the offsets points upwards.
+        let scale = size as f32 / self.font.units_per_em() as f32;
+
+        glyphs.glyph_infos().iter().zip(glyphs.glyph_positions().iter())
+            .map(|(info, position)| ShapedGlyph {
+                index: info.glyph_id as u16,
+                cluster: info.cluster as usize,
+                advance: position.x_advance as f32 * scale,
+                offset: Position::new(position.x_offset as f32 * scale, -position.y_offset as f32 * scale)
+            })
+            .collect()
+    }
+
+    fn glyph_by_identifier(&self, id: u16, size: u32, outline_thickness: f32) -> Glyph {
+        let outline_thickness = match self.rendering {
+            Rendering::Bitmap => outline_thickness,
+            Rendering::DistanceField => 0.0
+        };
+
+        let key = (id, outline_thickness.to_bits());
+
+        let mut pages = self.pages.borrow_mut();
+        let page = pages.entry(size).or_insert_with(Page::new);
+
+        if let Some(glyph) = page.glyphs.get(&key) {
+            return *glyph
+        }
+
+        let glyph = self.rasterize_glyph(page, id, size, outline_thickness);
+        page.glyphs.insert(key, glyph);
+
+        glyph
+    }
+
+    fn face(&self) -> &owned_ttf_parser::Face<'_> {
+        match self.font {
+            rusttype::Font::Ref(ref face) => face,
+            rusttype::Font::Owned(ref face) => face.as_face_ref()
+        }
+    }
+
+    fn units_to_pixels(&self, units: i16, size: u32) -> f32 {
+        units as f32 * size as f32 / self.font.units_per_em() as f32
+    }
+
+    fn scale(&self, size: u32) -> rusttype::Scale {
+        // The character size is the size of the 'em square' whereas rusttype expects the distance
+        // from the ascent to the descent.
+        let metrics = self.font.v_metrics_unscaled();
+        let units_per_em = self.font.units_per_em() as f32;
+
+        rusttype::Scale::uniform(size as f32 * (metrics.ascent - metrics.descent) / units_per_em)
+    }
+
+    fn rasterize_glyph(&self, page: &mut Page, id: u16, size: u32, outline_thickness: f32) -> Glyph {
+        let glyph = self.font.glyph(rusttype::GlyphId(id))
+            .scaled(self.scale(size))
+            .positioned(rusttype::point(0.0, 0.0));
+
+        let mut result = Glyph {
+            advance: glyph.unpositioned().h_metrics().advance_width,
+            ..Glyph::default()
+        };
+
+        // Glyphs with no outline (such as the space character) have no bounds and aren't added to
+        // the texture.
+        let bounds = match glyph.pixel_bounding_box() {
+            Some(bounds) => bounds,
+            None => return result
+        };
+
+        // Draw the glyph in a temporary bitmap; it's either its coverage (dilated by the outline
+        // thickness if it's an outline glyph) or its distance field.
+        let (coverages, bounds) = match self.rendering {
+            Rendering::Bitmap => rasterize_coverage(&glyph, bounds, outline_thickness),
+            Rendering::DistanceField => {
+                let spread = self.distance_field_spread(size);
+                rasterize_distance_field(&self.font, id, self.scale(size), spread)
+            }
+        };
+
+        let width = bounds.size.width as usize;
+        let height = bounds.size.height as usize;
+
+        // Find a free area in the page, including the padding on each side; if the page is full,
+        // the glyph is left blank (like the space character).
+        let area = match page.find_area(Size::new(width as i32 + GLYPH_PADDING * 2, height as i32 + GLYPH_PADDING * 2)) {
+            Some(area) => area,
+            None => {
+                debug::report(Message {
+                    source: Source::Other,
+                    kind: Kind::Other,
+                    severity: Severity::Medium,
+                    id: 0,
+                    text: format!("the texture of the font is full at size {}; glyph {} is left blank", size, id)
+                });
+
+                return result
+            }
+        };
+
+        result.bounds = Box::new(
+            Position::new(bounds.position.x as f32, bounds.position.y as f32),
+            Size::new(width as f32, height as f32)
+        );
+        result.texture_rect = area;
+
+        // Copy the glyph in the image of the page; it's white, and its coverage goes to the alpha
+        // channel.
+        let left = (area.position.x + GLYPH_PADDING) as isize;
+        let top = (area.position.y + GLYPH_PADDING) as isize;
+
+        for y in 0..height {
+            for x in 0..width {
+                let position = Position::new(left + x as isize, top + y as isize);
+                let alpha = (coverages[y * width + x] * 255.0).round().min(255.0) as u8;
+
+                *page.image.pixel_mut(position).unwrap() = Color::rgba(255, 255, 255, alpha);
+            }
+        }
+
+        page.outdated = true;
+
+        result
+    }
+}
+
+fn rasterize_coverage(glyph: &rusttype::PositionedGlyph, bounds: rusttype::Rect<i32>, outline_thickness: f32) -> (Vec<f32>, Box<i32>) {
+    let width = bounds.width() as usize;
+    let height = bounds.height() as usize;
+
+    let mut coverages = vec![0.0; width * height];
+    glyph.draw(|x, y, coverage| {
+        coverages[y as usize * width + x as usize] = coverage;
+    });
+
+    let extent = outline_thickness.ceil().max(0.0) as i32;
+    if extent > 0 {
+        coverages = dilate(&coverages, Size::new(width, height), outline_thickness, extent as usize);
+    }
+
+    let bounds = Box::new(
+        Position::new(bounds.min.x - extent, bounds.min.y - extent),
+        Size::new(width as i32 + extent * 2, height as i32 + extent * 2)
+    );
+
+    (coverages, bounds)
+}
+
+fn rasterize_distance_field(font: &rusttype::Font, id: u16, scale: rusttype::Scale, spread: f32) -> (Vec<f32>, Box<i32>) {
+    // The glyph is rasterized at a bigger scale, in a grid that covers the final bitmap (which is
+    // bigger than the glyph by the spread on each side).
+    let factor = DISTANCE_FIELD_SUPERSAMPLING;
+
+    let glyph = font.glyph(rusttype::GlyphId(id))
+        .scaled(rusttype::Scale { x: scale.x * factor as f32, y: scale.y * factor as f32 })
+        .positioned(rusttype::point(0.0, 0.0));
+
+    // It's only called for glyphs that have bounds at the regular scale.
+    let magnified_bounds = glyph.pixel_bounding_box().unwrap_or_default();
+
+    let extent = spread.ceil() as i32;
+    let left = magnified_bounds.min.x.div_euclid(factor) - extent;
+    let top = magnified_bounds.min.y.div_euclid(factor) - extent;
+    let right = (magnified_bounds.max.x + factor - 1).div_euclid(factor) + extent;
+    let bottom = (magnified_bounds.max.y + factor - 1).div_euclid(factor) + extent;
+
+    let width = (right - left) as usize;
+    let height = (bottom - top) as usize;
+
+    let grid_width = width * factor as usize;
+    let grid_height = height * factor as usize;
+
+    let offset_x = (magnified_bounds.min.x - left * factor) as usize;
+    let offset_y = (magnified_bounds.min.y - top * factor) as usize;
+
+    let mut inside = vec![false; grid_width * grid_height];
+    glyph.draw(|x, y, coverage| {
+        if coverage >= 0.5 {
+            inside[(y as usize + offset_y) * grid_width + x as usize + offset_x] = true;
+        }
+    });
+
+    // Compute the distance of each cell to the nearest cell of the other kind.
+    let size = Size::new(grid_width, grid_height);
+    let to_inside = distance_transform(&inside.iter().map(|inside| !inside).collect::<Vec<bool>>(), size);
+    let to_outside = distance_transform(&inside, size);
+
+    // Sample the signed distance at the center of each pixel of the bitmap, and map it to [0, 1]
+    // (the edge is at 0.5).
+    let mut values = vec![0.0; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let index = (y * factor as usize + factor as usize / 2) * grid_width + x * factor as usize + factor as usize / 2;
+
+            let distance = if inside[index] {
+                to_outside[index].sqrt() - 0.5
+            } else {
+                -(to_inside[index].sqrt() - 0.5)
+            };
+
+            let distance = distance / factor as f32;
+            values[y * width + x] = (0.5 + distance / (spread * 2.0)).clamp(0.0, 1.0);
+        }
+    }
+
+    let bounds = Box::new(Position::new(left, top), Size::new(width as i32, height as i32));
+
+    (values, bounds)
+}
+
+fn distance_transform(cells: &[bool], size: Size<usize>) -> Vec<f32> {
+    // Compute the squared euclidean distance of each cell to the nearest cell which is not set
+    // (cells which are not set have a distance of zero); it's the algorithm of Felzenszwalb and
+    // Huttenlocher, applied on the columns and then on the rows.
+    const INFINITY: f32 = 1e20;
+
+    let mut distances: Vec<f32> = cells.iter()
+        .map(|cell| if *cell { INFINITY } else { 0.0 })
+        .collect();
+
+    let length = size.width.max(size.height);
+    let mut input = vec![0.0; length];
+    let mut output = vec![0.0; length];
+
+    for x in 0..size.width {
+        for y in 0..size.height {
+            input[y] = distances[y * size.width + x];
+        }
+
+        distance_transform_1d(&input[..size.height], &mut output[..size.height]);
+
+        for y in 0..size.height {
+            distances[y * size.width + x] = output[y];
+        }
+    }
+
+    for y in 0..size.height {
+        let row = &mut distances[y * size.width..(y + 1) * size.width];
+
+        input[..size.width].copy_from_slice(row);
+        distance_transform_1d(&input[..size.width], row);
+    }
+
+    distances
+}
+
+fn distance_transform_1d(input: &[f32], output: &mut [f32]) {
+    // Compute the lower envelope of the parabolas rooted at each sample.
+    let length = input.len();
+
+    let mut vertices = vec![0; length];
+    let mut boundaries = vec![0.0; length + 1];
+
+    let mut count = 0;
+    boundaries[0] = f32::MIN;
+    boundaries[1] = f32::MAX;
+
+    for index in 1..length {
+        loop {
+            let vertex = vertices[count];
+            let intersection = ((input[index] + (index * index) as f32) - (input[vertex] + (vertex * vertex) as f32))
+                / (2.0 * index as f32 - 2.0 * vertex as f32);
+
+            if intersection <= boundaries[count] {
+                count -= 1;
+                continue
+            }
+
+            count += 1;
+            vertices[count] = index;
+            boundaries[count] = intersection;
+            boundaries[count + 1] = f32::MAX;
+
+            break
+        }
+    }
+
+    let mut count = 0;
+    for (index, value) in output.iter_mut().enumerate() {
+        while boundaries[count + 1] < index as f32 {
+            count += 1;
+        }
+
+        let vertex = vertices[count];
+        let distance = index as f32 - vertex as f32;
+        *value = distance * distance + input[vertex];
+    }
+}
+
+fn dilate(coverages: &[f32], size: Size<usize>, thickness: f32, extent: usize) -> Vec<f32> {
+    // Dilate the coverage of a glyph by a given thickness; each pixel takes the maximum coverage of
+    // the pixels around it (within the thickness), attenuated at the border so the outline remains
+    // anti-aliased. The resulting bitmap is bigger by the extent on each side.
+    let width = size.width + extent * 2;
+    let height = size.height + extent * 2;
+
+    let mut result = vec![0.0; width * height];
+
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let mut value: f32 = 0.0;
+
+            for dy in -(extent as isize)..=(extent as isize) {
+                for dx in -(extent as isize)..=(extent as isize) {
+                    let source_x = x + dx - extent as isize;
+                    let source_y = y + dy - extent as isize;
+
+                    if source_x < 0 || source_y < 0 || source_x >= size.width as isize || source_y >= size.height as isize {
+                        continue
+                    }
+
+                    let distance = ((dx * dx + dy * dy) as f32).sqrt();
+                    let factor = (thickness + 0.5 - distance).clamp(0.0, 1.0);
+
+                    let coverage = coverages[source_y as usize * size.width + source_x as usize];
+                    value = value.max(coverage * factor);
+                }
+            }
+
+            result[y as usize * width + x as usize] = value;
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+    use super::*;
+
+    fn get_sample_font_path() -> OsString {
+        let mut filename = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        filename.push("resources/DejaVuSans.ttf");
+
+        filename.into_os_string()
+    }
+
+    #[test]
+    fn font_open() {
+        let font = Font::open(get_sample_font_path());
+        assert!(font.is_ok());
+
+        let font = Font::open("this/file/does/not/exist.ttf");
+        assert_eq!(font.err().unwrap().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn font_load() {
+        let mut file = File::open(get_sample_font_path()).unwrap();
+        let font = Font::load(&mut file);
+        assert!(font.is_ok());
+
+        // Loading something that is not a font must fail.
+        let mut bytes: &[u8] = b"not a font";
+        let font = Font::load(&mut bytes);
+        assert_eq!(font.err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn font_glyph() {
+        let font = Font::open(get_sample_font_path()).unwrap();
+
+        let glyph = font.glyph('A', 32);
+        assert!(glyph.advance > 0.0);
+        assert!(glyph.bounds.size.width > 0.0);
+        assert!(glyph.bounds.size.height > 0.0);
+
+        // The top of the glyph is above the baseline.
+        assert!(glyph.bearing().y < 0.0);
+
+        // The texture rectangle includes the padding.
+        assert_eq!(glyph.texture_rect.size.width, glyph.bounds.size.width as i32 + 2);
+        assert_eq!(glyph.texture_rect.size.height, glyph.bounds.size.height as i32 + 2);
+
+        // Requesting the same glyph again returns the cached one.
+        assert_eq!(font.glyph('A', 32), glyph);
+
+        // A bigger character size gives a bigger glyph.
+        let bigger_glyph = font.glyph('A', 64);
+        assert!(bigger_glyph.advance > glyph.advance);
+        assert!(bigger_glyph.bounds.size.height > glyph.bounds.size.height);
+
+        // The space character has an advance, but no bounds.
+        let space = font.glyph(' ', 32);
+        assert!(space.advance > 0.0);
+        assert_eq!(space.bounds, Box::default());
+        assert_eq!(space.texture_rect, Box::default());
+    }
+
+    #[test]
+    fn font_page_full() {
+        let mut page = Page::new();
+        assert!(page.find_area(Size::new(1000, 1000)).is_some());
+        assert!(page.find_area(Size::new(1000, 1000)).is_some());
+        assert!(page.find_area(Size::new(1000, 1000)).is_none());
+        assert_eq!(page.image.size(), Size::new(PAGE_MAXIMUM_SIZE, PAGE_MAXIMUM_SIZE));
+
+        // A glyph which doesn't fit in the texture is left blank.
+        let font = Font::open(get_sample_font_path()).unwrap();
+
+        let glyph = font.glyph('W', 2500);
+        assert!(glyph.advance > 0.0);
+        assert_eq!(glyph.bounds, Box::default());
+        assert_eq!(glyph.texture_rect, Box::default());
+    }
+
+    #[test]
+    fn font_outline_glyph() {
+        let font = Font::open(get_sample_font_path()).unwrap();
+
+        // The outline is bigger than the regular glyph by its thickness on each side, and it
+        // doesn't change the advance.
+        let glyph = font.glyph('o', 32);
+        let outline = font.outline_glyph('o', 32, 2.0);
+        assert_eq!(outline.advance, glyph.advance);
+        assert_eq!(outline.bounds.left(), glyph.bounds.left() - 2.0);
+        assert_eq!(outline.bounds.top(), glyph.bounds.top() - 2.0);
+        assert_eq!(outline.bounds.size.width, glyph.bounds.size.width + 4.0);
+        assert_eq!(outline.bounds.size.height, glyph.bounds.size.height + 4.0);
+
+        // Both are cached separately.
+        assert_ne!(outline.texture_rect, glyph.texture_rect);
+        assert_eq!(font.outline_glyph('o', 32, 2.0), outline);
+        assert_eq!(font.outline_glyph('o', 32, 0.0), glyph);
+    }
+
+    #[test]
+    fn font_distance_field() {
+        let mut font = Font::open(get_sample_font_path()).unwrap();
+        let bitmap_glyph = font.glyph('I', 32);
+
+        font.set_rendering(Rendering::DistanceField);
+        assert_eq!(font.rendering(), Rendering::DistanceField);
+
+        // The glyph is bigger by (at least) the spread on each side, and its metrics don't change.
+        let spread = font.distance_field_spread(32);
+        let glyph = font.glyph('I', 32);
+        assert_eq!(glyph.advance, bitmap_glyph.advance);
+        assert!(glyph.bounds.left() <= bitmap_glyph.bounds.left() - spread);
+        assert!(glyph.bounds.right() >= bitmap_glyph.bounds.right() + spread);
+
+        // The distance decreases away from the glyph, and it's about the middle on the edge.
+        let image = font.image(32);
+        let rect = glyph.texture_rect;
+        let alpha = |x: f32, y: f32| {
+            let position = Position::new(
+                (rect.left() as f32 + 1.0 + x - glyph.bounds.left()) as isize,
+                (rect.top() as f32 + 1.0 + y - glyph.bounds.top()) as isize
+            );
+            image.pixel(position).unwrap().alpha
+        };
+
+        let center_x = bitmap_glyph.bounds.left() + bitmap_glyph.bounds.size.width / 2.0;
+        let center_y = bitmap_glyph.bounds.top() + bitmap_glyph.bounds.size.height / 2.0;
+        assert!(alpha(center_x, center_y) > 128);
+        assert!(alpha(glyph.bounds.left(), center_y) < 32);
+
+        let edge = alpha(bitmap_glyph.bounds.left(), center_y);
+        assert!(edge > 64 && edge < 192);
+
+        // The outline is computed by the shader.
+        assert_eq!(font.outline_glyph('I', 32, 2.0), glyph);
+    }
+
+    #[test]
+    fn font_distance_transform() {
+        // The squared distances to the unset cell.
+        let cells = [true, true, false, true, true, true];
+        let distances = distance_transform(&cells, Size::new(6, 1));
+        assert_eq!(distances, vec![4.0, 1.0, 0.0, 1.0, 4.0, 9.0]);
+
+        let cells = [
+            true, true, true,
+            true, true, true,
+            true, true, false
+        ];
+        let distances = distance_transform(&cells, Size::new(3, 3));
+        assert_eq!(distances, vec![8.0, 5.0, 4.0, 5.0, 2.0, 1.0, 4.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn font_decorations() {
+        let font = Font::open(get_sample_font_path()).unwrap();
+
+        assert!(font.underline_position(32) > 0.0);
+        assert!(font.underline_thickness(32) >= 1.0);
+        assert!(font.strikethrough_position(32) < 0.0);
+        assert!(font.strikethrough_position(32) > -font.ascent(32));
+        assert!(font.strikethrough_thickness(32) >= 1.0);
+
+        // The white area is made of opaque white pixels.
+        let rect = font.white_rect(32);
+        let image = font.image(32);
+        for y in rect.top() - 1..rect.bottom() + 1 {
+            for x in rect.left() - 1..rect.right() + 1 {
+                assert_eq!(*image.pixel(Position::new(x as isize, y as isize)).unwrap(), Color::WHITE);
+            }
+        }
+    }
+
+    #[test]
+    fn font_kerning() {
+        let font = Font::open(get_sample_font_path()).unwrap();
+
+        assert!(font.kerning('T', 'o', 32) < 0.0);
+        assert_eq!(font.kerning('1', '2', 32), 0.0);
+    }
+
+    #[test]
+    fn font_line_spacing() {
+        let font = Font::open(get_sample_font_path()).unwrap();
+
+        assert!(font.ascent(32) > 0.0);
+        assert!(font.descent(32) > 0.0);
+        assert!(font.line_spacing(32) >= font.ascent(32) + font.descent(32));
+        assert!(font.line_spacing(64) > font.line_spacing(32));
+    }
+
+    #[test]
+    fn font_image() {
+        let font = Font::open(get_sample_font_path()).unwrap();
+
+        let image = font.image(32);
+        assert_eq!(image.size(), Size::new(PAGE_INITIAL_SIZE, PAGE_INITIAL_SIZE));
+        assert_eq!(font.texture_size(32), Size::new(128, 128));
+
+        // The glyph is drawn in the image, within its texture rectangle (and not on the padding).
+        let glyph = font.glyph('W', 32);
+        let image = font.image(32);
+
+        let rect = glyph.texture_rect;
+        let mut coverage = 0;
+        for y in rect.top()..rect.bottom() {
+            for x in rect.left()..rect.right() {
+                let pixel = image.pixel(Position::new(x as isize, y as isize)).unwrap();
+                if x == rect.left() || y == rect.top() {
+                    assert_eq!(pixel.alpha, 0);
+                }
+                coverage += pixel.alpha as u32;
+            }
+        }
+        assert!(coverage > 0);
+
+        // Requesting many glyphs makes the image grow, and existing glyphs stay in place.
+        for character in "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789".chars() {
+            font.glyph(character, 32);
+        }
+
+        let grown_image = font.image(32);
+        assert!(grown_image.size().width > PAGE_INITIAL_SIZE);
+        assert_eq!(font.glyph('W', 32), glyph);
+
+        for y in rect.top()..rect.bottom() {
+            for x in rect.left()..rect.right() {
+                let position = Position::new(x as isize, y as isize);
+                assert_eq!(grown_image.pixel(position), image.pixel(position));
+            }
+        }
+    }
+}
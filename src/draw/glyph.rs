@@ -5,8 +5,59 @@
 //
 // Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, January 2020
 
+use crate::geometry::{Position, Box};
+
 /// The information to display a character of a given font.
 ///
-/// The **Glyph struct** is not documented yet. Pull requests are welcome.
+/// A glyph is the visual representation of a character, rasterized at a given character size. It's
+/// never constructed directly; it's returned by the `glyph()` method of a font which rasterizes it
+/// on demand and packs it into the texture of the font.
+///
+/// The `advance` field is the horizontal offset to apply to the pen position to reach the next
+/// glyph. The `bounds` field is the bounding box of the glyph, relative to the pen position on the
+/// baseline (the Y axis points downwards, therefore the top of most glyphs is negative). Its
+/// position is also known as the 'bearing' of the glyph and can be obtained with `bearing()`. The
+/// `texture_rect` field is the area (in pixels) of the glyph in the texture of the font.
+///
+/// **Implementation notes**
+///
+/// - The bounds and the texture rectangle do not necessarily have the same size; the texture
+///   rectangle includes the padding that is added around each glyph to prevent bleeding.
 ///
-pub struct Glyph {}
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Glyph {
+    /// The offset to move horizontally to the next character.
+    pub advance: f32,
+    /// The bounding box of the glyph, relative to the baseline.
+    pub bounds: Box<f32>,
+    /// The area of the glyph in the texture of the font.
+    pub texture_rect: Box<i32>
+}
+
+impl Glyph {
+    /// Returns the bearing of the glyph.
+    ///
+    /// This function returns the offset from the pen position (on the baseline) to the top-left
+    /// corner of the glyph. It's the position of the glyph bounds.
+    ///
+    pub fn bearing(&self) -> Position<f32> {
+        self.bounds.position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Size;
+
+    #[test]
+    fn glyph_bearing() {
+        let glyph = Glyph {
+            advance: 10.0,
+            bounds: Box::new(Position::new(1.0, -12.0), Size::new(8.0, 12.0)),
+            texture_rect: Box::new(Position::new(2, 2), Size::new(10, 14))
+        };
+
+        assert_eq!(glyph.bearing(), Position::new(1.0, -12.0));
+    }
+}
@@ -0,0 +1,36 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+use crate::draw::gl;
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for u16 {}
+    impl Sealed for u32 {}
+}
+
+/// The types of the indices of a vertex array
+///
+/// An index refers to a vertex of a vertex array by its position in the array (see
+/// `VertexArray::update_indices()`). The indices are either 16-bit or 32-bit unsigned integers;
+/// the 16-bit indices take half the memory but they can only refer to the first 65536 vertices.
+///
+/// This trait is implemented for `u16` and `u32` only.
+///
+pub trait Index: Copy + Into<u32> + sealed::Sealed {
+    #[doc(hidden)]
+    const GL_TYPE: gl::types::GLenum;
+}
+
+impl Index for u16 {
+    const GL_TYPE: gl::types::GLenum = gl::UNSIGNED_SHORT;
+}
+
+impl Index for u32 {
+    const GL_TYPE: gl::types::GLenum = gl::UNSIGNED_INT;
+}
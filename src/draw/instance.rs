@@ -0,0 +1,127 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+use crate::geometry::{Size, Box, Matrix};
+use crate::image::Color;
+
+/// The attributes of an instance of a vertex array.
+///
+/// When a vertex array has instances (see `VertexArray::update_instances()`), its vertices are
+/// drawn once per instance with a single drawing call; the vertices are transformed by the matrix
+/// of the instance, their color is multiplied by its color, and their texture coordinates are
+/// relative to its texture rectangle. It's the most efficient way to draw many copies of the same
+/// mesh which only differ by these attributes.
+///
+/// ```no_run
+/// # use byteplug::geometry::{Size, Matrix};
+/// # use byteplug::image::Color;
+/// # use byteplug::draw::{Surface, Options, VertexArray, Instance, RenderStates};
+/// # struct Bullet { color: Color }
+/// # impl Bullet { fn matrix(&self) -> Matrix { Matrix::new() } }
+/// # let mut surface = Surface::new(Size::new(640, 480), Options::default()).unwrap();
+/// # let bullets: Vec<Bullet> = Vec::new();
+/// # let mut bullet_mesh = VertexArray::new();
+/// let instances: Vec<Instance> = bullets.iter()
+///     .map(|bullet| Instance::with_matrix_and_color(bullet.matrix(), bullet.color))
+///     .collect();
+///
+/// bullet_mesh.update_instances(&instances);
+/// surface.draw_vertices(&bullet_mesh, &RenderStates::default());
+/// ```
+///
+/// The color is made of normalized values and the texture rectangle is made of normalized texture
+/// coordinates (the position and the size of the area); use `set_texture_rect()` to compute it
+/// from an area in pixels.
+///
+/// **Implementation notes**
+///
+/// - The instances are passed to the shaders with the attributes 3 and 4 (the rows of the matrix,
+///   as `vec3`), 5 (the color, as `vec4`) and 6 (the texture rectangle, as `vec4`); the custom
+///   shaders drawing instances must declare them at these locations.
+///
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Instance {
+    /// The elements of the matrix of the instance (see `Matrix`).
+    pub matrix: [f32; 6],
+    /// The color of the instance (red, green, blue and alpha).
+    pub color: [f32; 4],
+    /// The texture rectangle of the instance (left, top, width and height).
+    pub texture_rect: [f32; 4]
+}
+
+impl Instance {
+    /// Constructs a default instance.
+    ///
+    /// The instance has the identity matrix, white as color and the entire texture as texture
+    /// rectangle; it doesn't change the vertices.
+    ///
+    pub fn new() -> Instance {
+        Instance {
+            matrix: Matrix::new().elements,
+            color: [1.0, 1.0, 1.0, 1.0],
+            texture_rect: [0.0, 0.0, 1.0, 1.0]
+        }
+    }
+
+    /// Constructs an instance from a matrix and a color.
+    ///
+    /// The instance has the given matrix and color, with the entire texture as texture rectangle.
+    ///
+    pub fn with_matrix_and_color(matrix: Matrix, color: Color) -> Instance {
+        let mut instance = Instance::new();
+        instance.matrix = matrix.elements;
+        instance.color = [
+            color.red   as f32 / 255.0,
+            color.green as f32 / 255.0,
+            color.blue  as f32 / 255.0,
+            color.alpha as f32 / 255.0
+        ];
+
+        instance
+    }
+
+    /// Changes the texture rectangle from an area in pixels.
+    ///
+    /// This function changes the texture rectangle of the instance to an area of a texture of a
+    /// given size (for instance, a region of an atlas).
+    ///
+    pub fn set_texture_rect(&mut self, texture_rect: Box<i32>, texture_size: Size<i32>) {
+        let width = texture_size.width as f32;
+        let height = texture_size.height as f32;
+
+        self.texture_rect = [
+            texture_rect.left() as f32 / width,
+            texture_rect.top() as f32 / height,
+            texture_rect.size.width as f32 / width,
+            texture_rect.size.height as f32 / height
+        ];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Position;
+
+    #[test]
+    fn instance_new() {
+        let instance = Instance::with_matrix_and_color(Matrix::new(), Color::WHITE);
+        assert_eq!(instance, Instance::new());
+
+        let instance = Instance::with_matrix_and_color(Matrix::with_elements([1.0, 2.0, 3.0, 4.0, 5.0, 6.0]), Color::RED);
+        assert_eq!(instance.matrix, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(instance.color, [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn instance_set_texture_rect() {
+        let mut instance = Instance::new();
+        instance.set_texture_rect(Box::new(Position::new(16, 8), Size::new(32, 16)), Size::new(64, 32));
+        assert_eq!(instance.texture_rect, [0.25, 0.25, 0.5, 0.5]);
+    }
+}
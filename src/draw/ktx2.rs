@@ -0,0 +1,291 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+//! The KTX2 container format
+//!
+//! This module reads the KTX2 files holding compressed 2D textures, and their mipmap. Only the
+//! compressed formats guaranteed by OpenGL ES 3.2 (ETC2, EAC and ASTC) are supported, and the
+//! supercompressed files (BasisLZ and Zstandard) aren't.
+//!
+//! See https://github.khronos.org/KTX-Specification/ for the specification of the format.
+//!
+use std::io::{self, Read};
+use std::convert::TryInto;
+use crate::geometry::Size;
+use crate::draw::gl;
+
+const IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+
+// The size of the header (identifier included) and of the entries of the level index.
+const HEADER_SIZE: usize = 80;
+const LEVEL_SIZE: usize = 24;
+
+/// A compressed format
+///
+/// It's the OpenGL internal format of a compressed texture, along with the size of its blocks
+/// (in pixels) and the number of bytes of a block.
+///
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub(crate) struct Format {
+    pub internal_format: gl::types::GLenum,
+    pub block_size: Size<i32>,
+    pub block_bytes: usize
+}
+
+/// A compressed texture
+///
+/// It's the content of a KTX2 file; the levels are the compressed data of the levels of the mipmap
+/// (the first one is the texture itself).
+///
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub(crate) struct Ktx2 {
+    pub format: Format,
+    pub size: Size<i32>,
+    pub levels: Vec<Vec<u8>>
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("invalid KTX2 file ({})", message))
+}
+
+// Convert a Vulkan format (as stored in KTX2 files) to a compressed format.
+fn from_vulkan_format(format: u32) -> Option<Format> {
+    let (internal_format, block_width, block_height, block_bytes) = match format {
+        147 => (gl::COMPRESSED_RGB8_ETC2,                      4,  4,  8),
+        148 => (gl::COMPRESSED_SRGB8_ETC2,                     4,  4,  8),
+        149 => (gl::COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2,  4,  4,  8),
+        150 => (gl::COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2, 4,  4,  8),
+        151 => (gl::COMPRESSED_RGBA8_ETC2_EAC,                 4,  4,  16),
+        152 => (gl::COMPRESSED_SRGB8_ALPHA8_ETC2_EAC,          4,  4,  16),
+        153 => (gl::COMPRESSED_R11_EAC,                        4,  4,  8),
+        154 => (gl::COMPRESSED_SIGNED_R11_EAC,                 4,  4,  8),
+        155 => (gl::COMPRESSED_RG11_EAC,                       4,  4,  16),
+        156 => (gl::COMPRESSED_SIGNED_RG11_EAC,                4,  4,  16),
+        157 => (gl::COMPRESSED_RGBA_ASTC_4x4,                  4,  4,  16),
+        158 => (gl::COMPRESSED_SRGB8_ALPHA8_ASTC_4x4,          4,  4,  16),
+        159 => (gl::COMPRESSED_RGBA_ASTC_5x4,                  5,  4,  16),
+        160 => (gl::COMPRESSED_SRGB8_ALPHA8_ASTC_5x4,          5,  4,  16),
+        161 => (gl::COMPRESSED_RGBA_ASTC_5x5,                  5,  5,  16),
+        162 => (gl::COMPRESSED_SRGB8_ALPHA8_ASTC_5x5,          5,  5,  16),
+        163 => (gl::COMPRESSED_RGBA_ASTC_6x5,                  6,  5,  16),
+        164 => (gl::COMPRESSED_SRGB8_ALPHA8_ASTC_6x5,          6,  5,  16),
+        165 => (gl::COMPRESSED_RGBA_ASTC_6x6,                  6,  6,  16),
+        166 => (gl::COMPRESSED_SRGB8_ALPHA8_ASTC_6x6,          6,  6,  16),
+        167 => (gl::COMPRESSED_RGBA_ASTC_8x5,                  8,  5,  16),
+        168 => (gl::COMPRESSED_SRGB8_ALPHA8_ASTC_8x5,          8,  5,  16),
+        169 => (gl::COMPRESSED_RGBA_ASTC_8x6,                  8,  6,  16),
+        170 => (gl::COMPRESSED_SRGB8_ALPHA8_ASTC_8x6,          8,  6,  16),
+        171 => (gl::COMPRESSED_RGBA_ASTC_8x8,                  8,  8,  16),
+        172 => (gl::COMPRESSED_SRGB8_ALPHA8_ASTC_8x8,          8,  8,  16),
+        173 => (gl::COMPRESSED_RGBA_ASTC_10x5,                 10, 5,  16),
+        174 => (gl::COMPRESSED_SRGB8_ALPHA8_ASTC_10x5,         10, 5,  16),
+        175 => (gl::COMPRESSED_RGBA_ASTC_10x6,                 10, 6,  16),
+        176 => (gl::COMPRESSED_SRGB8_ALPHA8_ASTC_10x6,         10, 6,  16),
+        177 => (gl::COMPRESSED_RGBA_ASTC_10x8,                 10, 8,  16),
+        178 => (gl::COMPRESSED_SRGB8_ALPHA8_ASTC_10x8,         10, 8,  16),
+        179 => (gl::COMPRESSED_RGBA_ASTC_10x10,                10, 10, 16),
+        180 => (gl::COMPRESSED_SRGB8_ALPHA8_ASTC_10x10,        10, 10, 16),
+        181 => (gl::COMPRESSED_RGBA_ASTC_12x10,                12, 10, 16),
+        182 => (gl::COMPRESSED_SRGB8_ALPHA8_ASTC_12x10,        12, 10, 16),
+        183 => (gl::COMPRESSED_RGBA_ASTC_12x12,                12, 12, 16),
+        184 => (gl::COMPRESSED_SRGB8_ALPHA8_ASTC_12x12,        12, 12, 16),
+        _ => return None
+    };
+
+    Some(Format {
+        internal_format: internal_format,
+        block_size: Size::new(block_width, block_height),
+        block_bytes: block_bytes
+    })
+}
+
+// Compute the size of a level of the mipmap.
+pub(crate) fn level_size(size: Size<i32>, level: usize) -> Size<i32> {
+    let shift = |length: i32| length.checked_shr(level as u32).unwrap_or(0).max(1);
+    Size::new(shift(size.width), shift(size.height))
+}
+
+// Compute the number of levels of a complete mipmap (down to a single pixel).
+fn max_level_count(size: Size<i32>) -> usize {
+    32 - size.width.max(size.height).max(1).leading_zeros() as usize
+}
+
+// Compute the number of bytes of a level of a given size (the blocks on the edges are complete).
+fn level_bytes(format: &Format, size: Size<i32>) -> usize {
+    let columns = (size.width + format.block_size.width - 1) / format.block_size.width;
+    let rows = (size.height + format.block_size.height - 1) / format.block_size.height;
+
+    columns as usize * rows as usize * format.block_bytes
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(bytes[offset..offset + 8].try_into().unwrap())
+}
+
+/// Parse a KTX2 file.
+///
+/// This function parses the content of a KTX2 file; an error is returned if it's not a valid
+/// KTX2 file or if it's not supported.
+///
+pub(crate) fn parse(bytes: &[u8]) -> io::Result<Ktx2> {
+    if bytes.len() < HEADER_SIZE || bytes[..12] != IDENTIFIER {
+        return Err(invalid_data("wrong identifier"))
+    }
+
+    let header = |index: usize| read_u32(bytes, 12 + index * 4);
+    let (vulkan_format, width, height, depth) = (header(0), header(2), header(3), header(4));
+    let (layer_count, face_count, level_count, supercompression) = (header(5), header(6), header(7), header(8));
+
+    let format = from_vulkan_format(vulkan_format)
+        .ok_or_else(|| invalid_data("unsupported format"))?;
+
+    if supercompression != 0 {
+        return Err(invalid_data("supercompression isn't supported"))
+    }
+
+    if depth != 0 || layer_count != 0 || face_count != 1 || width == 0 || height == 0 {
+        return Err(invalid_data("not a 2D texture"))
+    }
+
+    if width > i32::MAX as u32 || height > i32::MAX as u32 {
+        return Err(invalid_data("the texture is too large"))
+    }
+
+    let size = Size::new(width as i32, height as i32);
+
+    // A level count of zero requests the mipmap to be generated, which isn't possible with
+    // compressed formats; only the texture itself is stored.
+    let level_count = level_count.max(1) as usize;
+    if level_count > max_level_count(size) {
+        return Err(invalid_data("too many levels"))
+    }
+
+    if bytes.len() < HEADER_SIZE + level_count * LEVEL_SIZE {
+        return Err(invalid_data("truncated level index"))
+    }
+
+    let mut levels = Vec::with_capacity(level_count);
+    for level in 0..level_count {
+        let entry = HEADER_SIZE + level * LEVEL_SIZE;
+        let offset = read_u64(bytes, entry) as usize;
+        let length = read_u64(bytes, entry + 8) as usize;
+
+        if length != level_bytes(&format, level_size(size, level)) {
+            return Err(invalid_data("wrong level size"))
+        }
+
+        let data = offset.checked_add(length)
+            .and_then(|end| bytes.get(offset..end))
+            .ok_or_else(|| invalid_data("truncated level"))?;
+
+        levels.push(data.to_vec());
+    }
+
+    Ok(Ktx2 {
+        format: format,
+        size: size,
+        levels: levels
+    })
+}
+
+/// Read a KTX2 file.
+///
+/// This function reads the content of a KTX2 file from a reader, then parses it.
+///
+pub(crate) fn read<R: Read>(reader: &mut R) -> io::Result<Ktx2> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+
+    parse(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Construct a KTX2 file; the levels are stored after the level index, from the smallest one.
+    fn make_file(format: u32, size: (u32, u32), supercompression: u32, levels: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = IDENTIFIER.to_vec();
+        let header = [format, 1, size.0, size.1, 0, 0, 1, levels.len() as u32, supercompression];
+        for value in header.iter() {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&[0; 32]); // the data format descriptor, key/values and global data
+
+        let mut offset = HEADER_SIZE + levels.len() * LEVEL_SIZE;
+        let mut offsets = vec![0; levels.len()];
+        for (level, data) in levels.iter().enumerate().rev() {
+            offsets[level] = offset;
+            offset += data.len();
+        }
+
+        for (level, data) in levels.iter().enumerate() {
+            bytes.extend_from_slice(&(offsets[level] as u64).to_le_bytes());
+            bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        }
+
+        for data in levels.iter().rev() {
+            bytes.extend_from_slice(data);
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn ktx2_level_bytes() {
+        let format = from_vulkan_format(147).unwrap();
+        assert_eq!(level_bytes(&format, Size::new(8, 4)), 16);
+        assert_eq!(level_bytes(&format, Size::new(5, 1)), 16);
+
+        let format = from_vulkan_format(183).unwrap();
+        assert_eq!(format.internal_format, gl::COMPRESSED_RGBA_ASTC_12x12);
+        assert_eq!(level_bytes(&format, Size::new(25, 12)), 48);
+
+        assert_eq!(level_size(Size::new(8, 4), 0), Size::new(8, 4));
+        assert_eq!(level_size(Size::new(8, 4), 3), Size::new(1, 1));
+        assert_eq!(level_size(Size::new(1, 1), 40), Size::new(1, 1));
+
+        assert_eq!(max_level_count(Size::new(1, 1)), 1);
+        assert_eq!(max_level_count(Size::new(8, 4)), 4);
+        assert_eq!(max_level_count(Size::new(5, 9)), 4);
+    }
+
+    #[test]
+    fn ktx2_parse() {
+        let levels = vec![vec![1; 16], vec![2; 8], vec![3; 8], vec![4; 8]];
+        let ktx2 = parse(&make_file(147, (8, 4), 0, &levels)).unwrap();
+
+        assert_eq!(ktx2.format.internal_format, gl::COMPRESSED_RGB8_ETC2);
+        assert_eq!(ktx2.size, Size::new(8, 4));
+        assert_eq!(ktx2.levels, levels);
+
+        let invalid_files = [
+            b"not a KTX2 file".to_vec(),
+            make_file(37, (8, 4), 0, &levels),
+            make_file(147, (8, 4), 2, &levels),
+            make_file(147, (8, 4), 0, &[vec![1; 8]]),
+            make_file(147, (8, 4), 0, &levels)[..200].to_vec()
+        ];
+
+        for file in invalid_files.iter() {
+            assert_eq!(parse(file).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        }
+
+        // A 1x1 texture can't have more than one level (the size of the levels would overflow).
+        let error = parse(&make_file(147, (1, 1), 0, &vec![vec![1; 8]; 33])).unwrap_err();
+        assert_eq!(error.to_string(), "invalid KTX2 file (too many levels)");
+
+        let mut file = make_file(147, (1, 1), 0, &[vec![1; 8]]);
+        file[12 + 7 * 4..12 + 8 * 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(parse(&file).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
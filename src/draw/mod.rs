@@ -46,79 +46,117 @@ macro_rules! gl_check {
         $s
         if cfg!(debug_assertions) {
             let err = gl::GetError();
-            match err {
-                gl::NO_ERROR => (),
-                _ => {
-                    let err_str = match err {
-                        gl::INVALID_ENUM => "GL_INVALID_ENUM",
-                        gl::INVALID_VALUE => "GL_INVALID_VALUE",
-                        gl::INVALID_OPERATION => "GL_INVALID_OPERATION",
-                        gl::INVALID_FRAMEBUFFER_OPERATION => "GL_INVALID_FRAMEBUFFER_OPERATION",
-                        gl::OUT_OF_MEMORY => "GL_OUT_OF_MEMORY",
-                        gl::STACK_UNDERFLOW => "GL_STACK_UNDERFLOW",
-                        gl::STACK_OVERFLOW => "GL_STACK_OVERFLOW",
-                        _ => "unknown error"
-                    };
-                    println!("{}:{} - {} caused {}",
-                            file!(),
-                            line!(),
-                            stringify!($s),
-                            err_str);
-                }
-            }
+            $crate::draw::debug::check_error(file!(), line!(), stringify!($s), err);
         }
     }
 }
 
+pub mod debug;
+
 pub(crate) use context::get_or_create_context;
+pub(crate) use options::configure_context;
 
 // Not sure about those ones; guess they should be removed at some point.
 #[allow(unused_imports)]
 pub(crate) use context::make_context_current;
 #[allow(unused_imports)]
 pub(crate) use default_shader::get_or_create_default_shader;
+pub(crate) use distance_field_shader::get_or_create_distance_field_shader;
 
 mod context;
 
 mod options;
+mod surface_error;
 mod surface;
 mod view;
 
 mod uniform;
+mod uniform_buffer;
+mod shader_stage;
+mod shader_error;
+mod shader_type;
+mod shader_variable;
 mod shader;
 mod default_shader;
+mod distance_field_shader;
+mod wrap;
+mod texture_format;
+mod ktx2;
 mod texture;
+mod atlas;
+mod atlas_builder;
 
 mod primitive;
 mod usage;
+mod attribute_format;
+mod attribute;
+mod vertex_layout;
 mod vertex;
+mod index;
+mod instance;
 mod vertex_array;
+mod vertex_mapping;
 
+mod blend_factor;
+mod blend_equation;
+mod blend_mode;
+mod render_states;
 mod drawable;
+mod batch;
 
 mod shape;
 
 mod glyph;
+mod rendering;
 mod font;
+mod alignment;
+mod span;
 mod text;
 
+#[cfg(test)]
+pub(crate) mod testing;
+
 pub use options::Options;
+pub use surface_error::SurfaceError;
 pub use surface::Surface;
 pub use view::View;
 
 pub use uniform::Uniform;
+pub use uniform_buffer::UniformBuffer;
+pub use shader_stage::ShaderStage;
+pub use shader_error::ShaderError;
+pub use shader_type::ShaderType;
+pub use shader_variable::ShaderVariable;
 pub use shader::Shader;
+pub use wrap::Wrap;
+pub use texture_format::TextureFormat;
 pub use texture::Texture;
+pub use atlas::Atlas;
+pub use atlas_builder::AtlasBuilder;
 
 pub use primitive::Primitive;
 pub use usage::Usage;
+pub use attribute_format::AttributeFormat;
+pub use attribute::Attribute;
+pub use vertex_layout::VertexLayout;
 pub use vertex::Vertex;
+pub use index::Index;
+pub use instance::Instance;
 pub use vertex_array::VertexArray;
+pub use vertex_mapping::VertexMapping;
 
+pub use blend_factor::BlendFactor;
+pub use blend_equation::BlendEquation;
+pub use blend_mode::BlendMode;
+pub use render_states::RenderStates;
 pub use drawable::Drawable;
+pub use batch::Batch;
 
 pub use shape::Shape;
 
 pub use glyph::Glyph;
+pub use rendering::Rendering;
 pub use font::Font;
+pub use alignment::Alignment;
+pub use span::Span;
 pub use text::Text;
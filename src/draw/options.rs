@@ -4,13 +4,69 @@
 // Please refer to the LICENSE file that can be found at the root of the project directory.
 //
 // Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, May 2020
-#[allow(dead_code)]
-#[derive(Default)]
+
+use glutin::{ContextBuilder, ContextCurrentState};
+
+/// The options of a surface.
+///
+/// The options of a surface are the properties of its framebuffer and of its OpenGL context; they
+/// must be specified when the surface is created (or when the window is created, in the case of a
+/// window surface) and can't be changed afterward.
+///
+/// ```no_run
+/// # use byteplug::geometry::Size;
+/// # use byteplug::draw::{Surface, Options};
+/// let options = Options {
+///     antialiasing_level: 4,
+///     vsync: true,
+///     ..Options::default()
+/// };
+///
+/// let mut surface = Surface::new(Size::new(640, 480), options).unwrap();
+/// println!("{:?}", surface.options());
+/// ```
+///
+/// The options are requests; the graphics driver may not support them and pick the closest
+/// configuration instead. The options that were really obtained are returned by
+/// `Surface::options()`.
+///
+/// **Implementation notes**
+///
+/// - In the case of a non-window surface, the depth buffer, the stencil buffer and the
+///   multisampling are implemented with renderbuffers attached to its framebuffer; a multisampled
+///   surface must be resolved (see `Surface::resolve()`) before its texture is used.
+/// - The vertical synchronization only applies to window surfaces and it can't be queried from
+///   the driver; the requested value is reported.
+/// - The debug option requests a debug OpenGL context; its debug output is routed to the logger of
+///   the `debug` module.
+///
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
 pub struct Options {
-    depth_bits: Option<u32>,
-    stencil_bits: Option<u32>,
-    antialiasing_level: Option<u32>,
-    srgb: Option<u32>,
-    vsync: Option<u32>,
-    debug: Option<u32>
+    /// The number of bits of the depth buffer (no depth buffer if it's zero).
+    pub depth_bits: u8,
+    /// The number of bits of the stencil buffer (no stencil buffer if it's zero).
+    pub stencil_bits: u8,
+    /// The number of samples per pixel (no multisampling if it's zero).
+    pub antialiasing_level: u16,
+    /// Whether the colors are stored in the sRGB color space.
+    pub srgb: bool,
+    /// Whether the vertical synchronization is enabled.
+    pub vsync: bool,
+    /// Whether the OpenGL context is a debug context.
+    pub debug: bool
+}
+
+/// Configure a context builder with the options.
+///
+/// This function applies the options to the builder of a windowed OpenGL context (the options
+/// apply to its default framebuffer).
+///
+pub(crate) fn configure_context<'a, T: ContextCurrentState>(builder: ContextBuilder<'a, T>, options: &Options) -> ContextBuilder<'a, T> {
+    builder
+        .with_depth_buffer(options.depth_bits)
+        .with_stencil_buffer(options.stencil_bits)
+        .with_multisampling(options.antialiasing_level)
+        .with_srgb(options.srgb)
+        .with_vsync(options.vsync)
+        .with_gl_debug_flag(options.debug)
 }
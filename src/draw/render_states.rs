@@ -0,0 +1,119 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+use crate::geometry::{Box, Matrix};
+use crate::draw::{Shader, Texture};
+use crate::draw::BlendMode;
+
+/// The states used to draw.
+///
+/// The render states are the parameters of a drawing operation; they're passed to
+/// `Drawable::draw()` and to `Surface::draw_vertices()`. They're made of the shader program, the
+/// blending mode, the texture, the transformation and the scissor rectangle.
+///
+/// ```no_run
+/// # use byteplug::geometry::Size;
+/// # use byteplug::draw::{Surface, Options, Shape, Shader, Uniform, RenderStates};
+/// # const VERTEX_SHADER_SRC: &[u8] = b"";
+/// # const FRAGMENT_SHADER_SRC: &[u8] = b"";
+/// # let mut surface = Surface::new(Size::new(640, 480), Options::default()).unwrap();
+/// # let shape = Shape::new();
+/// # let elapsed = 0.0;
+/// let mut shader = Shader::new(VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC).unwrap();
+/// shader.set_uniform("time", Uniform::Float(elapsed));
+///
+/// let states = RenderStates {
+///     shader: Some(&shader),
+///     ..RenderStates::default()
+/// };
+/// surface.draw_with_states(&shape, &states);
+/// ```
+///
+/// A drawable combines the states it receives with its own; it typically transforms them with its
+/// own matrix (see `transformed()`), and it overrides the texture if it has one. This way,
+/// drawables can be nested (a drawable made of other drawables passes its states down).
+///
+/// **Implementation notes**
+///
+/// - If there is no shader, the default shader is used (or the shader the drawable needs, for
+///   instance, the distance field shader of texts). A custom shader must have the same vertex
+///   attributes (unless the vertices have a custom layout, see `VertexLayout`) and the same
+///   `viewport` and `model` uniforms as the default shader; the other uniforms must be set before
+///   drawing.
+/// - The scissor rectangle is in pixels, relative to the top-left corner of the surface; nothing is
+///   drawn outside of it.
+///
+#[derive(Copy, Clone)]
+pub struct RenderStates<'a> {
+    /// The shader program (the default one if it's none).
+    pub shader: Option<&'a Shader>,
+    /// The blending mode.
+    pub blend_mode: BlendMode,
+    /// The texture (the default white texture if it's none).
+    pub texture: Option<&'a Texture>,
+    /// The transformation applied to the vertices.
+    pub transform: Matrix,
+    /// The scissor rectangle (the entire surface if it's none).
+    pub scissor: Option<Box<i32>>
+}
+
+impl<'a> RenderStates<'a> {
+    /// Constructs the default render states.
+    ///
+    /// This function is the default constructor. It creates render states with the default shader,
+    /// the alpha blending mode, no texture, the identity transformation and no scissor rectangle.
+    ///
+    pub fn new() -> RenderStates<'a> {
+        RenderStates {
+            shader: None,
+            blend_mode: BlendMode::ALPHA,
+            texture: None,
+            transform: Matrix::IDENTITY,
+            scissor: None
+        }
+    }
+
+    /// Returns the render states transformed by a matrix.
+    ///
+    /// This function returns a copy of the render states whose transformation is combined with a
+    /// given matrix; the matrix is applied first, then the existing transformation.
+    ///
+    pub fn transformed(&self, matrix: Matrix) -> RenderStates<'a> {
+        RenderStates {
+            transform: self.transform.combine(matrix),
+            ..*self
+        }
+    }
+}
+
+impl<'a> Default for RenderStates<'a> {
+    fn default() -> RenderStates<'a> {
+        RenderStates::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{Position, Transformer};
+
+    #[test]
+    fn render_states_transformed() {
+        let states = RenderStates::new();
+        assert_eq!(states.transform, Matrix::IDENTITY);
+
+        let translation = Transformer::new().translate(Position::new(10.0, 0.0)).matrix;
+        let scaling = Transformer::new().magnify(2.0, None).matrix;
+
+        // The matrix of the nested drawable (the magnification) is applied first, then the one of
+        // the parent drawable (the translation).
+        let states = states.transformed(translation).transformed(scaling);
+
+        let transformer = Transformer::with_matrix(states.transform);
+        assert_eq!(transformer.transform_position(&Position::new(1.0, 1.0)), Position::new(12.0, 2.0));
+    }
+}
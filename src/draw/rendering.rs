@@ -0,0 +1,26 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+/// The rendering modes of a font
+///
+/// A rendering mode specifies how the glyphs of a font are stored in its textures, and therefore,
+/// how they're drawn.
+///
+/// With the bitmap mode, the coverage of the glyphs is stored; it's the most accurate rendering at
+/// the character size, but it blurs when the text is magnified. With the distance field mode, the
+/// distance to the edge of the glyphs is stored instead, and the edges are reconstructed by a
+/// dedicated shader; the text stays crisp at any scale, and outlines, glows and shadows are
+/// computed by the shader.
+///
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum Rendering {
+    /// The coverage of the glyphs is stored.
+    #[default]
+    Bitmap,
+    /// The signed distance to the edge of the glyphs is stored.
+    DistanceField
+}
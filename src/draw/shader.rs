@@ -7,35 +7,206 @@
 
 use std::ffi::CString;
 use std::string::String;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use crate::draw::gl;
-use crate::draw::Uniform;
+use crate::draw::{Uniform, UniformBuffer};
+use crate::draw::{ShaderStage, ShaderError, ShaderType, ShaderVariable};
+use crate::draw::shader_error::log_lines;
+use crate::draw::texture::TextureObject;
+use crate::draw::uniform_buffer::BufferObject;
+use crate::draw::debug::set_object_label;
 
 /// A drawing program stored on the graphics card.
 ///
-/// The **Shader struct** is not documented yet. Pull requests are welcome.
+/// A shader is a program made of a vertex shader and a fragment shader, written in GLSL ES 3.0;
+/// it's used to draw with custom effects (see `RenderStates`). Creating it returns an error if its
+/// shaders don't compile or don't link (see `ShaderError`), and its active uniforms and attributes
+/// can be inspected (see `uniforms()` and `attributes()`) to validate them against what the
+/// application expects.
+///
+/// ```no_run
+/// # use byteplug::draw::{Shader, ShaderError};
+/// # const VERTEX_SHADER_SRC: &[u8] = b"";
+/// # const FRAGMENT_SHADER_SRC: &[u8] = b"";
+/// # fn main() -> Result<(), ShaderError> {
+/// let shader = Shader::new(VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC)?;
+/// let has_time = shader.uniforms().iter().any(|uniform| uniform.name == "time");
+/// # Ok(())
+/// # }
+/// ```
 ///
 /// **Implementation notes**
 ///
 /// - Very primitive implementation of the shader class; will obviously change a lot.
-/// - Boolean uniform setter is missing.
 /// - Implement the destructor (free shaders and the program as described in the OpenGL docs)
+/// - The locations of the uniforms are cached by name; they're looked up once, and not every time
+///   the `viewport` and `model` uniforms are set before drawing.
+/// - The textures given as uniforms are bound to the texture units from 1 onward, in the order
+///   they're first set (the unit 0 is the texture of the render states). They're bound again each
+///   time the shader is bound, so the shader shares their OpenGL texture object; it stays alive
+///   as long as the shader, even if the texture is dropped in the meantime.
+/// - Likewise, the uniform buffers are bound to the binding points from 0 onward each time the
+///   shader is bound; several shaders can share a buffer without agreeing on a binding point.
 ///
 #[allow(dead_code)]
 pub struct Shader {
     vertex_shader: gl::types::GLuint,
     fragment_shader: gl::types::GLuint,
-    program: gl::types::GLuint
+    program: gl::types::GLuint,
+    locations: RefCell<HashMap<String, i32>>, // see notes
+    textures: Vec<(i32, Rc<TextureObject>)>, // the location of the sampler and the texture, by unit
+    uniform_buffers: Vec<(u32, Rc<BufferObject>)> // the index of the block and the buffer, by binding point
+}
+
+// Uploads a uniform at a location of the current shader program (textures are turned into the
+// index of their texture unit beforehand).
+fn upload(location: i32, uniform: Uniform) {
+    unsafe {
+        match uniform {
+            Uniform::Boolean(value) => {
+                gl_check!(gl::Uniform1i(location, value as i32));
+            },
+            Uniform::Integer(value) => {
+                gl_check!(gl::Uniform1i(location, value));
+            },
+            Uniform::UnsignedInteger(value) => {
+                gl_check!(gl::Uniform1ui(location, value));
+            },
+            Uniform::Float(value) => {
+                gl_check!(gl::Uniform1f(location, value));
+            },
+            Uniform::Vector2(x, y) => {
+                gl_check!(gl::Uniform2f(location, x, y));
+            },
+            Uniform::Vector3(x, y, z) => {
+                gl_check!(gl::Uniform3f(location, x, y, z));
+            },
+            Uniform::Vector4(x, y, z, w) => {
+                gl_check!(gl::Uniform4f(location, x, y, z, w));
+            },
+            Uniform::IntegerVector2(x, y) => {
+                gl_check!(gl::Uniform2i(location, x, y));
+            },
+            Uniform::IntegerVector3(x, y, z) => {
+                gl_check!(gl::Uniform3i(location, x, y, z));
+            },
+            Uniform::IntegerVector4(x, y, z, w) => {
+                gl_check!(gl::Uniform4i(location, x, y, z, w));
+            },
+            Uniform::UnsignedIntegerVector2(x, y) => {
+                gl_check!(gl::Uniform2ui(location, x, y));
+            },
+            Uniform::UnsignedIntegerVector3(x, y, z) => {
+                gl_check!(gl::Uniform3ui(location, x, y, z));
+            },
+            Uniform::UnsignedIntegerVector4(x, y, z, w) => {
+                gl_check!(gl::Uniform4ui(location, x, y, z, w));
+            },
+            Uniform::Matrix2(value) => {
+                gl_check!(gl::UniformMatrix2fv(location, 1, gl::FALSE, value.as_ptr() as _));
+            },
+            Uniform::Matrix3(value) => {
+                gl_check!(gl::UniformMatrix3fv(location, 1, gl::FALSE, value.as_ptr() as _));
+            },
+            Uniform::Matrix4(value) => {
+                gl_check!(gl::UniformMatrix4fv(location, 1, gl::FALSE, value.as_ptr() as _));
+            },
+            Uniform::Color(color) => {
+                gl_check!(gl::Uniform4f(
+                    location,
+                    color.red   as f32 / 255.0,
+                    color.green as f32 / 255.0,
+                    color.blue  as f32 / 255.0,
+                    color.alpha as f32 / 255.0
+                ));
+            },
+            Uniform::IntegerArray(values) => {
+                gl_check!(gl::Uniform1iv(location, values.len() as _, values.as_ptr()));
+            },
+            Uniform::FloatArray(values) => {
+                gl_check!(gl::Uniform1fv(location, values.len() as _, values.as_ptr()));
+            },
+            Uniform::Vector2Array(values) => {
+                gl_check!(gl::Uniform2fv(location, values.len() as _, values.as_ptr() as _));
+            },
+            Uniform::Vector3Array(values) => {
+                gl_check!(gl::Uniform3fv(location, values.len() as _, values.as_ptr() as _));
+            },
+            Uniform::Vector4Array(values) => {
+                gl_check!(gl::Uniform4fv(location, values.len() as _, values.as_ptr() as _));
+            },
+            Uniform::Matrix3Array(values) => {
+                gl_check!(gl::UniformMatrix3fv(location, values.len() as _, gl::FALSE, values.as_ptr() as _));
+            },
+            Uniform::Matrix4Array(values) => {
+                gl_check!(gl::UniformMatrix4fv(location, values.len() as _, gl::FALSE, values.as_ptr() as _));
+            },
+            Uniform::Texture(_) => unreachable!("textures must be set with set_uniform()")
+        }
+    }
+}
+
+fn max_texture_units() -> usize {
+    unsafe {
+        let mut units = 0;
+        gl_check!(gl::GetIntegerv(gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS, &mut units));
+
+        units as usize
+    }
+}
+
+fn from_shader_stage(stage: ShaderStage) -> gl::types::GLenum {
+    match stage {
+        ShaderStage::Vertex   => gl::VERTEX_SHADER,
+        ShaderStage::Fragment => gl::FRAGMENT_SHADER
+    }
+}
+
+fn to_shader_type(kind: gl::types::GLenum) -> ShaderType {
+    match kind {
+        gl::BOOL              => ShaderType::Boolean,
+        gl::INT               => ShaderType::Integer,
+        gl::UNSIGNED_INT      => ShaderType::UnsignedInteger,
+        gl::FLOAT             => ShaderType::Float,
+        gl::FLOAT_VEC2        => ShaderType::Vector2,
+        gl::FLOAT_VEC3        => ShaderType::Vector3,
+        gl::FLOAT_VEC4        => ShaderType::Vector4,
+        gl::INT_VEC2          => ShaderType::IntegerVector2,
+        gl::INT_VEC3          => ShaderType::IntegerVector3,
+        gl::INT_VEC4          => ShaderType::IntegerVector4,
+        gl::UNSIGNED_INT_VEC2 => ShaderType::UnsignedIntegerVector2,
+        gl::UNSIGNED_INT_VEC3 => ShaderType::UnsignedIntegerVector3,
+        gl::UNSIGNED_INT_VEC4 => ShaderType::UnsignedIntegerVector4,
+        gl::FLOAT_MAT2        => ShaderType::Matrix2,
+        gl::FLOAT_MAT3        => ShaderType::Matrix3,
+        gl::FLOAT_MAT4        => ShaderType::Matrix4,
+        gl::SAMPLER_2D        => ShaderType::Sampler2D,
+        kind                  => ShaderType::Other(kind)
+    }
+}
+
+// Make a Rust string out of a string returned by OpenGL, given its length (it's ASCII, which is
+// valid UTF-8, but the driver isn't trusted).
+fn gl_string(mut bytes: Vec<u8>, length: i32) -> String {
+    bytes.truncate(length.max(0) as usize);
+    String::from_utf8_lossy(&bytes).trim_end().to_string()
 }
 
-fn compile_shader(source: &[u8], type_: u32) -> Result<u32, String> {
+fn compile_shader(source: &[u8], stage: ShaderStage) -> Result<u32, ShaderError> {
     unsafe {
-        // Create the OpenGL shader object and attach its source code
-        gl_check!(let shader = gl::CreateShader(type_));
+        // Create the OpenGL shader object and attach its source code; its length is passed
+        // explicitly so the driver doesn't read past the slice (a trailing null character, if
+        // any, isn't part of the source).
+        let source = source.strip_suffix(b"\0").unwrap_or(source);
+
+        gl_check!(let shader = gl::CreateShader(from_shader_stage(stage)));
         gl_check!(gl::ShaderSource(
             shader,
             1,
             [source.as_ptr() as *const _].as_ptr(),
-            std::ptr::null(),
+            [source.len() as gl::types::GLint].as_ptr(),
         ));
 
         // Compile the OpenGL shader and check for errors
@@ -45,18 +216,24 @@ fn compile_shader(source: &[u8], type_: u32) -> Result<u32, String> {
         gl_check!(gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success));
 
         if success == gl::FALSE as _ {
+            let mut capacity: i32 = 0;
+            gl_check!(gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut capacity));
+
             let mut length: i32 = 0;
-            let mut info_log: [u8; 1024] = [0; 1024];
+            let mut log = vec![0u8; capacity.max(1) as usize];
             gl_check!(gl::GetShaderInfoLog(shader,
-                                 1024,
+                                 log.len() as _,
                                  &mut length,
-                                 info_log.as_mut_ptr() as *mut _
+                                 log.as_mut_ptr() as *mut _
             ));
-            assert_ne!(length, 0);
+            gl_check!(gl::DeleteShader(shader));
 
-            // Make a UTF-8 Rust string out of the ASCII C 'info log' string (note that ASCII is
-            // valid UTF-8)
-            Err(String::from_utf8_unchecked(info_log.split_at(length as usize).0.to_vec()))
+            let log = gl_string(log, length);
+            Err(ShaderError::Compile {
+                stage: stage,
+                lines: log_lines(&log),
+                log: log
+            })
         }
         else {
             Ok(shader)
@@ -64,7 +241,7 @@ fn compile_shader(source: &[u8], type_: u32) -> Result<u32, String> {
     }
 }
 
-fn make_program(shaders: Vec<u32>) -> Result<u32, String> {
+fn make_program(shaders: Vec<u32>) -> Result<u32, ShaderError> {
     unsafe {
         gl_check!(let program = gl::CreateProgram());
 
@@ -81,19 +258,19 @@ fn make_program(shaders: Vec<u32>) -> Result<u32, String> {
         gl_check!(gl::GetProgramiv(program, gl::LINK_STATUS, &mut success));
 
         if success == gl::FALSE as _ {
-            let mut length: i32 = 0;
-            let mut info_log: [u8; 1024] = [0; 1024];
+            let mut capacity: i32 = 0;
+            gl_check!(gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut capacity));
 
+            let mut length: i32 = 0;
+            let mut log = vec![0u8; capacity.max(1) as usize];
             gl_check!(gl::GetProgramInfoLog(program,
-                                            1024,
+                                            log.len() as _,
                                             &mut length,
-                                            info_log.as_mut_ptr() as *mut _
+                                            log.as_mut_ptr() as *mut _
             ));
-            assert_ne!(length, 0);
+            gl_check!(gl::DeleteProgram(program));
 
-            // Make a UTF-8 Rust string out of the ASCII C 'info log' string (note that ASCII is
-            // valid UTF-8)
-            Err(String::from_utf8_unchecked(info_log.split_at(length as usize).0.to_vec()))
+            Err(ShaderError::Link { log: gl_string(log, length) })
         }
         else {
             Ok(program)
@@ -102,84 +279,171 @@ fn make_program(shaders: Vec<u32>) -> Result<u32, String> {
 }
 
 impl Shader {
-    /// Brief description
+    /// Constructs a shader from the sources of its shaders.
     ///
-    /// The **new() method** is not documented yet. Pull requests are welcome.
+    /// This function compiles the vertex shader and the fragment shader, then links them into a
+    /// shader program; the sources don't need to be null-terminated. An error is returned if a
+    /// shader doesn't compile or if they can't be linked (see `ShaderError`).
     ///
-    pub fn new(vertex_shader_src: &[u8], fragment_shader_src: &[u8]) -> Shader {
-        let vertex_shader = compile_shader(vertex_shader_src, gl::VERTEX_SHADER)
-            .expect("Failed to compile the vertex shader");
+    pub fn new(vertex_shader_src: &[u8], fragment_shader_src: &[u8]) -> Result<Shader, ShaderError> {
+        let vertex_shader = compile_shader(vertex_shader_src, ShaderStage::Vertex)?;
 
-        let fragment_shader = compile_shader(fragment_shader_src, gl::FRAGMENT_SHADER)
-            .expect("Failed to compile the fragment shader");
+        let fragment_shader = match compile_shader(fragment_shader_src, ShaderStage::Fragment) {
+            Ok(fragment_shader) => fragment_shader,
+            Err(error) => {
+                unsafe {
+                    gl_check!(gl::DeleteShader(vertex_shader));
+                }
+                return Err(error)
+            }
+        };
 
-        let program = make_program(vec![vertex_shader, fragment_shader])
-            .expect("Failed to make the shader program");
+        let program = match make_program(vec![vertex_shader, fragment_shader]) {
+            Ok(program) => program,
+            Err(error) => {
+                unsafe {
+                    gl_check!(gl::DeleteShader(vertex_shader));
+                    gl_check!(gl::DeleteShader(fragment_shader));
+                }
+                return Err(error)
+            }
+        };
 
-        Shader {
+        Ok(Shader {
             vertex_shader: vertex_shader,
             fragment_shader: fragment_shader,
-            program: program
-        }
+            program: program,
+            locations: RefCell::new(HashMap::new()),
+            textures: Vec::new(),
+            uniform_buffers: Vec::new()
+        })
     }
 
-    /// Brief description
+    /// Returns the active uniforms.
     ///
-    /// The **set_uniform() method** is not documented yet. Pull requests are welcome.
+    /// This function returns the uniforms that are used by the shader program, with their type
+    /// and their location (see `ShaderVariable`).
     ///
-    pub fn set_uniform(&mut self, name: &str, uniform: Uniform) {
-        self.bind();
+    pub fn uniforms(&self) -> Vec<ShaderVariable> {
+        self.variables(gl::ACTIVE_UNIFORMS, gl::ACTIVE_UNIFORM_MAX_LENGTH, |program, index, capacity, length, size, kind, name| unsafe {
+            gl_check!(gl::GetActiveUniform(program, index, capacity, length, size, kind, name));
+            gl_check!(let location = gl::GetUniformLocation(program, name));
 
-        let location = unsafe {
-            gl_check!(let location = gl::GetUniformLocation(self.program, CString::new(name).unwrap().into_raw()));
+            location
+        })
+    }
+
+    /// Returns the active attributes.
+    ///
+    /// This function returns the vertex attributes that are used by the shader program, with
+    /// their type and their location (see `ShaderVariable`).
+    ///
+    pub fn attributes(&self) -> Vec<ShaderVariable> {
+        self.variables(gl::ACTIVE_ATTRIBUTES, gl::ACTIVE_ATTRIBUTE_MAX_LENGTH, |program, index, capacity, length, size, kind, name| unsafe {
+            gl_check!(gl::GetActiveAttrib(program, index, capacity, length, size, kind, name));
+            gl_check!(let location = gl::GetAttribLocation(program, name));
 
             location
-        };
+        })
+    }
+
+    /// Changes the value of a uniform.
+    ///
+    /// This function changes the value of a uniform of the shader program given its name; the
+    /// value is kept until it's changed again. Uniforms which don't exist (or which were optimized
+    /// out by the graphics driver) are silently ignored.
+    ///
+    /// A texture is bound to a texture unit of its own, which is passed to the sampler; setting the
+    /// same sampler again replaces the texture and keeps the unit. It panics if there are more
+    /// textures than the graphics driver has texture units.
+    ///
+    pub fn set_uniform(&mut self, name: &str, uniform: Uniform) {
+        let location = self.location(name);
 
         match uniform {
-            Uniform::Integer(value) => {
-                unsafe {
-                    gl_check!(gl::Uniform1i(location, value));
-                }
-            },
-            Uniform::UnsignedInteger(value) => {
-                unsafe {
-                    gl_check!(gl::Uniform1ui(location, value));
-                }
-            },
-            Uniform::Float(value) => {
-                unsafe {
-                    gl_check!(gl::Uniform1f(location, value));
-                }
-            },
-            Uniform::Vector2(x, y) => {
-                unsafe {
-                    gl_check!(gl::Uniform2f(location, x, y));
-                }
-            },
-            Uniform::Vector3(x, y, z) => {
-                unsafe {
-                    gl_check!(gl::Uniform3f(location, x, y, z));
-                }
-            },
-            Uniform::Matrix2(value) => {
-                unsafe {
-                    gl_check!(gl::UniformMatrix2fv(location, 1, gl::FALSE, value.as_ptr() as _));
+            Uniform::Texture(texture) => {
+                if location == -1 {
+                    return
                 }
+
+                let unit = match self.textures.iter().position(|(other, _)| *other == location) {
+                    Some(index) => {
+                        self.textures[index].1 = texture.shared_object();
+                        index + 1
+                    },
+                    None => {
+                        self.textures.push((location, texture.shared_object()));
+                        self.textures.len()
+                    }
+                };
+                assert!(unit < max_texture_units(), "the shader has too many textures");
+
+                self.bind();
+                upload(location, Uniform::Integer(unit as i32));
             },
-            Uniform::Matrix3(value) => {
-                unsafe {
-                    gl_check!(gl::UniformMatrix3fv(location, 1, gl::FALSE, value.as_ptr() as _));
-                }
+            _ => {
+                self.bind();
+                upload(location, uniform);
+            }
+        }
+    }
+
+    /// Uploads a uniform to the shader program.
+    ///
+    /// This function is the same as `set_uniform()` except that it doesn't borrow the shader
+    /// mutably; it's used by the surface to set the `viewport` and `model` uniforms of the shader
+    /// found in the render states. It doesn't accept textures.
+    ///
+    pub(crate) fn upload_uniform(&self, name: &str, uniform: Uniform) {
+        let location = self.location(name);
+
+        self.bind();
+        upload(location, uniform);
+    }
+
+    /// Changes the buffer of a uniform block.
+    ///
+    /// This function gives a uniform buffer to a uniform block of the shader program given its
+    /// name; the same buffer can be given to several shaders to share its values (see
+    /// `UniformBuffer`). Uniform blocks which don't exist are silently ignored.
+    ///
+    pub fn set_uniform_buffer(&mut self, name: &str, buffer: &UniformBuffer) {
+        let name = CString::new(name).unwrap();
+        let index = unsafe {
+            gl_check!(let index = gl::GetUniformBlockIndex(self.program, name.as_ptr()));
+
+            index
+        };
+
+        if index == gl::INVALID_INDEX {
+            return
+        }
+
+        let binding = match self.uniform_buffers.iter().position(|(other, _)| *other == index) {
+            Some(binding) => {
+                self.uniform_buffers[binding].1 = buffer.shared_object();
+                binding
             },
-            Uniform::Matrix4(value) => {
-                unsafe {
-                    gl_check!(gl::UniformMatrix4fv(location, 1, gl::FALSE, value.as_ptr() as _));
-                }
+            None => {
+                self.uniform_buffers.push((index, buffer.shared_object()));
+                self.uniform_buffers.len() - 1
             }
+        };
+
+        unsafe {
+            gl_check!(gl::UniformBlockBinding(self.program, index, binding as u32));
         }
     }
 
+    /// Changes the label of the shader.
+    ///
+    /// This function attaches a label to the underlying OpenGL program object; it identifies the
+    /// shader in the debug messages (see the `debug` module) and in the debugging tools.
+    ///
+    pub fn set_label(&mut self, label: &str) {
+        set_object_label(gl::PROGRAM, self.program, label);
+    }
+
     /// Brief description
     ///
     /// The **bind() method** is not documented yet. Pull requests are welcome.
@@ -187,6 +451,199 @@ impl Shader {
     pub fn bind(&self) {
         unsafe {
             gl_check!(gl::UseProgram(self.program));
+
+            for (index, (_, texture)) in self.textures.iter().enumerate() {
+                gl_check!(gl::ActiveTexture(gl::TEXTURE1 + index as u32));
+                gl_check!(gl::BindTexture(gl::TEXTURE_2D, texture.0));
+            }
+
+            // Leave the unit 0 active for the texture of the render states.
+            if !self.textures.is_empty() {
+                gl_check!(gl::ActiveTexture(gl::TEXTURE0));
+            }
+
+            for (binding, (_, buffer)) in self.uniform_buffers.iter().enumerate() {
+                gl_check!(gl::BindBufferBase(gl::UNIFORM_BUFFER, binding as u32, buffer.0));
+            }
         }
     }
+
+    // Returns the active variables of a kind; the closure queries a variable given its index and
+    // returns its location (the uniforms and the attributes are queried the same way).
+    fn variables<F>(&self, count_parameter: gl::types::GLenum, length_parameter: gl::types::GLenum, query: F) -> Vec<ShaderVariable>
+        where F: Fn(u32, u32, i32, *mut i32, *mut i32, *mut gl::types::GLenum, *mut gl::types::GLchar) -> i32
+    {
+        let (count, max_length) = unsafe {
+            let mut count = 0;
+            let mut max_length = 0;
+            gl_check!(gl::GetProgramiv(self.program, count_parameter, &mut count));
+            gl_check!(gl::GetProgramiv(self.program, length_parameter, &mut max_length));
+
+            (count, max_length)
+        };
+
+        (0..count.max(0) as u32).map(|index| {
+            let mut name = vec![0u8; max_length.max(1) as usize];
+            let mut length = 0;
+            let mut size = 0;
+            let mut kind = 0;
+
+            let location = query(self.program, index, name.len() as _, &mut length, &mut size, &mut kind, name.as_mut_ptr() as *mut _);
+
+            ShaderVariable {
+                name: gl_string(name, length),
+                kind: to_shader_type(kind),
+                size: size as usize,
+                location: location
+            }
+        }).collect()
+    }
+
+    // Returns the location of a uniform (see notes).
+    fn location(&self, name: &str) -> i32 {
+        let cached = self.locations.borrow().get(name).copied();
+        if let Some(location) = cached {
+            return location
+        }
+
+        let c_name = CString::new(name).unwrap();
+        let location = unsafe {
+            gl_check!(let location = gl::GetUniformLocation(self.program, c_name.as_ptr()));
+
+            location
+        };
+
+        self.locations.borrow_mut().insert(name.to_string(), location);
+        location
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{Position, Size};
+    use crate::image::Color;
+    use crate::draw::{Texture, Vertex, VertexArray, Primitive, Usage, RenderStates};
+    use crate::draw::testing::{render, pixel};
+
+    const VERTEX_SHADER_SRC: &[u8] = b"
+#version 300 es
+precision mediump float;
+
+layout(location = 0) in vec2 vertex_position;
+
+uniform mat3 model;
+uniform mat4 viewport;
+
+void main() {
+    gl_Position = viewport * vec4((model * vec3(vertex_position, 1.0)), 1.0);
+}
+\0";
+
+    const FRAGMENT_SHADER_SRC: &[u8] = b"
+#version 300 es
+precision mediump float;
+
+out vec4 out_color;
+
+uniform vec4 tint;
+uniform float factors[2];
+uniform sampler2D extra_texture;
+
+layout(std140) uniform Shared {
+    vec4 offset;
+};
+
+void main() {
+    out_color = texture(extra_texture, vec2(0.5)) * tint * factors[0] * factors[1] + offset;
+}
+\0";
+
+    #[test]
+    fn shader_set_uniform() {
+        let image = render(Size::new(4, 4), |surface| {
+            let texture = Texture::with_size(Size::new(1, 1), Color::GREEN);
+
+            let mut shared = UniformBuffer::new(16);
+            shared.update(0, &[[0.0f32, 0.0, 1.0, 0.0]]);
+
+            let mut shader = Shader::new(VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC).unwrap();
+            shader.set_uniform("tint", Uniform::Color(Color::WHITE));
+            shader.set_uniform("factors", Uniform::FloatArray(&[0.5, 2.0]));
+            shader.set_uniform("extra_texture", Uniform::Texture(&texture));
+            shader.set_uniform_buffer("Shared", &shared);
+            shader.set_uniform("unknown", Uniform::Float(1.0));
+
+            // The shader keeps the texture and the uniform buffer alive.
+            drop(texture);
+            drop(shared);
+
+            let quad = vec![
+                Vertex::with_position_and_color(Position::new(0.0, 0.0), Color::WHITE),
+                Vertex::with_position_and_color(Position::new(4.0, 0.0), Color::WHITE),
+                Vertex::with_position_and_color(Position::new(0.0, 4.0), Color::WHITE),
+                Vertex::with_position_and_color(Position::new(4.0, 4.0), Color::WHITE)
+            ];
+            let vertex_array = VertexArray::with_vertices(&quad, Primitive::TriangleStrips, Usage::Static);
+
+            let states = RenderStates {
+                shader: Some(&shader),
+                ..RenderStates::default()
+            };
+            surface.erase(Color::BLACK);
+            surface.draw_vertices(&vertex_array, &states);
+        });
+
+        assert_eq!(pixel(&image, 2, 2), Color::rgb(0, 255, 255));
+    }
+
+    #[test]
+    fn shader_new_errors() {
+        render(Size::new(1, 1), |_| {
+            let broken = b"#version 300 es\nprecision mediump float;\n\nvoid main() {\n    undeclared = 1.0;\n}\n\0";
+            match Shader::new(VERTEX_SHADER_SRC, broken).err().unwrap() {
+                ShaderError::Compile { stage, lines, log } => {
+                    assert_eq!(stage, ShaderStage::Fragment);
+                    assert_eq!(lines, vec![5]);
+                    assert!(!log.is_empty());
+                },
+                error => panic!("unexpected error {:?}", error)
+            }
+
+            let error = Shader::new(broken, FRAGMENT_SHADER_SRC).err().unwrap();
+            assert!(matches!(error, ShaderError::Compile { stage: ShaderStage::Vertex, .. }));
+
+            // The fragment shader reads a varying that the vertex shader doesn't write.
+            let reading = b"#version 300 es\nprecision mediump float;\nin vec4 missing;\nout vec4 out_color;\nvoid main() {\n    out_color = missing;\n}\n\0";
+            let error = Shader::new(VERTEX_SHADER_SRC, reading).err().unwrap();
+            assert!(matches!(error, ShaderError::Link { .. }));
+        });
+    }
+
+    #[test]
+    fn shader_reflection() {
+        render(Size::new(1, 1), |_| {
+            let shader = Shader::new(VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC).unwrap();
+
+            let attributes = shader.attributes();
+            assert_eq!(attributes, vec![ShaderVariable {
+                name: String::from("vertex_position"),
+                kind: ShaderType::Vector2,
+                size: 1,
+                location: 0
+            }]);
+
+            let mut uniforms = shader.uniforms();
+            uniforms.sort_by(|a, b| a.name.cmp(&b.name));
+
+            let names: Vec<&str> = uniforms.iter().map(|uniform| uniform.name.as_str()).collect();
+            assert_eq!(names, vec!["extra_texture", "factors[0]", "model", "offset", "tint", "viewport"]);
+
+            assert_eq!(uniforms[0].kind, ShaderType::Sampler2D);
+            assert_eq!((uniforms[1].kind, uniforms[1].size), (ShaderType::Float, 2));
+            assert_eq!(uniforms[2].kind, ShaderType::Matrix3);
+            assert_eq!(uniforms[3].location, -1);
+            assert_ne!(uniforms[4].location, -1);
+        });
+    }
 }
@@ -0,0 +1,130 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+use std::fmt;
+use crate::draw::ShaderStage;
+
+// Extract the line numbers from the log of the graphics driver; the drivers prefix their messages
+// with the number of the source string and the line number, either as '0:12' (Mesa, ANGLE) or as
+// '0(12)' (NVIDIA), possibly after the severity.
+pub(crate) fn log_lines(log: &str) -> Vec<u32> {
+    let mut lines = Vec::new();
+
+    for message in log.lines() {
+        let message = message.trim_start();
+        let message = ["ERROR: ", "WARNING: "].iter()
+            .find_map(|prefix| message.strip_prefix(prefix))
+            .unwrap_or(message);
+
+        let source_length = message.find(|c: char| !c.is_ascii_digit()).unwrap_or(message.len());
+        if source_length == 0 {
+            continue
+        }
+
+        let rest = &message[source_length..];
+        let rest = match rest.strip_prefix(':').or_else(|| rest.strip_prefix('(')) {
+            Some(rest) => rest,
+            None => continue
+        };
+
+        let line_length = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        if let Ok(line) = rest[..line_length].parse::<u32>() {
+            if !lines.contains(&line) {
+                lines.push(line);
+            }
+        }
+    }
+
+    lines.sort_unstable();
+    lines
+}
+
+/// An error of shader program creation.
+///
+/// A shader program can't be created if one of its shaders doesn't compile, or if its shaders
+/// can't be linked together (see `Shader::new()`). The error carries the log of the graphics
+/// driver; when a shader doesn't compile, it also carries the stage of the shader and the line
+/// numbers mentioned in the log, so the errors can be shown next to the source code.
+///
+/// ```no_run
+/// # use byteplug::draw::{Shader, ShaderError, ShaderStage};
+/// # const VERTEX_SHADER_SRC: &[u8] = b"";
+/// # const FRAGMENT_SHADER_SRC: &[u8] = b"";
+/// # fn report(_stage: ShaderStage, _lines: &[u32], _log: &str) {}
+/// # let mut materials = Vec::new();
+/// match Shader::new(VERTEX_SHADER_SRC, FRAGMENT_SHADER_SRC) {
+///     Ok(shader) => materials.push(shader),
+///     Err(ShaderError::Compile { stage, lines, log }) => report(stage, &lines, &log),
+///     Err(error) => println!("{}", error)
+/// }
+/// ```
+///
+/// **Implementation notes**
+///
+/// - The format of the log isn't standardized; the line numbers are extracted from the formats of
+///   the common graphics drivers, and the list is empty if the format isn't recognized.
+///
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum ShaderError {
+    /// A shader doesn't compile.
+    Compile {
+        stage: ShaderStage,
+        lines: Vec<u32>,
+        log: String
+    },
+    /// The shaders can't be linked together.
+    Link {
+        log: String
+    }
+}
+
+impl ShaderError {
+    /// Returns the log of the graphics driver.
+    pub fn log(&self) -> &str {
+        match self {
+            ShaderError::Compile { log, .. } => log,
+            ShaderError::Link { log } => log
+        }
+    }
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShaderError::Compile { stage, log, .. } => write!(f, "failed to compile the {:?} shader: {}", stage, log),
+            ShaderError::Link { log } => write!(f, "failed to link the shader program: {}", log)
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shader_error_log_lines() {
+        let mesa = "0:12(5): error: `color' undeclared\n0:3(1): error: syntax error\n0:12(9): warning: unused";
+        assert_eq!(log_lines(mesa), vec![3, 12]);
+
+        let angle = "ERROR: 0:7: 'vec5' : no matching overloaded function found\nERROR: 1 compilation errors.";
+        assert_eq!(log_lines(angle), vec![7]);
+
+        let nvidia = "0(21) : error C0000: syntax error, unexpected '}'";
+        assert_eq!(log_lines(nvidia), vec![21]);
+
+        assert_eq!(log_lines("error: linking failed"), Vec::<u32>::new());
+    }
+
+    #[test]
+    fn shader_error_log() {
+        let error = ShaderError::Link { log: String::from("no main") };
+        assert_eq!(error.log(), "no main");
+        assert_eq!(error.to_string(), "failed to link the shader program: no main");
+    }
+}
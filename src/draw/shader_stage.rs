@@ -0,0 +1,18 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+/// The stages of a shader program.
+///
+/// A shader program is made of a shader per stage of the graphics pipeline; the vertex shader
+/// computes the position of the vertices, and the fragment shader computes the color of the
+/// pixels. See `ShaderError`.
+///
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment
+}
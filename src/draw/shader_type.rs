@@ -0,0 +1,34 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+/// The GLSL types of the shader variables.
+///
+/// These are the types of the active uniforms and attributes of a shader program, as reported by
+/// the graphics driver (see `ShaderVariable`). The types the framework doesn't name are reported
+/// with the value of their OpenGL enumeration.
+///
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ShaderType {
+    Boolean,
+    Integer,
+    UnsignedInteger,
+    Float,
+    Vector2,
+    Vector3,
+    Vector4,
+    IntegerVector2,
+    IntegerVector3,
+    IntegerVector4,
+    UnsignedIntegerVector2,
+    UnsignedIntegerVector3,
+    UnsignedIntegerVector4,
+    Matrix2,
+    Matrix3,
+    Matrix4,
+    Sampler2D,
+    Other(u32)
+}
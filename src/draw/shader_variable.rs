@@ -0,0 +1,40 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+use crate::draw::ShaderType;
+
+/// An active variable of a shader program.
+///
+/// A shader variable describes a uniform or an attribute that is used by a shader program (see
+/// `Shader::uniforms()` and `Shader::attributes()`); the variables which are declared but not
+/// used are removed by the graphics driver and aren't reported.
+///
+/// ```no_run
+/// # use byteplug::draw::Shader;
+/// # let shader = Shader::new(b"", b"").unwrap();
+/// for uniform in shader.uniforms() {
+///     println!("{} ({:?}) at location {}", uniform.name, uniform.kind, uniform.location);
+/// }
+/// ```
+///
+/// **Implementation notes**
+///
+/// - The name of an array is reported with the index of its first element (for instance,
+///   `lights[0]`), and its size is the number of elements.
+/// - The uniforms of a uniform block have no location; it's `-1`.
+///
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct ShaderVariable {
+    /// The name of the variable.
+    pub name: String,
+    /// The type of the variable.
+    pub kind: ShaderType,
+    /// The number of elements (one if it's not an array).
+    pub size: usize,
+    /// The location of the variable.
+    pub location: i32
+}
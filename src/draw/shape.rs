@@ -12,7 +12,7 @@ use crate::image::Color;
 use crate::draw::{Surface, Texture};
 use crate::draw::{Primitive, Usage};
 use crate::draw::{Vertex, VertexArray};
-use crate::draw::Drawable;
+use crate::draw::{Drawable, RenderStates};
 
 fn compute_normal(p1: &Position<f32>, p2: &Position<f32>) -> Position<f32> {
     // Compute the dot product of two vectors.
@@ -121,9 +121,9 @@ fn dot_product(p1: &Position<f32>, p2: &Position<f32>) -> f32 {
 /// - An `update()` method is available to synchronize the system memory with the graphics memory
 ///   but it's not mandatory as it's done before the shape is used for drawing, if it's not done
 ///   yet.
-/// - Implementation will not change when the vertex array implementation changes to use mapped
-///   memory technique (this note should be removed after the vertex array implementation is
-///   updated).
+/// - The vertex arrays have the stream usage, and updating them re-uses their graphics memory (see
+///   `VertexArray::update_vertices()`); an animated shape doesn't re-allocate it every frame,
+///   unless its number of points grows.
 /// - The implementation supports convex shapes only. It will be updated later to support concave
 ///   shapes.
 /// - The implementation doesn't support texture yet. It will be implemented later.
@@ -524,10 +524,15 @@ impl<'a> Transformable for Shape<'a> {
 }
 
 impl<'a> Drawable for Shape<'a> {
-    fn draw(&self, surface: &mut Surface) {
+    fn draw(&self, surface: &mut Surface, states: &RenderStates) {
+        let states = RenderStates {
+            texture: self.texture.or(states.texture),
+            ..states.transformed(self.matrix())
+        };
+
         // Draw the shape vertices first, then the outline vertices.
-        surface.draw_vertices(&self.vertices, self.texture, Some(self.matrix()));
-        surface.draw_vertices(&self.outline_vertices, self.texture, Some(self.matrix()));
+        surface.draw_vertices(&self.vertices, &states);
+        surface.draw_vertices(&self.outline_vertices, &states);
     }
 }
 
@@ -535,8 +540,60 @@ impl<'a> Drawable for Shape<'a> {
 mod tests {
     use super::*;
 
+    use crate::geometry::Size;
+    use crate::draw::testing::{render, assert_reference_image, pixel};
+
     #[test]
     fn shape() {
-        // To be implemented.
+        // The outline is drawn around the points.
+        let image = render(Size::new(32, 32), |surface| {
+            let points = vec![
+                Position::new(8.0, 8.0),
+                Position::new(24.0, 8.0),
+                Position::new(24.0, 24.0),
+                Position::new(8.0, 24.0)
+            ];
+
+            let mut shape = Shape::with_points(&points);
+            shape.set_color(Color::RED);
+            shape.set_outline_color(Color::BLUE);
+            shape.set_outline_thickness(4.0);
+            shape.update();
+
+            surface.erase(Color::BLACK);
+            surface.draw(&shape);
+        });
+
+        assert_eq!(pixel(&image, 2, 16), Color::BLACK);
+        assert_eq!(pixel(&image, 5, 16), Color::BLUE);
+        assert_eq!(pixel(&image, 16, 26), Color::BLUE);
+        assert_eq!(pixel(&image, 16, 16), Color::RED);
+        assert_eq!(pixel(&image, 29, 29), Color::BLACK);
+    }
+
+    #[test]
+    fn shape_draw() {
+        let image = render(Size::new(32, 32), |surface| {
+            let points = vec![
+                Position::new(8.0, 8.0),
+                Position::new(24.0, 8.0),
+                Position::new(24.0, 24.0),
+                Position::new(8.0, 24.0)
+            ];
+
+            let mut shape = Shape::with_points(&points);
+            shape.set_color(Color::RED);
+            shape.update();
+
+            surface.erase(Color::BLACK);
+            surface.draw(&shape);
+        });
+
+        assert_eq!(pixel(&image, 7, 7), Color::BLACK);
+        assert_eq!(pixel(&image, 8, 8), Color::RED);
+        assert_eq!(pixel(&image, 23, 23), Color::RED);
+        assert_eq!(pixel(&image, 24, 24), Color::BLACK);
+
+        assert_reference_image(&image, "shape_draw", 0);
     }
 }
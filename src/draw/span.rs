@@ -0,0 +1,94 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+use crate::geometry::Position;
+use crate::image::Color;
+use crate::draw::Font;
+
+/// A styled run of a text.
+///
+/// A span is a portion of a text with its own style; a text is made of a sequence of spans which
+/// are laid out as one block. It allows to mix styles within a single text, for instance, to
+/// highlight a keyword with a bold font, or to underline a link.
+///
+/// The font, the character size and the color are optional; when they're not set, the span
+/// inherits the ones of the text. The other properties are specific to the span.
+///
+/// ```no_run
+/// # use byteplug::image::Color;
+/// # use byteplug::draw::{Font, Text, Span};
+/// # let font = Font::open("resources/DejaVuSans.ttf").unwrap();
+/// # let mut text = Text::with_string("", &font, 24);
+/// let spans = vec![
+///     Span::new("Click "),
+///     Span { underlined: true, color: Some(Color::BLUE), ..Span::new("here") },
+///     Span { character_size: Some(32), color: Some(Color::RED), ..Span::new(" to continue.") }
+/// ];
+///
+/// text.set_spans(spans);
+/// ```
+///
+/// The glow and the shadow are only drawn if the font is in the distance field rendering mode (see
+/// `Rendering`); they're computed by the shader.
+///
+/// **Implementation notes**
+///
+/// - The outline is drawn below the glyphs; underlines and strikethroughs aren't outlined.
+/// - In the distance field rendering mode, the thickness of the outline, the radius of the glow
+///   and the offset of the shadow are limited to the spread of the distance field.
+///
+#[derive(Clone)]
+pub struct Span<'a> {
+    /// The string of the span.
+    pub string: String,
+    /// The font of the span (inherited from the text if it's none).
+    pub font: Option<&'a Font>,
+    /// The character size of the span (inherited from the text if it's none).
+    pub character_size: Option<u32>,
+    /// The fill color of the span (inherited from the text if it's none).
+    pub color: Option<Color>,
+    /// Whether the span is underlined.
+    pub underlined: bool,
+    /// Whether the span is struck through.
+    pub strikethrough: bool,
+    /// The thickness of the outline of the glyphs (in pixels), zero if there is no outline.
+    pub outline_thickness: f32,
+    /// The color of the outline of the glyphs.
+    pub outline_color: Color,
+    /// The radius of the glow around the glyphs (in pixels), zero if there is no glow.
+    pub glow_radius: f32,
+    /// The color of the glow around the glyphs.
+    pub glow_color: Color,
+    /// The offset of the shadow of the glyphs (in pixels).
+    pub shadow_offset: Position,
+    /// The color of the shadow of the glyphs, transparent if there is no shadow.
+    pub shadow_color: Color
+}
+
+impl<'a> Span<'a> {
+    /// Constructs a span from a string.
+    ///
+    /// This function constructs a span with no style of its own; it inherits the font, the
+    /// character size and the color of the text, and it has no decoration and no outline.
+    ///
+    pub fn new(string: &str) -> Span<'a> {
+        Span {
+            string: String::from(string),
+            font: None,
+            character_size: None,
+            color: None,
+            underlined: false,
+            strikethrough: false,
+            outline_thickness: 0.0,
+            outline_color: Color::BLACK,
+            glow_radius: 0.0,
+            glow_color: Color::WHITE,
+            shadow_offset: Position::zero(),
+            shadow_color: Color::TRANSPARENT
+        }
+    }
+}
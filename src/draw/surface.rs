@@ -5,6 +5,7 @@
 //
 // Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, January 2020
 
+use std::ops::Range;
 use winit::dpi::PhysicalSize;
 use glutin::{
     GlProfile, GlRequest, Api,
@@ -14,16 +15,51 @@ use glutin::{
 };
 use crate::geometry::{Position, Size, Box};
 use crate::geometry::Matrix;
-use crate::image::Color;
+use crate::image::{Color, Image};
 use crate::draw::context::get_or_create_context;
-use crate::draw::{gl, Options};
-use crate::draw::{Texture, VertexArray};
-use crate::draw::Uniform;
-use crate::draw::default_shader::get_or_create_default_shader;
+use crate::draw::{gl, Options, SurfaceError};
+use crate::draw::{Texture, VertexArray, VertexLayout};
+use crate::draw::{Shader, Uniform};
+use crate::draw::{BlendFactor, BlendEquation, BlendMode};
+use crate::draw::default_shader::{get_or_create_default_shader, get_or_create_instanced_shader};
+use crate::draw::debug::enable_debug_output;
 use crate::draw::View;
-use crate::draw::Drawable;
+use crate::draw::{Drawable, RenderStates};
 use crate::application::get_or_create_event_loop;
 
+fn from_blend_factor(factor: BlendFactor) -> gl::types::GLenum {
+    match factor {
+        BlendFactor::Zero                     => gl::ZERO,
+        BlendFactor::One                      => gl::ONE,
+        BlendFactor::SourceColor              => gl::SRC_COLOR,
+        BlendFactor::OneMinusSourceColor      => gl::ONE_MINUS_SRC_COLOR,
+        BlendFactor::DestinationColor         => gl::DST_COLOR,
+        BlendFactor::OneMinusDestinationColor => gl::ONE_MINUS_DST_COLOR,
+        BlendFactor::SourceAlpha              => gl::SRC_ALPHA,
+        BlendFactor::OneMinusSourceAlpha      => gl::ONE_MINUS_SRC_ALPHA,
+        BlendFactor::DestinationAlpha         => gl::DST_ALPHA,
+        BlendFactor::OneMinusDestinationAlpha => gl::ONE_MINUS_DST_ALPHA
+    }
+}
+
+fn from_blend_equation(equation: BlendEquation) -> gl::types::GLenum {
+    match equation {
+        BlendEquation::Add             => gl::FUNC_ADD,
+        BlendEquation::Subtract        => gl::FUNC_SUBTRACT,
+        BlendEquation::ReverseSubtract => gl::FUNC_REVERSE_SUBTRACT,
+        BlendEquation::Min             => gl::MIN,
+        BlendEquation::Max             => gl::MAX
+    }
+}
+
+// Reverse the order of the rows of pixels (OpenGL reads them from the bottom to the top).
+fn flip_rows(pixels: &mut [Color], size: Size<usize>) {
+    for row in 0..size.height / 2 {
+        let (top, bottom) = pixels.split_at_mut((size.height - row - 1) * size.width);
+        top[row * size.width..(row + 1) * size.width].swap_with_slice(&mut bottom[..size.width]);
+    }
+}
+
 fn make_default_texture() -> Texture {
     // There must always be a current texture when using the default shader. This simply is a 1x1
     // white texture because white is the identity color (won't change the vertex color after
@@ -34,6 +70,240 @@ fn make_default_texture() -> Texture {
     texture
 }
 
+// Compute the format and the attachment point of the renderbuffer holding the depth and stencil
+// buffers of a non-window surface.
+fn depth_stencil_format(depth_bits: u8, stencil_bits: u8) -> Option<(gl::types::GLenum, gl::types::GLenum)> {
+    match (depth_bits, stencil_bits) {
+        (0, 0) => None,
+        (0, _) => Some((gl::STENCIL_INDEX8, gl::STENCIL_ATTACHMENT)),
+        (depth, 0) if depth <= 16 => Some((gl::DEPTH_COMPONENT16, gl::DEPTH_ATTACHMENT)),
+        (depth, 0) if depth <= 24 => Some((gl::DEPTH_COMPONENT24, gl::DEPTH_ATTACHMENT)),
+        (_, 0) => Some((gl::DEPTH_COMPONENT32F, gl::DEPTH_ATTACHMENT)),
+        (depth, _) if depth <= 24 => Some((gl::DEPTH24_STENCIL8, gl::DEPTH_STENCIL_ATTACHMENT)),
+        (_, _) => Some((gl::DEPTH32F_STENCIL8, gl::DEPTH_STENCIL_ATTACHMENT))
+    }
+}
+
+// Query a parameter of an attachment of the bound framebuffer (zero if there is no attachment).
+fn attachment_parameter(attachment: gl::types::GLenum, parameter: gl::types::GLenum) -> i32 {
+    unsafe {
+        let mut object_type = 0;
+        gl_check!(gl::GetFramebufferAttachmentParameteriv(
+            gl::FRAMEBUFFER,
+            attachment,
+            gl::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE,
+            &mut object_type
+        ));
+
+        if object_type as gl::types::GLenum == gl::NONE {
+            return 0
+        }
+
+        let mut value = 0;
+        gl_check!(gl::GetFramebufferAttachmentParameteriv(
+            gl::FRAMEBUFFER,
+            attachment,
+            parameter,
+            &mut value
+        ));
+
+        value
+    }
+}
+
+/// The framebuffer of a non-window surface
+///
+/// The texture is attached to the main framebuffer. If the surface is multisampled, it draws on
+/// a second framebuffer whose attachments are multisampled renderbuffers, and which is resolved
+/// into the main framebuffer (and thus the texture) on demand. The depth and stencil buffers are
+/// attached to the framebuffer which is drawn on.
+///
+/// Note that the OpenGL context of the surface must be current when it's used.
+///
+struct Framebuffer {
+    texture: Texture,
+    frame_buffer: u32,
+    multisample_frame_buffer: u32, // zero if the surface isn't multisampled
+    color_buffer: u32,             // zero if the surface isn't multisampled
+    depth_stencil_buffer: u32,     // zero if there is no depth and stencil buffers
+    samples: i32,
+    srgb: bool
+}
+
+impl Framebuffer {
+    fn new(texture: Texture, size: Size<i32>, options: &Options) -> Result<Framebuffer, SurfaceError> {
+        let samples = unsafe {
+            let mut maximum_samples = 0;
+            gl_check!(gl::GetIntegerv(gl::MAX_SAMPLES, &mut maximum_samples));
+
+            (options.antialiasing_level as i32).min(maximum_samples)
+        };
+
+        let mut framebuffer = Framebuffer {
+            texture: texture,
+            frame_buffer: 0,
+            multisample_frame_buffer: 0,
+            color_buffer: 0,
+            depth_stencil_buffer: 0,
+            samples: samples,
+            srgb: options.srgb
+        };
+
+        let depth_stencil = depth_stencil_format(options.depth_bits, options.stencil_bits);
+
+        unsafe {
+            gl_check!(gl::GenFramebuffers(1, &mut framebuffer.frame_buffer));
+
+            if samples > 0 {
+                gl_check!(gl::GenFramebuffers(1, &mut framebuffer.multisample_frame_buffer));
+                gl_check!(gl::GenRenderbuffers(1, &mut framebuffer.color_buffer));
+            }
+
+            if depth_stencil.is_some() {
+                gl_check!(gl::GenRenderbuffers(1, &mut framebuffer.depth_stencil_buffer));
+            }
+        }
+
+        framebuffer.allocate(size, depth_stencil);
+
+        unsafe {
+            gl_check!(gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer.frame_buffer));
+            gl_check!(gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                framebuffer.texture.object(),
+                0
+            ));
+
+            if samples > 0 {
+                gl_check!(gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer.multisample_frame_buffer));
+                gl_check!(gl::FramebufferRenderbuffer(
+                    gl::FRAMEBUFFER,
+                    gl::COLOR_ATTACHMENT0,
+                    gl::RENDERBUFFER,
+                    framebuffer.color_buffer
+                ));
+            }
+
+            if let Some((_, attachment)) = depth_stencil {
+                gl_check!(gl::FramebufferRenderbuffer(
+                    gl::FRAMEBUFFER,
+                    attachment,
+                    gl::RENDERBUFFER,
+                    framebuffer.depth_stencil_buffer
+                ));
+            }
+        }
+
+        // An empty framebuffer is always incomplete; it's checked once the surface is resized.
+        if size.width > 0 && size.height > 0 {
+            if let Err(error) = framebuffer.check() {
+                framebuffer.delete();
+                return Err(error)
+            }
+        }
+
+        Ok(framebuffer)
+    }
+
+    // Check whether the graphics driver supports the combination of formats and samples.
+    fn check(&self) -> Result<(), SurfaceError> {
+        let frame_buffers = [self.frame_buffer, self.multisample_frame_buffer];
+        for frame_buffer in frame_buffers.iter().filter(|frame_buffer| **frame_buffer != 0) {
+            let status = unsafe {
+                gl_check!(gl::BindFramebuffer(gl::FRAMEBUFFER, *frame_buffer));
+                gl_check!(let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER));
+
+                status
+            };
+
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(SurfaceError::IncompleteFramebuffer(status))
+            }
+        }
+
+        Ok(())
+    }
+
+    // (Re-)allocate the storage of the texture and the renderbuffers; they stay attached.
+    fn allocate(&mut self, size: Size<i32>, depth_stencil: Option<(gl::types::GLenum, gl::types::GLenum)>) {
+        let format = if self.srgb { gl::SRGB8_ALPHA8 } else { gl::RGBA8 };
+
+        self.texture.bind();
+        unsafe {
+            gl_check!(gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                format as _,
+                size.width,
+                size.height,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                std::ptr::null()
+            ));
+
+            if self.color_buffer != 0 {
+                gl_check!(gl::BindRenderbuffer(gl::RENDERBUFFER, self.color_buffer));
+                gl_check!(gl::RenderbufferStorageMultisample(gl::RENDERBUFFER, self.samples, format, size.width, size.height));
+            }
+
+            if let Some((format, _)) = depth_stencil {
+                gl_check!(gl::BindRenderbuffer(gl::RENDERBUFFER, self.depth_stencil_buffer));
+                gl_check!(gl::RenderbufferStorageMultisample(gl::RENDERBUFFER, self.samples, format, size.width, size.height));
+            }
+        }
+    }
+
+    // Bind the framebuffer which is drawn on.
+    fn bind(&self) {
+        let frame_buffer = match self.multisample_frame_buffer {
+            0 => self.frame_buffer,
+            multisample_frame_buffer => multisample_frame_buffer
+        };
+
+        unsafe {
+            gl_check!(gl::BindFramebuffer(gl::FRAMEBUFFER, frame_buffer));
+        }
+    }
+
+    // Copy the content of the multisampled framebuffer into the texture.
+    fn resolve(&self, size: Size<i32>) {
+        if self.multisample_frame_buffer == 0 {
+            return
+        }
+
+        unsafe {
+            gl_check!(gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.multisample_frame_buffer));
+            gl_check!(gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, self.frame_buffer));
+            gl_check!(gl::BlitFramebuffer(
+                0, 0, size.width, size.height,
+                0, 0, size.width, size.height,
+                gl::COLOR_BUFFER_BIT,
+                gl::NEAREST
+            ));
+        }
+
+        self.bind();
+    }
+
+    fn delete(&mut self) {
+        unsafe {
+            gl_check!(gl::DeleteFramebuffers(1, &self.frame_buffer));
+
+            if self.multisample_frame_buffer != 0 {
+                gl_check!(gl::DeleteFramebuffers(1, &self.multisample_frame_buffer));
+                gl_check!(gl::DeleteRenderbuffers(1, &self.color_buffer));
+            }
+
+            if self.depth_stencil_buffer != 0 {
+                gl_check!(gl::DeleteRenderbuffers(1, &self.depth_stencil_buffer));
+            }
+        }
+    }
+}
+
 /// The underlying glutin context type
 ///
 /// The definition of the OpenGL context as per the **glutin** module will be different. In the case
@@ -78,13 +348,14 @@ enum UnderlyingContext {
 ///   matters in their case is the framebuffer that is created later.
 /// - The notion of surface size overlaps with the notion of window size. It's the user's
 ///   responsibility to resize the surface according to the window size.
-/// - Implement the destructor (free OpenGL objects as described in the docs).
+/// - The color attachment of the framebuffer of a non-window surface is a texture, so what's drawn
+///   on it can be used in subsequent drawing operations (see `texture()`).
 ///
 #[allow(dead_code)]
 pub struct Surface {
     context: Option<UnderlyingContext>, // shouldn't be a Option, but the make_current() methods consume themselves
-    render_buffer: u32, // not used in the case of a window surface
-    frame_buffer: u32,  // not used in the case of a window surface
+    framebuffer: Option<Framebuffer>, // not used in the case of a window surface
+    options: Options, // the requested options
     size: Size<i32>,
     view: View,
     default_texture: Texture
@@ -95,63 +366,67 @@ impl Surface {
     ///
     /// The **new() function** is not documented yet. Pull requests are welcome.
     ///
-    pub fn new(size: Size<i32>, _options: Options) -> Surface {
+    /// An error is returned if the OpenGL context can't be created, or if the framebuffer isn't
+    /// supported with the given options (see `SurfaceError`).
+    ///
+    pub fn new(size: Size<i32>, options: Options) -> Result<Surface, SurfaceError> {
 
         let event_loop = get_or_create_event_loop();
         let shared_context = get_or_create_context();
 
+        // The other options apply to the framebuffer which is created later.
         let context_builder = ContextBuilder::new()
             .with_gl(GlRequest::Specific(Api::OpenGlEs, (3, 2)))
             .with_gl_profile(GlProfile::Core)
+            .with_gl_debug_flag(options.debug)
             .with_srgb(false)
             .with_multisampling(0)
             .with_shared_lists(shared_context);
 
         let size_one = PhysicalSize::new(1, 1);
-        let context = context_builder.build_headless(&event_loop, size_one).unwrap();
-
-        let mut render_buffer = 0;
-        let mut frame_buffer = 0;
-        unsafe {
-            gl_check!(gl::GenRenderbuffers(1, &mut render_buffer));
-            gl_check!(gl::BindRenderbuffer(gl::RENDERBUFFER, render_buffer));
-            gl_check!(gl::RenderbufferStorage(
-                gl::RENDERBUFFER,
-                gl::RGB8,
-                size.width as _,
-                size.height as _,
-            ));
-            gl_check!(gl::GenFramebuffers(1, &mut frame_buffer));
-            gl_check!(gl::BindFramebuffer(gl::FRAMEBUFFER, frame_buffer));
-            gl_check!(gl::FramebufferRenderbuffer(
-                gl::FRAMEBUFFER,
-                gl::COLOR_ATTACHMENT0,
-                gl::RENDERBUFFER,
-                render_buffer,
-            ));
+        let context = context_builder.build_headless(&event_loop, size_one)
+            .map_err(|error| SurfaceError::Context(error.to_string()))?;
 
-            gl_check!(gl::Viewport(0, 0, size.width as _, size.height as _));
-        }
+        // The color attachment is a texture (textures are shared between the OpenGL contexts, the
+        // framebuffer isn't, therefore it's created once the context of the surface is current).
+        let texture = Texture::new();
 
         // fix this when a solution to cast Size to different T type is found
         let view_size = Size::<f32>::new(size.width as f32, size.height as f32);
         let view = View::with_box(Box::new(Position::zero(), view_size));
 
-        Surface {
+        let mut surface = Surface {
             context: Some(UnderlyingContext::NoWindow(context)),
-            render_buffer: render_buffer,
-            frame_buffer: frame_buffer,
+            framebuffer: None,
+            options: options,
             size: size,
             view: view,
             default_texture: make_default_texture()
+        };
+
+        surface.activate();
+        if options.debug {
+            enable_debug_output();
+        }
+
+        let framebuffer = Framebuffer::new(texture, size, &options)?;
+        framebuffer.bind();
+        surface.framebuffer = Some(framebuffer);
+
+        unsafe {
+            gl_check!(gl::Viewport(0, 0, size.width as _, size.height as _));
         }
+
+        Ok(surface)
     }
 
     /// Brief description
     ///
     /// The **from_window() function** is not documented yet. Pull requests are welcome.
     ///
-    pub fn from_window(context: RawContext<NotCurrent>, size: Size<i32>) -> Surface {
+    /// The options are the ones the OpenGL context was requested with.
+    ///
+    pub fn from_window(context: RawContext<NotCurrent>, size: Size<i32>, options: Options) -> Surface {
         unsafe {
             gl_check!(gl::Viewport(0, 0, size.width as _, size.height as _));
         }
@@ -160,20 +435,31 @@ impl Surface {
         let view_size = Size::<f32>::new(size.width as f32, size.height as f32);
         let view = View::with_box(Box::new(Position::zero(), view_size));
 
-        Surface {
+        let mut surface = Surface {
             context: Some(UnderlyingContext::WithWindow(context)),
-            render_buffer: 0, // not used
-            frame_buffer: 0,  // not used
+            framebuffer: None, // not used
+            options: options,
             size: size,
             view: view,
             default_texture: make_default_texture()
+        };
+
+        if options.debug {
+            surface.activate();
+            enable_debug_output();
         }
+
+        surface
     }
 
     /// Brief description
     ///
     /// The **resize() function** is not documented yet. Pull requests are welcome.
     ///
+    /// The framebuffer of a non-window surface created with an empty size isn't checked until the
+    /// surface is resized; this function panics if it isn't supported with the options of the
+    /// surface (see `SurfaceError`).
+    ///
     pub fn resize(&mut self, size: Size<i32>) {
         // The code is a little bit tricky because make_current() methods of the context consume
         // the context, but we only have a reference to self, and thus a reference to the context
@@ -190,7 +476,22 @@ impl Surface {
                     self.size = size;
                     self.view = self.default_view();
 
-                    // todo: destroy the generated buffer and re-create it
+                    // Re-allocating the texture and the renderbuffers keeps them attached to the
+                    // framebuffer.
+                    let depth_stencil = depth_stencil_format(self.options.depth_bits, self.options.stencil_bits);
+
+                    let framebuffer = self.framebuffer.as_mut().unwrap();
+                    framebuffer.allocate(size, depth_stencil);
+
+                    if size.width > 0 && size.height > 0 {
+                        if let Err(error) = framebuffer.check() {
+                            panic!("{}", error);
+                        }
+                    }
+
+                    framebuffer.bind();
+
+                    gl_check!(gl::Viewport(0, 0, size.width as _, size.height as _));
 
                     current_context.treat_as_not_current()
                 };
@@ -245,6 +546,130 @@ impl Surface {
         Box::new(Position::new(x as i32, y as i32), Size::new(width as i32, height as i32))
     }
 
+    /// Captures the content of the surface.
+    ///
+    /// This function copies the pixels of the surface from the graphics memory into an image; the
+    /// first row of the image is the top of the surface. It works with both window and non-window
+    /// surfaces, and it's useful to take screenshots and to compare the drawing operations against
+    /// reference images.
+    ///
+    /// On a window surface, the pixels are read from the back buffer (the one being drawn); its
+    /// content is undefined after the buffers are swapped, so the surface must be captured before
+    /// calling `swap()`.
+    ///
+    /// ```no_run
+    /// # use byteplug::image::Color;
+    /// # use byteplug::application::Window;
+    /// # fn draw_frame<T>(window: &mut Window<T>) {
+    /// let surface = window.surface();
+    /// surface.erase(Color::BLACK);
+    /// // draw the frame...
+    ///
+    /// let screenshot = surface.capture();
+    /// surface.swap();
+    /// # }
+    /// ```
+    ///
+    /// Note that it's a slow operation as it waits for all the drawing operations to complete.
+    ///
+    pub fn capture(&mut self) -> Image {
+        self.activate();
+
+        // The pixels of a non-window surface are read from its texture.
+        if let Some(framebuffer) = &self.framebuffer {
+            framebuffer.resolve(self.size);
+            unsafe {
+                gl_check!(gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer.frame_buffer));
+            }
+        }
+
+        let size = Size::new(self.size.width.max(0) as usize, self.size.height.max(0) as usize);
+        let mut pixels = vec![Color::TRANSPARENT; size.width * size.height];
+
+        if !pixels.is_empty() {
+            unsafe {
+                gl_check!(gl::PixelStorei(gl::PACK_ALIGNMENT, 1));
+                gl_check!(gl::ReadPixels(
+                    0,
+                    0,
+                    size.width as _,
+                    size.height as _,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    pixels.as_mut_ptr() as _
+                ));
+            }
+        }
+
+        // Non-window surfaces are drawn upside down already (see bind_shader()).
+        match &self.framebuffer {
+            Some(framebuffer) => framebuffer.bind(),
+            None => flip_rows(&mut pixels, size)
+        }
+
+        Image::with_pixels(size, pixels).unwrap()
+    }
+
+    /// Returns the texture of the surface.
+    ///
+    /// This function returns the texture the surface draws on, if it's not a window surface; it
+    /// can be used in subsequent drawing operations (on other surfaces), for instance, to draw a
+    /// minimap, a cached layer of the interface, or to apply a post-processing shader.
+    ///
+    /// Note that the texture must not be drawn on its own surface, and that the texture of a
+    /// multisampled surface is only updated when the surface is resolved (see `resolve()`).
+    ///
+    pub fn texture(&self) -> Option<&Texture> {
+        self.framebuffer.as_ref().map(|framebuffer| &framebuffer.texture)
+    }
+
+    /// Resolves the surface.
+    ///
+    /// This function copies the content of a multisampled non-window surface into its texture; it
+    /// must be called after drawing, before the texture is used. It does nothing if the surface
+    /// isn't multisampled, or if it's a window surface.
+    ///
+    pub fn resolve(&mut self) {
+        if self.framebuffer.is_some() {
+            self.activate();
+            self.framebuffer.as_ref().unwrap().resolve(self.size);
+        }
+    }
+
+    /// Returns the options of the surface.
+    ///
+    /// This function returns the options that were really obtained from the graphics driver, which
+    /// may differ from the requested options (see `Options`).
+    ///
+    pub fn options(&mut self) -> Options {
+        self.activate();
+
+        let (color, depth, stencil) = match self.framebuffer {
+            Some(_) => (gl::COLOR_ATTACHMENT0, gl::DEPTH_ATTACHMENT, gl::STENCIL_ATTACHMENT),
+            None => (gl::BACK, gl::DEPTH, gl::STENCIL)
+        };
+
+        let (samples, flags) = unsafe {
+            let mut samples = 0;
+            let mut flags = 0;
+            gl_check!(gl::GetIntegerv(gl::SAMPLES, &mut samples));
+            gl_check!(gl::GetIntegerv(gl::CONTEXT_FLAGS, &mut flags));
+
+            (samples, flags)
+        };
+
+        let encoding = attachment_parameter(color, gl::FRAMEBUFFER_ATTACHMENT_COLOR_ENCODING);
+
+        Options {
+            depth_bits: attachment_parameter(depth, gl::FRAMEBUFFER_ATTACHMENT_DEPTH_SIZE) as u8,
+            stencil_bits: attachment_parameter(stencil, gl::FRAMEBUFFER_ATTACHMENT_STENCIL_SIZE) as u8,
+            antialiasing_level: samples as u16,
+            srgb: encoding as gl::types::GLenum == gl::SRGB,
+            vsync: self.options.vsync,
+            debug: (flags as gl::types::GLenum & gl::CONTEXT_FLAG_DEBUG_BIT) != 0
+        }
+    }
+
     /// Brief description
     ///
     /// The **view() function** is not documented yet. Pull requests are welcome.
@@ -283,43 +708,133 @@ impl Surface {
         let blue  = color.blue  as f32 / 255.0;
         let alpha = color.alpha as f32 / 255.0;
 
+        self.activate();
         unsafe {
             gl_check!(gl::ClearColor(red, green, blue, alpha));
             gl_check!(gl::Clear(gl::COLOR_BUFFER_BIT));
         }
     }
 
+    /// Draws a drawable.
+    ///
+    /// This function draws a drawable entity with the default render states.
+    ///
     pub fn draw(&mut self, drawable: &dyn Drawable) {
-        drawable.draw(self);
+        drawable.draw(self, &RenderStates::default());
     }
 
-    /// Brief description
+    /// Draws a drawable with render states.
+    ///
+    /// This function draws a drawable entity with given render states; it's used to draw it with a
+    /// custom shader, an additional transformation, or a scissor rectangle.
     ///
-    /// The **draw_vertices() function** is not documented yet. Pull requests are welcome.
+    pub fn draw_with_states(&mut self, drawable: &dyn Drawable, states: &RenderStates) {
+        drawable.draw(self, states);
+    }
+
+    /// Draws vertices.
     ///
-    pub fn draw_vertices(&mut self, vertices: &VertexArray, texture: Option<&Texture>, matrix: Option<Matrix>) {
+    /// This function draws a vertex array with given render states; the vertices are transformed by
+    /// the transformation of the states, and drawn with their shader and texture (or the default
+    /// ones). If the vertex array has instances, the default shader is its instanced variant. The
+    /// default shaders expect the `Vertex` layout; vertex arrays of a custom vertex type must be
+    /// drawn with a custom shader.
+    ///
+    pub fn draw_vertices<V: VertexLayout>(&mut self, vertices: &VertexArray<V>, states: &RenderStates) {
+        let shader = match states.shader {
+            Some(shader) => shader,
+            None if vertices.is_instanced() => get_or_create_instanced_shader(),
+            None => get_or_create_default_shader()
+        };
+
+        self.bind_shader(shader, states.transform);
+        self.bind_blend_mode(states.blend_mode);
+        self.bind_texture(states.texture);
+        self.bind_scissor(states.scissor);
+
+        // Delegate the drawing calls to the vertices.
+        vertices.draw(self);
+    }
+
+    /// Draw ranges of vertices with their own texture.
+    ///
+    /// This function draws several ranges of the same vertex array with the default shader and a
+    /// given blending mode; the shader and its uniforms are set up once, then only the texture is
+    /// changed between the ranges. It's used by `Batch` to draw many entities with as few state
+    /// changes as possible.
+    ///
+    pub(crate) fn draw_vertex_ranges(&mut self, vertices: &VertexArray, blend_mode: BlendMode, ranges: &[(Option<&Texture>, Range<usize>)]) {
+        self.bind_shader(get_or_create_default_shader(), Matrix::IDENTITY);
+        self.bind_blend_mode(blend_mode);
+        self.bind_scissor(None);
+
+        for (texture, range) in ranges {
+            self.bind_texture(*texture);
+            vertices.draw_range(self, range.clone());
+        }
+    }
+
+    fn bind_shader(&mut self, shader: &Shader, matrix: Matrix) {
 
         // To draw on the surface, we must make its underlying OpenGL context (and thus associated
         // framebuffer) current. This is so the DrawArrays() function operates on it.
         self.activate();
 
-        // For now, it's always using the default shader program; make it current.
-        let default_shader = get_or_create_default_shader();
-        default_shader.bind();
+        // Make the shader program current.
+        shader.bind();
+
+        // The rows of a texture go from the top to the bottom of the image, but OpenGL draws from the
+        // bottom to the top; non-window surfaces are drawn upside down so their texture is in the
+        // same orientation as the other textures.
+        let mut viewport = self.view.matrix();
+        if self.framebuffer.is_some() {
+            viewport = Matrix::with_elements([1.0, 0.0, 0.0, 0.0, -1.0, 0.0]).combine(viewport);
+        }
+
+        // Set the viewport uniform (commonly called the projection matrix) and the model uniform.
+        shader.upload_uniform("viewport", Uniform::Matrix4(viewport.as_4x4_array()));
+        shader.upload_uniform("model", Uniform::Matrix3(matrix.as_3x3_array()));
+    }
 
-        // Set the viewport uniform (commonly called the projection matrix)
-        default_shader.set_uniform("viewport", Uniform::Matrix4(self.view.matrix().as_4x4_array()));
+    fn bind_blend_mode(&mut self, blend_mode: BlendMode) {
+        unsafe {
+            gl_check!(gl::Enable(gl::BLEND));
+            gl_check!(gl::BlendFuncSeparate(
+                from_blend_factor(blend_mode.color_source),
+                from_blend_factor(blend_mode.color_destination),
+                from_blend_factor(blend_mode.alpha_source),
+                from_blend_factor(blend_mode.alpha_destination)
+            ));
+            gl_check!(gl::BlendEquationSeparate(
+                from_blend_equation(blend_mode.color_equation),
+                from_blend_equation(blend_mode.alpha_equation)
+            ));
+        }
+    }
 
-        match matrix {
-            Some(matrix) => {
-                default_shader.set_uniform("model", Uniform::Matrix3(matrix.as_3x3_array()));
+    fn bind_scissor(&mut self, scissor: Option<Box<i32>>) {
+        match scissor {
+            Some(scissor) => {
+                // OpenGL expects the bottom-left corner of the rectangle, from the bottom of the
+                // framebuffer (non-window surfaces are drawn upside down).
+                let bottom = match self.framebuffer {
+                    Some(_) => scissor.top(),
+                    None => self.size.height - scissor.bottom()
+                };
+                unsafe {
+                    gl_check!(gl::Enable(gl::SCISSOR_TEST));
+                    gl_check!(gl::Scissor(scissor.left(), bottom, scissor.size.width, scissor.size.height));
+                }
             },
             None => {
-                let matrix = Matrix::IDENTITY;
-                default_shader.set_uniform("model", Uniform::Matrix3(matrix.as_3x3_array()));
+                unsafe {
+                    gl_check!(gl::Disable(gl::SCISSOR_TEST));
+                }
             }
         }
+    }
 
+    fn bind_texture(&mut self, texture: Option<&Texture>) {
         // Set up the texture (mandatory to have one with the default shader); it's either the user
         // specified texture, or the default texture which is an "identity" texture.
         match texture {
@@ -334,9 +849,6 @@ impl Surface {
         unsafe {
             gl::ActiveTexture(gl::TEXTURE0);
         };
-
-        // Delegate the drawing calls to the vertices.
-        vertices.draw(self);
     }
 
     /// Brief description
@@ -383,7 +895,15 @@ impl Surface {
         context = match context {
             UnderlyingContext::NoWindow(mut underlying_context) => {
                 underlying_context = unsafe {
-                    underlying_context.make_current().unwrap().treat_as_not_current()
+                    let current_context = underlying_context.make_current().unwrap();
+
+                    // The framebuffer is bound again because other code (for instance,
+                    // Texture::to_image()) may have bound another one.
+                    if let Some(framebuffer) = &self.framebuffer {
+                        framebuffer.bind();
+                    }
+
+                    current_context.treat_as_not_current()
                 };
 
                 UnderlyingContext::NoWindow(underlying_context)
@@ -401,13 +921,57 @@ impl Surface {
     }
 }
 
+impl Drop for Surface {
+    fn drop(&mut self) {
+        // The framebuffers belong to the OpenGL context of the surface, it must be current to
+        // delete them.
+        if self.framebuffer.is_some() {
+            self.activate();
+            self.framebuffer.as_mut().unwrap().delete();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::draw::{Vertex, Primitive, Usage};
+    use crate::draw::testing::{lock_rendering, render, pixel};
+
+    #[test]
+    fn surface_depth_stencil_format() {
+        assert_eq!(depth_stencil_format(0, 0), None);
+        assert_eq!(depth_stencil_format(0, 8), Some((gl::STENCIL_INDEX8, gl::STENCIL_ATTACHMENT)));
+        assert_eq!(depth_stencil_format(16, 0), Some((gl::DEPTH_COMPONENT16, gl::DEPTH_ATTACHMENT)));
+        assert_eq!(depth_stencil_format(24, 0), Some((gl::DEPTH_COMPONENT24, gl::DEPTH_ATTACHMENT)));
+        assert_eq!(depth_stencil_format(32, 0), Some((gl::DEPTH_COMPONENT32F, gl::DEPTH_ATTACHMENT)));
+        assert_eq!(depth_stencil_format(24, 8), Some((gl::DEPTH24_STENCIL8, gl::DEPTH_STENCIL_ATTACHMENT)));
+        assert_eq!(depth_stencil_format(32, 8), Some((gl::DEPTH32F_STENCIL8, gl::DEPTH_STENCIL_ATTACHMENT)));
+    }
+
+    #[test]
+    fn surface_flip_rows() {
+        let (a, b, c) = (Color::RED, Color::GREEN, Color::BLUE);
+
+        let mut pixels = vec![a, a, b, b, c, c];
+        flip_rows(&mut pixels, Size::new(2, 3));
+        assert_eq!(pixels, [c, c, b, b, a, a]);
+
+        let mut pixels = vec![a, b, c, a];
+        flip_rows(&mut pixels, Size::new(1, 4));
+        assert_eq!(pixels, [a, c, b, a]);
+
+        let mut pixels: Vec<Color> = vec![];
+        flip_rows(&mut pixels, Size::new(0, 0));
+        assert!(pixels.is_empty());
+    }
+
     #[test]
     fn surface_view() {
-        let mut surface = Surface::new(Size::zero(), Options::default());
+        let _lock = lock_rendering();
+
+        let mut surface = Surface::new(Size::zero(), Options::default()).unwrap();
 
         assert_eq!(surface.default_view(), View::new(Position::zero(), Size::zero()));
         assert_eq!(*surface.view(), surface.default_view());
@@ -424,4 +988,72 @@ mod tests {
         surface.resize(Size::new(320, 240));
         assert_eq!(*surface.view(), surface.default_view());
     }
+
+    #[test]
+    fn surface_resize() {
+        let _lock = lock_rendering();
+
+        // An empty surface can be created, then resized and drawn on.
+        let mut surface = Surface::new(Size::zero(), Options::default()).unwrap();
+        assert_eq!(surface.capture().size(), Size::zero());
+
+        surface.resize(Size::new(4, 4));
+        surface.erase(Color::RED);
+
+        let image = surface.capture();
+        assert_eq!(image.size(), Size::new(4, 4));
+        assert_eq!(pixel(&image, 0, 0), Color::RED);
+        assert_eq!(pixel(&image, 3, 3), Color::RED);
+    }
+
+    #[test]
+    fn surface_view_draw() {
+        // The view shows the top-left quarter of the scene, magnified twice.
+        let image = render(Size::new(16, 16), |surface| {
+            let vertices = vec![
+                Vertex::with_position_and_color(Position::new(0.0, 0.0), Color::RED),
+                Vertex::with_position_and_color(Position::new(4.0, 0.0), Color::RED),
+                Vertex::with_position_and_color(Position::new(0.0, 4.0), Color::RED),
+                Vertex::with_position_and_color(Position::new(4.0, 4.0), Color::RED)
+            ];
+            let vertex_array = VertexArray::with_vertices(&vertices, Primitive::TriangleStrips, Usage::Static);
+
+            surface.set_view(&View::new(Position::new(4.0, 4.0), Size::new(8.0, 8.0)));
+            surface.erase(Color::BLACK);
+            surface.draw_vertices(&vertex_array, &RenderStates::default());
+        });
+
+        assert_eq!(pixel(&image, 0, 0), Color::RED);
+        assert_eq!(pixel(&image, 7, 7), Color::RED);
+        assert_eq!(pixel(&image, 8, 8), Color::BLACK);
+    }
+
+    #[test]
+    fn surface_texture() {
+        let _lock = lock_rendering();
+
+        // Draw the top half of a non-window surface, then read its texture back.
+        let mut surface = Surface::new(Size::new(4, 4), Options::default()).unwrap();
+
+        let vertices = vec![
+            Vertex::with_position_and_color(Position::new(0.0, 0.0), Color::GREEN),
+            Vertex::with_position_and_color(Position::new(4.0, 0.0), Color::GREEN),
+            Vertex::with_position_and_color(Position::new(0.0, 2.0), Color::GREEN),
+            Vertex::with_position_and_color(Position::new(4.0, 2.0), Color::GREEN)
+        ];
+        let vertex_array = VertexArray::with_vertices(&vertices, Primitive::TriangleStrips, Usage::Static);
+
+        surface.erase(Color::RED);
+        surface.draw_vertices(&vertex_array, &RenderStates::default());
+
+        let image = surface.texture().unwrap().to_image();
+        assert_eq!(image.size(), Size::new(4, 4));
+        assert_eq!(pixel(&image, 0, 0), Color::GREEN);
+        assert_eq!(pixel(&image, 3, 1), Color::GREEN);
+        assert_eq!(pixel(&image, 0, 2), Color::RED);
+        assert_eq!(pixel(&image, 3, 3), Color::RED);
+
+        // The texture has the same orientation as the surface.
+        assert_eq!(image, surface.capture());
+    }
 }
@@ -0,0 +1,85 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+use std::fmt;
+use crate::draw::gl;
+
+// Returns the name of a status returned by CheckFramebufferStatus().
+fn framebuffer_status_name(status: gl::types::GLenum) -> &'static str {
+    match status {
+        gl::FRAMEBUFFER_UNDEFINED                     => "GL_FRAMEBUFFER_UNDEFINED",
+        gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT         => "GL_FRAMEBUFFER_INCOMPLETE_ATTACHMENT",
+        gl::FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT => "GL_FRAMEBUFFER_INCOMPLETE_MISSING_ATTACHMENT",
+        gl::FRAMEBUFFER_INCOMPLETE_MULTISAMPLE        => "GL_FRAMEBUFFER_INCOMPLETE_MULTISAMPLE",
+        gl::FRAMEBUFFER_UNSUPPORTED                   => "GL_FRAMEBUFFER_UNSUPPORTED",
+        _                                             => "unknown status"
+    }
+}
+
+/// An error of surface creation.
+///
+/// A non-window surface can't be created if its OpenGL context can't be created, or if the
+/// graphics driver doesn't support its framebuffer with the requested options (see `Options`);
+/// for instance, a driver may not support multisampled sRGB framebuffers. The surface can be
+/// created again with other options.
+///
+/// ```no_run
+/// # use byteplug::geometry::Size;
+/// # use byteplug::draw::{Surface, SurfaceError, Options};
+/// let options = Options { antialiasing_level: 8, srgb: true, ..Options::default() };
+///
+/// let surface = match Surface::new(Size::new(640, 480), options) {
+///     Ok(surface) => surface,
+///     Err(SurfaceError::IncompleteFramebuffer(_)) => {
+///         Surface::new(Size::new(640, 480), Options::default()).unwrap()
+///     },
+///     Err(error) => panic!("{}", error)
+/// };
+/// ```
+///
+/// **Implementation notes**
+///
+/// - The status of an incomplete framebuffer is the value returned by `glCheckFramebufferStatus()`
+///   (for instance, `GL_FRAMEBUFFER_UNSUPPORTED`).
+/// - An empty framebuffer is always incomplete, so the framebuffer of a surface created with an
+///   empty size is checked when it's resized instead (see `Surface::resize()`).
+///
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum SurfaceError {
+    /// The OpenGL context can't be created.
+    Context(String),
+    /// The framebuffer is incomplete.
+    IncompleteFramebuffer(u32)
+}
+
+impl fmt::Display for SurfaceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SurfaceError::Context(error) => write!(f, "failed to create the OpenGL context: {}", error),
+            SurfaceError::IncompleteFramebuffer(status) => write!(f, "the framebuffer is incomplete ({})", framebuffer_status_name(*status))
+        }
+    }
+}
+
+impl std::error::Error for SurfaceError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn surface_error_display() {
+        let error = SurfaceError::IncompleteFramebuffer(gl::FRAMEBUFFER_UNSUPPORTED);
+        assert_eq!(error.to_string(), "the framebuffer is incomplete (GL_FRAMEBUFFER_UNSUPPORTED)");
+
+        let error = SurfaceError::IncompleteFramebuffer(0);
+        assert_eq!(error.to_string(), "the framebuffer is incomplete (unknown status)");
+
+        let error = SurfaceError::Context(String::from("no display"));
+        assert_eq!(error.to_string(), "failed to create the OpenGL context: no display");
+    }
+}
@@ -0,0 +1,231 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+//! Support for the tests of the draw module
+//!
+//! The tests of the drawing operations render a scene on a non-window surface, capture it, and
+//! compare it against a reference image stored in `resources/references/`. When they don't match,
+//! the captured image and a diff image (the mismatching pixels are red) are written to
+//! `target/references/` so they can be inspected.
+//!
+//! ```
+//! let image = render(Size::new(32, 32), |surface| {
+//!     surface.erase(Color::BLACK);
+//!     surface.draw(&shape);
+//! });
+//! assert_reference_image(&image, "shape_draw", 0);
+//! ```
+//!
+//! A missing reference image fails the test; when the `BYTEPLUG_UPDATE_REFERENCES` environment
+//! variable is set, the captured image is saved as the reference image instead.
+//!
+//! The tests which use OpenGL without rendering a scene must hold the rendering lock (see
+//! `lock_rendering()`) for as long as they use it.
+//!
+//! **Implementation notes**
+//!
+//! - The tests using OpenGL are serialized because the OpenGL contexts aren't meant to be used by
+//!   the concurrent test threads.
+//! - The surfaces are created with a debug OpenGL context and the OpenGL errors panic while the
+//!   rendering lock is held, so the tests fail on them; the option is disabled again when the
+//!   lock is released, so it doesn't affect the other tests.
+//! - A software implementation of OpenGL is requested (with the `LIBGL_ALWAYS_SOFTWARE` variable
+//!   understood by Mesa) so the results don't depend on the graphics card; it's set under the
+//!   rendering lock, before the first OpenGL context is created, so no other thread reads the
+//!   environment while it's changed.
+//!
+use std::env;
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use crate::geometry::{Position, Size};
+use crate::image::{Color, Image};
+use crate::draw::{Surface, Options};
+use crate::draw::debug;
+
+static RENDERING_LOCK: Mutex<bool> = Mutex::new(false); // whether software rendering is requested
+
+fn reference_path(name: &str) -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("resources/references");
+    path.push(format!("{}.png", name));
+
+    path
+}
+
+fn output_path(name: &str, suffix: &str) -> PathBuf {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("target/references");
+    path.push(format!("{}.{}.png", name, suffix));
+
+    path
+}
+
+fn save_image(image: &Image, path: &PathBuf) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+    let mut image = image.clone();
+    image.save(&mut File::create(path).unwrap()).unwrap();
+}
+
+/// A lock on the OpenGL contexts of the tests.
+///
+/// This lock is returned by `lock_rendering()`; the OpenGL errors panic until it's dropped.
+///
+pub(crate) struct RenderingLock {
+    _guard: MutexGuard<'static, bool>
+}
+
+impl Drop for RenderingLock {
+    fn drop(&mut self) {
+        // The guard is released after this.
+        debug::set_panic_on_error(false);
+    }
+}
+
+/// Locks the OpenGL contexts of the tests.
+///
+/// This function waits until no other test uses OpenGL, then enables the panic on error option;
+/// the first time, it also requests a software implementation of OpenGL.
+///
+pub(crate) fn lock_rendering() -> RenderingLock {
+    // A previous test may have panicked while holding the lock; it doesn't matter.
+    let mut guard = RENDERING_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+
+    if !*guard {
+        env::set_var("LIBGL_ALWAYS_SOFTWARE", "1");
+        *guard = true;
+    }
+    debug::set_panic_on_error(true);
+
+    RenderingLock {
+        _guard: guard
+    }
+}
+
+/// Renders a scene on a non-window surface.
+///
+/// This function creates a non-window surface of a given size, lets the closure draw on it, then
+/// returns its content.
+///
+pub(crate) fn render<F: FnOnce(&mut Surface)>(size: Size<i32>, draw: F) -> Image {
+    let _lock = lock_rendering();
+
+    let options = Options {
+        debug: true,
+        ..Options::default()
+    };
+
+    let mut surface = Surface::new(size, options).unwrap();
+    draw(&mut surface);
+
+    surface.capture()
+}
+
+/// Compares two images.
+///
+/// This function compares two images of the same size pixel by pixel; two pixels match if none of
+/// their channels differ by more than the tolerance. It returns a diff image if they don't match,
+/// where the mismatching pixels are red and the other pixels are a faded grayscale copy of the
+/// actual image.
+///
+pub(crate) fn compare_images(actual: &Image, expected: &Image, tolerance: u8) -> Option<Image> {
+    assert_eq!(actual.size(), expected.size(), "the images don't have the same size");
+
+    let mut mismatch = false;
+    let pixels = actual.pixels().iter().zip(expected.pixels().iter())
+        .map(|(actual, expected)| {
+            let channels = [
+                (actual.red, expected.red),
+                (actual.green, expected.green),
+                (actual.blue, expected.blue),
+                (actual.alpha, expected.alpha)
+            ];
+
+            if channels.iter().any(|(a, b)| (*a as i16 - *b as i16).abs() > tolerance as i16) {
+                mismatch = true;
+                Color::RED
+            }
+            else {
+                let gray = ((actual.red as u16 + actual.green as u16 + actual.blue as u16) / 3 / 4) as u8;
+                Color::rgb(gray, gray, gray)
+            }
+        })
+        .collect();
+
+    if mismatch {
+        Some(Image::with_pixels(actual.size(), pixels).unwrap())
+    }
+    else {
+        None
+    }
+}
+
+/// Asserts that an image matches a reference image.
+///
+/// This function compares an image with the reference image of a given name (see
+/// `compare_images()`); it panics if they don't match, after the image and the diff image are
+/// written in the output directory. It also panics if the reference image doesn't exist, unless
+/// the reference images are being updated.
+///
+pub(crate) fn assert_reference_image(image: &Image, name: &str, tolerance: u8) {
+    let path = reference_path(name);
+
+    if env::var_os("BYTEPLUG_UPDATE_REFERENCES").is_some() {
+        save_image(image, &path);
+        return
+    }
+
+    if !path.exists() {
+        save_image(image, &output_path(name, "actual"));
+        panic!("the reference image {:?} doesn't exist (set BYTEPLUG_UPDATE_REFERENCES to create it)", path);
+    }
+
+    let reference = Image::open(&path).unwrap();
+    if reference.size() != image.size() {
+        save_image(image, &output_path(name, "actual"));
+        panic!("the image doesn't have the same size as the reference image {:?}", path);
+    }
+
+    if let Some(diff) = compare_images(image, &reference, tolerance) {
+        save_image(image, &output_path(name, "actual"));
+        save_image(&diff, &output_path(name, "diff"));
+
+        panic!("the image doesn't match the reference image {:?} (see {:?})",
+               path, output_path(name, "diff"));
+    }
+}
+
+/// Returns the color of a pixel of an image.
+///
+/// This function is a shortcut to check individual pixels of a rendering.
+///
+pub(crate) fn pixel(image: &Image, x: isize, y: isize) -> Color {
+    *image.pixel(Position::new(x, y)).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn testing_compare_images() {
+        let expected = Image::with_pixels(Size::new(2, 1), vec![Color::rgb(100, 100, 100), Color::BLACK]).unwrap();
+
+        let actual = Image::with_pixels(Size::new(2, 1), vec![Color::rgb(102, 99, 100), Color::BLACK]).unwrap();
+        assert!(compare_images(&actual, &expected, 2).is_none());
+
+        let diff = compare_images(&actual, &expected, 1).unwrap();
+        assert_eq!(pixel(&diff, 0, 0), Color::RED);
+        assert_eq!(pixel(&diff, 1, 0), Color::BLACK);
+
+        let actual = Image::with_pixels(Size::new(2, 1), vec![Color::rgb(100, 100, 100), Color::rgba(0, 0, 0, 0)]).unwrap();
+        let diff = compare_images(&actual, &expected, 254).unwrap();
+        assert_eq!(pixel(&diff, 0, 0), Color::rgb(25, 25, 25));
+        assert_eq!(pixel(&diff, 1, 0), Color::RED);
+    }
+}
@@ -5,8 +5,1735 @@
 //
 // Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, January 2020
 
+use std::ops::Range;
+use crate::geometry::{Position, Size, Box};
+use crate::geometry::{Transformable, Transformer};
+use crate::image::Color;
+use crate::draw::{Surface, Font, Glyph, Rendering};
+use crate::draw::{Primitive, Usage};
+use crate::draw::{Vertex, VertexArray};
+use crate::draw::Uniform;
+use crate::draw::Alignment;
+use crate::draw::Span;
+use crate::draw::{Drawable, RenderStates};
+use crate::draw::get_or_create_distance_field_shader;
+
+/// The resolved style of a span.
+///
+/// The properties that are inherited from the text are resolved before the layout is computed.
+///
+struct Style<'a> {
+    font: &'a Font,
+    character_size: u32,
+    color: Color,
+    underlined: bool,
+    strikethrough: bool,
+    effects: Effects
+}
+
+/// The effects drawn around the glyphs of a span.
+///
+/// With bitmap fonts, only the outline is drawn (with outline glyphs); with distance field fonts,
+/// they're all computed by the distance field shader.
+///
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct Effects {
+    outline_thickness: f32,
+    outline_color: Color,
+    glow_radius: f32,
+    glow_color: Color,
+    shadow_offset: Position,
+    shadow_color: Color
+}
+
+/// A line of a laid out text.
+///
+/// It refers to a range of characters (the end is exclusive and doesn't include the character
+/// that caused the line break).
+///
+struct Line {
+    start: usize,
+    end: usize,
+    last: bool // indicate if it's the last line of a paragraph
+}
+
+/// A glyph (or a decoration) positioned by the layout algorithm.
+struct Quad {
+    bounds: Box<f32>,
+    texture_rect: Box<i32>,
+    color: Color,
+    style: usize, // the index of the style of the span it belongs to
+    outline: bool // indicate if it belongs to the outline layer
+}
+
+/// The horizontal placement of a character, used for hit testing and cursor positioning.
+///
+/// The position is the left edge of the character, regardless of its direction.
+///
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+struct Caret {
+    x: f32,
+    width: f32,
+    line: usize,
+    right_to_left: bool
+}
+
+/// The vertical placement of a line, used for hit testing.
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+struct Row {
+    top: f32,
+    baseline: f32,
+    bottom: f32
+}
+
+/// A sequence of characters of the same style and direction.
+///
+/// Runs never span multiple paragraphs; the new line characters don't belong to any run.
+///
+struct Run {
+    start: usize,
+    end: usize,
+    style: usize,
+    level: u8 // the bidirectional embedding level (odd levels are right-to-left)
+}
+
+/// A glyph of a run.
+struct RunGlyph {
+    index: u16,
+    character: usize, // the first character of the cluster the glyph belongs to
+    advance: f32,
+    offset: Position
+}
+
+/// The characters converted into glyphs.
+///
+/// The glyphs of each run are in visual order. The advance of a cluster of characters (such as a
+/// ligature) is evenly split between its characters; the kerning is the offset to apply before a
+/// character (it's only used when the text isn't shaped).
+///
+struct Shaping {
+    runs: Vec<Run>,
+    glyphs: Vec<Vec<RunGlyph>>,
+    advances: Vec<f32>,
+    kernings: Vec<f32>
+}
+
+/// The result of the layout algorithm.
+struct Layout {
+    quads: Vec<Quad>,
+    carets: Vec<Caret>, // one per character, plus one at the end of the text
+    rows: Vec<Row>,
+    bounds: Box<f32>
+}
+
+// The characters of a text, each with the index of the style of its span.
+type Characters = [(char, usize)];
+
+fn itemize(characters: &Characters, bidirectional: bool) -> Vec<Run> {
+    // Split the characters into paragraphs, and the paragraphs into runs of characters of the same
+    // style and the same embedding level (computed with the Unicode bidirectional algorithm).
+    let mut runs = Vec::new();
+
+    let mut paragraph_start = 0;
+    while paragraph_start <= characters.len() {
+        let paragraph_end = characters[paragraph_start..].iter()
+            .position(|(character, _)| *character == '\n')
+            .map_or(characters.len(), |position| paragraph_start + position);
+
+        let paragraph = &characters[paragraph_start..paragraph_end];
+
+        let levels: Vec<u8> = if bidirectional {
+            let string: String = paragraph.iter().map(|(character, _)| *character).collect();
+            let info = unicode_bidi::ParagraphBidiInfo::new(&string, None);
+
+            // The levels are given per byte.
+            string.char_indices().map(|(offset, _)| info.levels[offset].number()).collect()
+        } else {
+            vec![0; paragraph.len()]
+        };
+
+        for (index, (_, style)) in paragraph.iter().enumerate() {
+            let level = levels[index];
+
+            match runs.last_mut() {
+                Some(Run { end, style: run_style, level: run_level, .. }) if *end == paragraph_start + index && *run_style == *style && *run_level == level => {
+                    *end += 1;
+                },
+                _ => runs.push(Run {
+                    start: paragraph_start + index,
+                    end: paragraph_start + index + 1,
+                    style: *style,
+                    level: level
+                })
+            }
+        }
+
+        paragraph_start = paragraph_end + 1;
+    }
+
+    runs
+}
+
+fn shape(styles: &[Style], characters: &Characters, shaping: bool) -> Shaping {
+    let runs = itemize(characters, shaping);
+
+    let mut glyphs = Vec::with_capacity(runs.len());
+    let mut advances = vec![0.0; characters.len()];
+    let mut kernings = vec![0.0; characters.len()];
+
+    for run in runs.iter() {
+        let style = &styles[run.style];
+        let font = style.font;
+        let size = style.character_size;
+
+        let mut run_glyphs = Vec::new();
+
+        if shaping {
+            let string: String = characters[run.start..run.end].iter().map(|(character, _)| *character).collect();
+            let offsets: Vec<usize> = string.char_indices().map(|(offset, _)| offset).collect();
+
+            for glyph in font.shape(&string, size, run.level % 2 == 1).into_iter() {
+                let character = run.start + offsets.binary_search(&glyph.cluster).unwrap_or_else(|index| index - 1);
+                advances[character] += glyph.advance;
+
+                run_glyphs.push(RunGlyph {
+                    index: glyph.index,
+                    character: character,
+                    advance: glyph.advance,
+                    offset: glyph.offset
+                });
+            }
+
+            // Split the advance of each cluster between its characters.
+            let mut clusters: Vec<usize> = run_glyphs.iter().map(|glyph| glyph.character).collect();
+            clusters.sort_unstable();
+            clusters.dedup();
+
+            for (index, cluster) in clusters.iter().enumerate() {
+                let end = clusters.get(index + 1).cloned().unwrap_or(run.end);
+                let advance = advances[*cluster] / (end - cluster) as f32;
+
+                for advance_of_character in advances[*cluster..end].iter_mut() {
+                    *advance_of_character = advance;
+                }
+            }
+        } else {
+            for index in run.start..run.end {
+                let character = characters[index].0;
+
+                let glyph_index = font.glyph_index(character);
+                let advance = font.glyph_by_index(glyph_index, size).advance;
+                advances[index] = advance;
+
+                // Kerning only applies between two characters of the same font and character size.
+                if index > 0 {
+                    let (previous_character, previous_style_index) = characters[index - 1];
+                    let previous_style = &styles[previous_style_index];
+
+                    if previous_character != '\n' && std::ptr::eq(font, previous_style.font) && size == previous_style.character_size {
+                        kernings[index] = font.kerning(previous_character, character, size);
+                    }
+                }
+
+                run_glyphs.push(RunGlyph {
+                    index: glyph_index,
+                    character: index,
+                    advance: advance,
+                    offset: Position::zero()
+                });
+            }
+        }
+
+        glyphs.push(run_glyphs);
+    }
+
+    Shaping {
+        runs: runs,
+        glyphs: glyphs,
+        advances: advances,
+        kernings: kernings
+    }
+}
+
+fn advance(shaping: &Shaping, index: usize, start: usize) -> f32 {
+    // The kerning isn't applied to the first character of a line.
+    if index > start {
+        shaping.advances[index] + shaping.kernings[index]
+    } else {
+        shaping.advances[index]
+    }
+}
+
+fn measure(shaping: &Shaping, start: usize, end: usize) -> f32 {
+    (start..end).map(|index| advance(shaping, index, start)).sum()
+}
+
+fn trim_end(characters: &Characters, start: usize, end: usize) -> usize {
+    // Trailing whitespaces are not taken into account when measuring and aligning a line.
+    let mut end = end;
+    while end > start && characters[end - 1].0.is_whitespace() {
+        end -= 1;
+    }
+
+    end
+}
+
+fn break_lines(characters: &Characters, shaping: &Shaping, max_width: Option<f32>) -> Vec<Line> {
+    // The algorithm is a simple greedy algorithm; words are added to the current line until the
+    // next word doesn't fit, in which case the line is broken at the last space. If a single word
+    // doesn't fit, it's broken in the middle.
+    let mut lines = Vec::new();
+
+    let mut start = 0;
+    let mut width = 0.0;
+    let mut last_space: Option<usize> = None;
+
+    let mut index = 0;
+    while index < characters.len() {
+        let character = characters[index].0;
+
+        if character == '\n' {
+            lines.push(Line { start: start, end: index, last: true });
+
+            start = index + 1;
+            width = 0.0;
+            last_space = None;
+
+            index += 1;
+            continue
+        }
+
+        let advance = advance(shaping, index, start);
+
+        if let Some(max_width) = max_width {
+            if width + advance > max_width && !character.is_whitespace() && index > start {
+                match last_space {
+                    Some(space) => {
+                        lines.push(Line { start: start, end: space, last: false });
+                        start = space + 1;
+                    },
+                    None => {
+                        lines.push(Line { start: start, end: index, last: false });
+                        start = index;
+                    }
+                }
+
+                // Restart measuring from the beginning of the new line.
+                index = start;
+                width = 0.0;
+                last_space = None;
+
+                continue
+            }
+        }
+
+        if character == ' ' {
+            last_space = Some(index);
+        }
+
+        width += advance;
+        index += 1;
+    }
+
+    lines.push(Line { start: start, end: characters.len(), last: true });
+
+    lines
+}
+
+fn reorder(levels: &[u8]) -> Vec<usize> {
+    // Compute the visual order of a sequence of runs from their embedding levels; from the highest
+    // level to the lowest odd level, any sequence of runs at that level or higher is reversed
+    // (rule L2 of the Unicode bidirectional algorithm).
+    let mut order: Vec<usize> = (0..levels.len()).collect();
+
+    let highest = levels.iter().cloned().max().unwrap_or(0);
+    let lowest_odd = levels.iter().cloned().min().unwrap_or(0) | 1;
+
+    for level in (lowest_odd..=highest).rev() {
+        let mut index = 0;
+        while index < order.len() {
+            if levels[order[index]] >= level {
+                let start = index;
+                while index < order.len() && levels[order[index]] >= level {
+                    index += 1;
+                }
+
+                order[start..index].reverse();
+            } else {
+                index += 1;
+            }
+        }
+    }
+
+    order
+}
+
+fn line_metrics(styles: &[Style], characters: &Characters, line: &Line) -> (f32, f32, f32) {
+    // Compute the ascent, the descent and the line gap of a line; it's the biggest ones of the
+    // styles of its characters. An empty line takes the metrics of the character that ended it
+    // (or the last character of the text).
+    let range = if line.start < line.end {
+        line.start..line.end
+    } else {
+        let index = line.start.min(characters.len() - 1);
+        index..index + 1
+    };
+
+    let mut ascent: f32 = 0.0;
+    let mut descent: f32 = 0.0;
+    let mut gap: f32 = 0.0;
+
+    for (_, style_index) in characters[range].iter() {
+        let style = &styles[*style_index];
+
+        let font_ascent = style.font.ascent(style.character_size);
+        let font_descent = style.font.descent(style.character_size);
+        let font_gap = style.font.line_spacing(style.character_size) - font_ascent - font_descent;
+
+        ascent = ascent.max(font_ascent);
+        descent = descent.max(font_descent);
+        gap = gap.max(font_gap);
+    }
+
+    (ascent, descent, gap)
+}
+
+fn glyph_quad(glyph: &Glyph, position: Position) -> Option<Box<f32>> {
+    // Glyphs with no visual (such as spaces) don't produce any quad. The quad covers the padding
+    // around the glyph, as defined by its texture rectangle.
+    if glyph.texture_rect.size.width == 0 || glyph.texture_rect.size.height == 0 {
+        return None
+    }
+
+    let padding_x = (glyph.texture_rect.size.width as f32 - glyph.bounds.size.width) / 2.0;
+    let padding_y = (glyph.texture_rect.size.height as f32 - glyph.bounds.size.height) / 2.0;
+
+    Some(Box::new(
+        Position::new(position.x + glyph.bounds.left() - padding_x, position.y + glyph.bounds.top() - padding_y),
+        Size::new(glyph.texture_rect.size.width as f32, glyph.texture_rect.size.height as f32)
+    ))
+}
+
+fn decoration_quad(style: &Style, style_index: usize, left: f32, right: f32, offset: f32, thickness: f32) -> Quad {
+    Quad {
+        bounds: Box::new(
+            Position::new(left, offset - thickness / 2.0),
+            Size::new(right - left, thickness)
+        ),
+        texture_rect: style.font.white_rect(style.character_size),
+        color: style.color,
+        style: style_index,
+        outline: false
+    }
+}
+
+fn decorate(styles: &[Style], style_index: usize, left: f32, right: f32, baseline: f32, quads: &mut Vec<Quad>) {
+    let style = &styles[style_index];
+    let font = style.font;
+    let size = style.character_size;
+
+    if right <= left {
+        return
+    }
+
+    if style.underlined {
+        let offset = baseline + font.underline_position(size);
+        quads.push(decoration_quad(style, style_index, left, right, offset, font.underline_thickness(size)));
+    }
+
+    if style.strikethrough {
+        let offset = baseline + font.strikethrough_position(size);
+        quads.push(decoration_quad(style, style_index, left, right, offset, font.strikethrough_thickness(size)));
+    }
+}
+
+fn layout(styles: &[Style], characters: &Characters, shaping: bool, alignment: Alignment, line_spacing: f32, max_width: Option<f32>) -> Layout {
+    if characters.is_empty() {
+        return Layout {
+            quads: Vec::new(),
+            carets: vec![Caret::default()],
+            rows: vec![Row::default()],
+            bounds: Box::default()
+        }
+    }
+
+    let shaping = shape(styles, characters, shaping);
+    let lines = break_lines(characters, &shaping, max_width);
+
+    // Measure the lines, and compute the reference width used to align them.
+    let widths: Vec<f32> = lines.iter()
+        .map(|line| measure(&shaping, line.start, trim_end(characters, line.start, line.end)))
+        .collect();
+
+    let reference_width = match max_width {
+        Some(max_width) => max_width,
+        None => widths.iter().cloned().fold(0.0, f32::max)
+    };
+
+    let mut quads = Vec::new();
+    let mut carets = vec![Caret::default(); characters.len() + 1];
+    let mut rows = Vec::with_capacity(lines.len());
+
+    let mut left = f32::MAX;
+    let mut right = f32::MIN;
+
+    let mut baseline = 0.0;
+    let mut previous_metrics: Option<(f32, f32)> = None; // the descent and gap of the previous line
+
+    for (line_index, line) in lines.iter().enumerate() {
+        let end = trim_end(characters, line.start, line.end);
+        let width = widths[line_index];
+
+        // The first baseline is at the ascent of the first line; the next ones are spaced by the
+        // descent of the previous line, the biggest gap and the ascent of the current line (scaled
+        // by the line spacing factor).
+        let (ascent, descent, gap) = line_metrics(styles, characters, line);
+        baseline = match previous_metrics {
+            Some((previous_descent, previous_gap)) => baseline + (previous_descent + previous_gap.max(gap) + ascent) * line_spacing,
+            None => ascent
+        };
+        previous_metrics = Some((descent, gap));
+
+        rows.push(Row {
+            top: baseline - ascent,
+            baseline: baseline,
+            bottom: baseline + descent
+        });
+
+        // Compute the offset of the line, and the extra space added to each space character when
+        // the text is justified.
+        let spaces = characters[line.start..end].iter().filter(|c| c.0 == ' ').count();
+
+        let mut offset = 0.0;
+        let mut extra_space = 0.0;
+
+        match alignment {
+            Alignment::Left => (),
+            Alignment::Center => offset = ((reference_width - width) / 2.0).floor(),
+            Alignment::Right => offset = reference_width - width,
+            Alignment::Justify => {
+                if !line.last && spaces > 0 {
+                    extra_space = (reference_width - width) / spaces as f32;
+                }
+            }
+        }
+
+        left = left.min(offset);
+        right = right.max(offset + width + extra_space * spaces as f32);
+
+        // Cut the runs to the line (without its trailing whitespaces), and order them visually.
+        let pieces: Vec<(usize, usize, usize)> = shaping.runs.iter().enumerate()
+            .filter(|(_, run)| run.start < end && run.end > line.start)
+            .map(|(index, run)| (index, run.start.max(line.start), run.end.min(end)))
+            .collect();
+
+        let levels: Vec<u8> = pieces.iter().map(|piece| shaping.runs[piece.0].level).collect();
+
+        let mut x = offset;
+
+        // The decorations of consecutive characters of the same span are merged into a single
+        // quad; it's the style index and the left edge of the current run.
+        let mut decoration: Option<(usize, f32)> = None;
+
+        for piece_index in reorder(&levels).into_iter() {
+            let (run_index, piece_start, piece_end) = pieces[piece_index];
+
+            let run = &shaping.runs[run_index];
+            let style_index = run.style;
+            let style = &styles[style_index];
+            let right_to_left = run.level % 2 == 1;
+
+            // The glyphs of the piece, grouped by cluster (the glyphs of a cluster are
+            // consecutive).
+            let glyphs: Vec<&RunGlyph> = shaping.glyphs[run_index].iter()
+                .filter(|glyph| glyph.character >= piece_start && glyph.character < piece_end)
+                .collect();
+
+            let mut clusters: Vec<usize> = glyphs.iter().map(|glyph| glyph.character).collect();
+            clusters.sort_unstable();
+            clusters.dedup();
+
+            let mut glyph_index = 0;
+            while glyph_index < glyphs.len() {
+                let cluster = glyphs[glyph_index].character;
+                let cluster_end = clusters.iter().cloned().find(|start| *start > cluster).unwrap_or(piece_end);
+
+                if cluster > line.start {
+                    x += shaping.kernings[cluster];
+                }
+
+                if let Some((decoration_style, decoration_left)) = decoration {
+                    if decoration_style != style_index {
+                        decorate(styles, decoration_style, decoration_left, x, baseline, &mut quads);
+                        decoration = None;
+                    }
+                }
+
+                if decoration.is_none() && (style.underlined || style.strikethrough) {
+                    decoration = Some((style_index, x));
+                }
+
+                let cluster_x = x;
+
+                while glyph_index < glyphs.len() && glyphs[glyph_index].character == cluster {
+                    let run_glyph = glyphs[glyph_index];
+                    let position = Position::new(x + run_glyph.offset.x, baseline + run_glyph.offset.y);
+
+                    if style.effects.outline_thickness > 0.0 && style.font.rendering() == Rendering::Bitmap {
+                        let outline_glyph = style.font.outline_glyph_by_index(run_glyph.index, style.character_size, style.effects.outline_thickness);
+                        if let Some(bounds) = glyph_quad(&outline_glyph, position) {
+                            quads.push(Quad {
+                                bounds: bounds,
+                                texture_rect: outline_glyph.texture_rect,
+                                color: style.effects.outline_color,
+                                style: style_index,
+                                outline: true
+                            });
+                        }
+                    }
+
+                    let glyph = style.font.glyph_by_index(run_glyph.index, style.character_size);
+                    if let Some(bounds) = glyph_quad(&glyph, position) {
+                        quads.push(Quad {
+                            bounds: bounds,
+                            texture_rect: glyph.texture_rect,
+                            color: style.color,
+                            style: style_index,
+                            outline: false
+                        });
+                    }
+
+                    x += run_glyph.advance;
+                    glyph_index += 1;
+                }
+
+                // Place the carets of the characters of the cluster; they're placed from the right
+                // to the left in right-to-left runs.
+                let mut caret_x = cluster_x;
+                let mut place_caret = |index: usize| {
+                    let mut width = shaping.advances[index];
+                    if characters[index].0 == ' ' {
+                        width += extra_space;
+                    }
+
+                    carets[index] = Caret { x: caret_x, width: width, line: line_index, right_to_left: right_to_left };
+                    caret_x += width;
+                };
+
+                if right_to_left {
+                    (cluster..cluster_end).rev().for_each(&mut place_caret);
+                } else {
+                    (cluster..cluster_end).for_each(&mut place_caret);
+                }
+
+                x = caret_x;
+            }
+        }
+
+        if let Some((decoration_style, decoration_left)) = decoration.take() {
+            decorate(styles, decoration_style, decoration_left, x, baseline, &mut quads);
+        }
+
+        // Trailing whitespaces get a caret (so they can be hit), but they're not drawn. The
+        // character that caused the line break (if any) is placed at the end of the line; it's
+        // overwritten by the next line if the line was broken in the middle of a word.
+        for (caret, advance) in carets[end..line.end].iter_mut().zip(shaping.advances[end..line.end].iter()) {
+            *caret = Caret { x: x, width: *advance, line: line_index, right_to_left: false };
+            x += advance;
+        }
+
+        carets[line.end] = Caret { x: x, width: 0.0, line: line_index, right_to_left: false };
+    }
+
+    let height = rows.last().unwrap().bottom;
+
+    Layout {
+        quads: quads,
+        carets: carets,
+        rows: rows,
+        bounds: Box::new(Position::new(left, 0.0), Size::new(right - left, height))
+    }
+}
+
+fn compute_vertices(quads: &[&Quad], texture_size: Size<i32>) -> Vec<Vertex> {
+    let mut vertices = Vec::with_capacity(quads.len() * 6);
+
+    let texture_width = texture_size.width as f32;
+    let texture_height = texture_size.height as f32;
+
+    for quad in quads.iter() {
+        let u1 = quad.texture_rect.left() as f32 / texture_width;
+        let v1 = quad.texture_rect.top() as f32 / texture_height;
+        let u2 = quad.texture_rect.right() as f32 / texture_width;
+        let v2 = quad.texture_rect.bottom() as f32 / texture_height;
+
+        let mut top_left = Vertex::with_position_and_color(quad.bounds.top_left(), quad.color);
+        top_left.u = u1;
+        top_left.v = v1;
+
+        let mut top_right = Vertex::with_position_and_color(quad.bounds.top_right(), quad.color);
+        top_right.u = u2;
+        top_right.v = v1;
+
+        let mut bottom_left = Vertex::with_position_and_color(quad.bounds.bottom_left(), quad.color);
+        bottom_left.u = u1;
+        bottom_left.v = v2;
+
+        let mut bottom_right = Vertex::with_position_and_color(quad.bounds.bottom_right(), quad.color);
+        bottom_right.u = u2;
+        bottom_right.v = v2;
+
+        // Two triangles per quad.
+        vertices.push(top_left);
+        vertices.push(top_right);
+        vertices.push(bottom_left);
+        vertices.push(bottom_left);
+        vertices.push(top_right);
+        vertices.push(bottom_right);
+    }
+
+    vertices
+}
+
+// The quads of a batch; it's the font, the character size, the layer and the effects.
+type Group<'a, 'b> = (&'a Font, u32, bool, Option<Effects>, Vec<&'b Quad>);
+
+/// A group of quads sharing the same texture.
+///
+/// The quads of a text are grouped by font, character size and layer (outline or fill) because
+/// each combination has its own texture; each batch is drawn in one draw call. With distance field
+/// fonts, they're also grouped by effects because they're uniforms of the shader.
+///
+struct Batch<'a> {
+    font: &'a Font,
+    character_size: u32,
+    effects: Option<Effects>,
+    vertices: VertexArray,
+    texture_size: Size<i32> // the size of the font texture when the vertices were computed
+}
+
 /// A drawable text of a given font.
 ///
-/// The **Text struct** is not documented yet. Pull requests are welcome.
+/// A text is one of the high-level drawable entity (built on top of vertex array) that allows you
+/// to draw a string on a surface with a given font. It has a character size, a fill color, and a
+/// set of layout properties which are the alignment, the line spacing and the maximum width.
+///
+/// Use `new()` to construct an empty text (with no font) and `with_string()` to construct a text
+/// from a string, a font and a character size.
+///
+/// ```no_run
+/// # use byteplug::geometry::Size;
+/// # use byteplug::image::Color;
+/// # use byteplug::draw::{Surface, Options, Font, Text, Alignment};
+/// # let mut surface = Surface::new(Size::new(640, 480), Options::default()).unwrap();
+/// let font = Font::open("resources/DejaVuSans.ttf").unwrap();
+///
+/// let mut text = Text::with_string("Hello world!", &font, 24);
+/// text.set_color(Color::WHITE);
+/// text.set_max_width(Some(200.0));
+/// text.set_alignment(Alignment::Center);
+/// text.update();
+///
+/// surface.draw(&text);
+/// ```
+///
+/// The string is laid out using the metrics of the font; the glyphs are placed on the baseline and
+/// spaced according to their advance and the kerning pairs of the font. A new line is started with
+/// the `'\n'` character and, if the text has a maximum width, lines are wrapped at the last space
+/// that fits (words that don't fit on a single line are broken). The line spacing is a factor of
+/// the line spacing of the font (1.0 by default).
+///
+/// A text is made of spans, which are styled runs of characters (see `Span`); they're laid out
+/// as one block. Setting the string of a text replaces its spans with a single span that inherits
+/// the style of the text. Spans can have their own font, character size and color, and can be
+/// underlined, struck through and outlined.
+///
+/// ```no_run
+/// # use byteplug::geometry::Position;
+/// # use byteplug::image::Color;
+/// # use byteplug::draw::{Font, Text, Span};
+/// # let font = Font::open("resources/DejaVuSans.ttf").unwrap();
+/// # let mut text = Text::with_string("", &font, 24);
+/// # let mouse_position = Position::new(0.0, 0.0);
+/// text.set_spans(vec![
+///     Span::new("Score: "),
+///     Span { color: Some(Color::YELLOW), ..Span::new("42") },
+///     Span { underlined: true, ..Span::new("details") }
+/// ]);
+/// text.update();
+///
+/// if let Some(index) = text.character_index(mouse_position) {
+///     if text.span_index(index) == Some(2) {
+///         // The link was clicked.
+///     }
+/// }
+/// ```
+///
+/// Scripts such as Arabic, Hebrew or Hindi require complex text shaping (ligatures, contextual
+/// forms, etc.) and bidirectional reordering; it's enabled with `set_shaping()`. The cursor
+/// positions and the selections (see `character_position()` and `selection_bounds()`) follow the
+/// logical order of the characters, and are placed correctly in the visual order.
+///
+/// The characters are grouped in vertex arrays of textured quads (using the texture of the font)
+/// per font, character size and layer (the outlines are drawn first). A text with a single style
+/// is therefore drawn in one draw call, regardless of the number of lines.
+///
+/// **Implementation notes**
 ///
-pub struct Text {}
+/// - Just like `Shape`, the vertices are not updated immediately; they're updated with the
+///   `update()` method. Properties changes are only visible after it's called.
+/// - The texture of the font can grow when glyphs are requested (by any text sharing the same
+///   font). Because the texture coordinates are normalized, the text must be updated again when it
+///   happens; `update()` detects it and re-computes the vertices.
+/// - The bounds are the local bounds of the text, from the left of the leftmost line to the right
+///   of the rightmost line, and from the top of the first line to the descent of the last line.
+///   The transformed bounds are returned by `global_bounds()`.
+/// - The first line is positioned so the top of the text (its ascent) is at the origin. Lines with
+///   mixed character sizes are spaced according to their biggest characters.
+/// - Kerning is only applied between characters of the same font and character size.
+/// - Texts whose font is in the distance field rendering mode are drawn with a dedicated shader;
+///   they stay crisp when they're magnified (or when the view is zoomed), and the outline, the glow
+///   and the shadow of the spans are computed by the shader.
+/// - Tabulation characters are not handled specially.
+/// - Nothing is drawn unless every span has a font (either its own or the one of the text).
+/// - The shaping is done per run of characters of the same span and direction; glyphs can't be
+///   substituted across two spans. The lines are broken after the shaping, and the advance of a
+///   cluster of characters (such as a ligature) is split evenly between its characters.
+/// - Only the default direction of the Unicode bidirectional algorithm is supported (it's
+///   determined by the first strong character of each paragraph); the alignment isn't affected.
+/// - Trailing whitespaces are always placed at the right of the line.
+///
+pub struct Text<'a> {
+    spans: Vec<Span<'a>>,
+    string: String, // the concatenated strings of the spans
+    font: Option<&'a Font>,
+    character_size: u32,
+    color: Color,
+    alignment: Alignment,
+    line_spacing: f32,
+    max_width: Option<f32>,
+    shaping: bool,
+    position: Position,
+    angle: f32,
+    magnifier: f32,
+    batches: Vec<Batch<'a>>,
+    carets: Vec<Caret>,
+    rows: Vec<Row>,
+    bounds: Box<f32>,
+    update: bool // indicate if vertices need to be re-computed
+}
+
+impl<'a> Text<'a> {
+    /// Constructs an empty text.
+    ///
+    /// This function is the default constructor. It creates a text with no string and no font;
+    /// nothing is drawn until the text has both.
+    ///
+    pub fn new() -> Text<'a> {
+        Text {
+            spans: Vec::new(),
+            string: String::new(),
+            font: None,
+            character_size: 30,
+            color: Color::WHITE,
+            alignment: Alignment::Left,
+            line_spacing: 1.0,
+            max_width: None,
+            shaping: false,
+            position: Position::zero(),
+            angle: 0.0,
+            magnifier: 1.0,
+            batches: Vec::new(),
+            carets: vec![Caret::default()],
+            rows: vec![Row::default()],
+            bounds: Box::default(),
+            update: true
+        }
+    }
+
+    /// Constructs a text from a string.
+    ///
+    /// This function constructs a text from a string, a font and a character size (in pixels).
+    ///
+    pub fn with_string(string: &str, font: &'a Font, character_size: u32) -> Text<'a> {
+        let mut text = Text::new();
+        text.set_string(string);
+        text.set_font(font);
+        text.set_character_size(character_size);
+
+        text
+    }
+
+    /// Returns the string of the text.
+    ///
+    /// This function returns the string that is displayed (the concatenated strings of the spans).
+    ///
+    pub fn string(&self) -> &str {
+        &self.string
+    }
+
+    /// Changes the string of the text.
+    ///
+    /// This function changes the string that is displayed. It replaces the spans of the text with
+    /// a single span that inherits the style of the text.
+    ///
+    pub fn set_string(&mut self, string: &str) {
+        self.set_spans(vec![Span::new(string)]);
+    }
+
+    /// Returns the spans of the text.
+    ///
+    /// This function returns the styled runs of characters the text is made of.
+    ///
+    pub fn spans(&self) -> &[Span<'a>] {
+        &self.spans
+    }
+
+    /// Changes the spans of the text.
+    ///
+    /// This function changes the styled runs of characters the text is made of. Note that the
+    /// fonts of the spans must outlive the text.
+    ///
+    pub fn set_spans(&mut self, spans: Vec<Span<'a>>) {
+        self.string = spans.iter().map(|span| span.string.as_str()).collect();
+        self.spans = spans;
+        self.update = true;
+    }
+
+    /// Returns the font of the text.
+    ///
+    /// This function returns the font used to display the text, if any.
+    ///
+    pub fn font(&self) -> Option<&'a Font> {
+        self.font
+    }
+
+    /// Changes the font of the text.
+    ///
+    /// This function changes the font used to display the text (spans with their own font aren't
+    /// affected). Note that the font must outlive the text.
+    ///
+    pub fn set_font(&mut self, font: &'a Font) {
+        self.font = Some(font);
+        self.update = true;
+    }
+
+    /// Removes the font of the text.
+    ///
+    /// This function removes the font of the text; nothing is drawn until a new font is set
+    /// (unless all spans have their own font).
+    ///
+    pub fn unset_font(&mut self) {
+        self.font = None;
+        self.update = true;
+    }
+
+    /// Returns the character size.
+    ///
+    /// This function returns the character size of the text (in pixels).
+    ///
+    pub fn character_size(&self) -> u32 {
+        self.character_size
+    }
+
+    /// Changes the character size.
+    ///
+    /// This function changes the character size of the text (in pixels). The default character
+    /// size is 30. Spans with their own character size aren't affected.
+    ///
+    pub fn set_character_size(&mut self, character_size: u32) {
+        self.character_size = character_size;
+        self.update = true;
+    }
+
+    /// Returns the color of the text.
+    ///
+    /// This function returns the **fill color** of the text.
+    ///
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Changes the color of the text.
+    ///
+    /// This function changes the **fill color** of the text. The default color is white. Spans
+    /// with their own color aren't affected.
+    ///
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+        self.update = true;
+    }
+
+    /// Returns the alignment of the text.
+    ///
+    /// This function returns the horizontal alignment of the lines.
+    ///
+    pub fn alignment(&self) -> Alignment {
+        self.alignment
+    }
+
+    /// Changes the alignment of the text.
+    ///
+    /// This function changes the horizontal alignment of the lines. The default alignment is left.
+    ///
+    pub fn set_alignment(&mut self, alignment: Alignment) {
+        self.alignment = alignment;
+        self.update = true;
+    }
+
+    /// Returns the line spacing factor.
+    ///
+    /// This function returns the factor applied to the line spacing of the font.
+    ///
+    pub fn line_spacing(&self) -> f32 {
+        self.line_spacing
+    }
+
+    /// Changes the line spacing factor.
+    ///
+    /// This function changes the factor applied to the line spacing of the font. The default
+    /// factor is 1.0.
+    ///
+    pub fn set_line_spacing(&mut self, line_spacing: f32) {
+        self.line_spacing = line_spacing;
+        self.update = true;
+    }
+
+    /// Returns the maximum width of the text.
+    ///
+    /// This function returns the width at which lines are wrapped, if any.
+    ///
+    pub fn max_width(&self) -> Option<f32> {
+        self.max_width
+    }
+
+    /// Changes the maximum width of the text.
+    ///
+    /// This function changes the width at which lines are wrapped. Lines are not wrapped if there
+    /// is no maximum width (which is the default).
+    ///
+    pub fn set_max_width(&mut self, max_width: Option<f32>) {
+        self.max_width = max_width;
+        self.update = true;
+    }
+
+    /// Returns whether the text is shaped.
+    ///
+    /// This function returns whether complex text shaping and bidirectional reordering are
+    /// enabled.
+    ///
+    pub fn shaping(&self) -> bool {
+        self.shaping
+    }
+
+    /// Enables or disables the shaping of the text.
+    ///
+    /// This function enables or disables complex text shaping and bidirectional reordering. When
+    /// it's enabled, the characters are converted into glyphs according to the rules of their
+    /// script (ligatures, contextual forms, mark positioning, etc.) and the runs of mixed
+    /// directions (such as Arabic or Hebrew within Latin text) are reordered according to the
+    /// Unicode bidirectional algorithm. It's disabled by default because it's slower, and it's
+    /// not needed for simple scripts.
+    ///
+    pub fn set_shaping(&mut self, shaping: bool) {
+        self.shaping = shaping;
+        self.update = true;
+    }
+
+    /// Returns the local bounds of the text.
+    ///
+    /// This function returns the bounding box of the text, ignoring its transformation. It's only
+    /// valid after the text is updated.
+    ///
+    pub fn bounds(&self) -> Box<f32> {
+        self.bounds
+    }
+
+    /// Returns the global bounds of the text.
+    ///
+    /// This function returns the bounding box of the text, after its transformation (position,
+    /// angle and magnifier) is applied. It's only valid after the text is updated.
+    ///
+    pub fn global_bounds(&self) -> Box<f32> {
+        Transformer::with_matrix(self.matrix()).transform_box(&self.bounds)
+    }
+
+    /// Returns the character at a given position.
+    ///
+    /// This function returns the index of the character (in the string of the text) which is
+    /// displayed at a given position, or none if there is no character. The position is in global
+    /// coordinates; the transformation of the text is taken into account. It's only valid after
+    /// the text is updated.
+    ///
+    pub fn character_index(&self, position: Position) -> Option<usize> {
+        let transformer = Transformer::with_matrix(self.matrix().inverse());
+        hit_test(&self.carets, &self.rows, transformer.transform_position(&position))
+    }
+
+    /// Returns the position of a character.
+    ///
+    /// This function returns the position of the cursor placed before a character (in logical
+    /// order), in global coordinates. It's the top of the line, on the left edge of the character
+    /// if it's left-to-right, or on its right edge if it's right-to-left. The index can be the
+    /// length of the string, in which case the position is at the end of the text. It's only
+    /// valid after the text is updated.
+    ///
+    pub fn character_position(&self, index: usize) -> Position {
+        let caret = self.carets[index.min(self.carets.len() - 1)];
+
+        let x = if caret.right_to_left { caret.x + caret.width } else { caret.x };
+        let position = Position::new(x, self.rows[caret.line].top);
+
+        Transformer::with_matrix(self.matrix()).transform_position(&position)
+    }
+
+    /// Returns the areas of a selection.
+    ///
+    /// This function returns the areas covered by a range of characters (in logical order), in
+    /// global coordinates. Because of line breaks and bidirectional text, a selection isn't
+    /// necessarily contiguous; there is one area per contiguous part of each line. It's only valid
+    /// after the text is updated.
+    ///
+    pub fn selection_bounds(&self, range: Range<usize>) -> Vec<Box<f32>> {
+        let transformer = Transformer::with_matrix(self.matrix());
+
+        selection(&self.carets, &self.rows, range).iter()
+            .map(|area| transformer.transform_box(area))
+            .collect()
+    }
+
+    /// Returns the span of a character.
+    ///
+    /// This function returns the index of the span the character belongs to, or none if the
+    /// index is out of range.
+    ///
+    pub fn span_index(&self, index: usize) -> Option<usize> {
+        let mut start = 0;
+        for (span_index, span) in self.spans.iter().enumerate() {
+            let end = start + span.string.chars().count();
+            if index < end {
+                return Some(span_index)
+            }
+
+            start = end;
+        }
+
+        None
+    }
+
+    /// Updates the vertices of the text.
+    ///
+    /// This function lays out the spans and updates the vertices on the graphics memory if the
+    /// text was modified (or if the texture of a font has grown) since the last update.
+    ///
+    pub fn update(&mut self) {
+        for batch in self.batches.iter() {
+            if batch.font.texture_size(batch.character_size) != batch.texture_size {
+                self.update = true;
+            }
+        }
+
+        if !self.update {
+            return
+        }
+
+        // Reuse the existing vertex arrays.
+        let mut arrays: Vec<VertexArray> = self.batches.drain(..).map(|batch| batch.vertices).collect();
+
+        match self.styles() {
+            Some(styles) => {
+                let characters: Vec<(char, usize)> = self.spans.iter().enumerate()
+                    .flat_map(|(index, span)| span.string.chars().map(move |character| (character, index)))
+                    .collect();
+
+                let layout = layout(
+                    &styles,
+                    &characters,
+                    self.shaping,
+                    self.alignment,
+                    self.line_spacing,
+                    self.max_width
+                );
+
+                // Group the quads by font, character size, layer and effects (with distance field
+                // fonts only), with the outline layer first.
+                let mut groups: Vec<Group> = Vec::new();
+                for outline in [true, false].iter() {
+                    for quad in layout.quads.iter().filter(|quad| quad.outline == *outline) {
+                        let style = &styles[quad.style];
+                        let effects = match style.font.rendering() {
+                            Rendering::Bitmap => None,
+                            Rendering::DistanceField => Some(style.effects)
+                        };
+
+                        let group = groups.iter_mut().find(|group| {
+                            std::ptr::eq(group.0, style.font) && group.1 == style.character_size && group.2 == *outline && group.3 == effects
+                        });
+
+                        match group {
+                            Some(group) => group.4.push(quad),
+                            None => groups.push((style.font, style.character_size, *outline, effects, vec![quad]))
+                        }
+                    }
+                }
+
+                for (font, character_size, _, effects, quads) in groups.into_iter() {
+                    // The texture size must be retrieved after the layout is computed because it
+                    // may have grown when the glyphs were requested.
+                    let texture_size = font.texture_size(character_size);
+
+                    let mut vertices = arrays.pop().unwrap_or_else(|| {
+                        let mut vertices = VertexArray::new();
+                        vertices.set_primitive(Primitive::Triangles);
+                        vertices.set_usage(Usage::Stream);
+
+                        vertices
+                    });
+                    vertices.update_vertices(&compute_vertices(&quads, texture_size));
+
+                    self.batches.push(Batch {
+                        font: font,
+                        character_size: character_size,
+                        effects: effects,
+                        vertices: vertices,
+                        texture_size: texture_size
+                    });
+                }
+
+                self.carets = layout.carets;
+                self.rows = layout.rows;
+                self.bounds = layout.bounds;
+            },
+            None => {
+                self.carets = vec![Caret::default()];
+                self.rows = vec![Row::default()];
+                self.bounds = Box::default();
+            }
+        }
+
+        // Mark the vertices as updated.
+        self.update = false;
+    }
+
+    fn styles(&self) -> Option<Vec<Style<'a>>> {
+        // Resolve the styles of the spans; it fails if a span has no font.
+        self.spans.iter().map(|span| {
+            Some(Style {
+                font: span.font.or(self.font)?,
+                character_size: span.character_size.unwrap_or(self.character_size),
+                color: span.color.unwrap_or(self.color),
+                underlined: span.underlined,
+                strikethrough: span.strikethrough,
+                effects: Effects {
+                    outline_thickness: span.outline_thickness,
+                    outline_color: span.outline_color,
+                    glow_radius: span.glow_radius,
+                    glow_color: span.glow_color,
+                    shadow_offset: span.shadow_offset,
+                    shadow_color: span.shadow_color
+                }
+            })
+        }).collect()
+    }
+}
+
+fn selection(carets: &[Caret], rows: &[Row], range: Range<usize>) -> Vec<Box<f32>> {
+    // Collect the horizontal extents of the selected characters, and merge the ones that touch
+    // each other on a same line.
+    let end = range.end.min(carets.len() - 1);
+    let start = range.start.min(end);
+
+    let mut extents: Vec<(usize, f32, f32)> = carets[start..end].iter()
+        .map(|caret| (caret.line, caret.x, caret.x + caret.width))
+        .collect();
+    extents.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.partial_cmp(&b.1).unwrap()));
+
+    let mut merged: Vec<(usize, f32, f32)> = Vec::new();
+    for (line, left, right) in extents.into_iter() {
+        match merged.last_mut() {
+            Some(last) if last.0 == line && left <= last.2 + 0.01 => last.2 = last.2.max(right),
+            _ => merged.push((line, left, right))
+        }
+    }
+
+    merged.into_iter()
+        .map(|(line, left, right)| {
+            let row = rows[line];
+            Box::new(Position::new(left, row.top), Size::new(right - left, row.bottom - row.top))
+        })
+        .collect()
+}
+
+fn hit_test(carets: &[Caret], rows: &[Row], position: Position) -> Option<usize> {
+    // Find the line; the space between two lines belongs to the line below.
+    if position.y < rows[0].top || position.y >= rows[rows.len() - 1].bottom {
+        return None
+    }
+
+    let line = rows.iter()
+        .position(|row| position.y < row.bottom)
+        .unwrap_or(rows.len() - 1);
+
+    // The last caret is the end of the text and can't be hit.
+    carets[..carets.len() - 1].iter()
+        .position(|caret| caret.line == line && position.x >= caret.x && position.x < caret.x + caret.width)
+}
+
+impl<'a> Transformable for Text<'a> {
+    fn position(&self) -> Position {
+        self.position
+    }
+
+    fn set_position(&mut self, position: Position) {
+        self.position = position;
+    }
+
+    fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    fn set_angle(&mut self, angle: f32) {
+        self.angle = angle;
+    }
+
+    fn magnifier(&self) -> f32 {
+        self.magnifier
+    }
+
+    fn set_magnifier(&mut self, magnifier: f32) {
+        self.magnifier = magnifier;
+    }
+}
+
+impl<'a> Drawable for Text<'a> {
+    fn draw(&self, surface: &mut Surface, states: &RenderStates) {
+        let states = states.transformed(self.matrix());
+
+        // The batches are ordered; the outline layer comes first.
+        for batch in self.batches.iter() {
+            let texture = batch.font.texture(batch.character_size);
+            let states = RenderStates {
+                texture: Some(&texture),
+                ..states
+            };
+
+            match batch.effects {
+                Some(effects) if states.shader.is_none() => {
+                    // The effects are converted to the units of the distance field; the distance
+                    // is 0.5 at the edge of the glyphs and 0.0 at the spread.
+                    let spread = batch.font.distance_field_spread(batch.character_size) * 2.0;
+
+                    let shader = get_or_create_distance_field_shader();
+                    shader.set_uniform("outline_color", Uniform::Color(effects.outline_color));
+                    shader.set_uniform("outline_thickness", Uniform::Float((effects.outline_thickness / spread).min(0.5)));
+                    shader.set_uniform("glow_color", Uniform::Color(effects.glow_color));
+                    shader.set_uniform("glow_radius", Uniform::Float((effects.glow_radius / spread).min(0.5)));
+                    shader.set_uniform("shadow_color", Uniform::Color(effects.shadow_color));
+                    shader.set_uniform("shadow_offset", Uniform::Vector2(
+                        effects.shadow_offset.x / batch.texture_size.width as f32,
+                        effects.shadow_offset.y / batch.texture_size.height as f32
+                    ));
+
+                    let states = RenderStates {
+                        shader: Some(shader),
+                        ..states
+                    };
+                    surface.draw_vertices(&batch.vertices, &states);
+                },
+                _ => surface.draw_vertices(&batch.vertices, &states)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+    use super::*;
+
+    fn get_sample_font() -> Font {
+        let mut filename = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        filename.push("resources/DejaVuSans.ttf");
+
+        Font::open(OsString::from(filename)).unwrap()
+    }
+
+    fn style(font: &Font, size: u32) -> Style<'_> {
+        Style {
+            font: font,
+            character_size: size,
+            color: Color::WHITE,
+            underlined: false,
+            strikethrough: false,
+            effects: Effects {
+                outline_thickness: 0.0,
+                outline_color: Color::BLACK,
+                glow_radius: 0.0,
+                glow_color: Color::WHITE,
+                shadow_offset: Position::zero(),
+                shadow_color: Color::TRANSPARENT
+            }
+        }
+    }
+
+    fn characters(string: &str, style: usize) -> Vec<(char, usize)> {
+        string.chars().map(|character| (character, style)).collect()
+    }
+
+    fn layout_string(font: &Font, string: &str, size: u32, alignment: Alignment, line_spacing: f32, max_width: Option<f32>) -> Layout {
+        layout(&[style(font, size)], &characters(string, 0), false, alignment, line_spacing, max_width)
+    }
+
+    fn measure_string(font: &Font, string: &str, size: u32) -> f32 {
+        let characters = characters(string, 0);
+        measure(&shape(&[style(font, size)], &characters, false), 0, characters.len())
+    }
+
+    fn break_string(font: &Font, string: &str, size: u32, max_width: Option<f32>) -> Vec<Line> {
+        let characters = characters(string, 0);
+        break_lines(&characters, &shape(&[style(font, size)], &characters, false), max_width)
+    }
+
+    fn line_count(layout: &Layout, font: &Font, size: u32) -> usize {
+        (((layout.bounds.size.height - font.ascent(size) - font.descent(size)) / font.line_spacing(size)).round() + 1.0) as usize
+    }
+
+    #[test]
+    fn text_layout_empty() {
+        let font = get_sample_font();
+
+        let layout = layout_string(&font, "", 20, Alignment::Left, 1.0, None);
+        assert_eq!(layout.quads.len(), 0);
+        assert_eq!(layout.bounds, Box::default());
+    }
+
+    #[test]
+    fn text_layout_single_line() {
+        let font = get_sample_font();
+
+        // Spaces don't produce quads, but they're taken into account in the width.
+        let layout = layout_string(&font, "Hello world", 20, Alignment::Left, 1.0, None);
+        assert_eq!(layout.quads.len(), 10);
+        assert_eq!(line_count(&layout, &font, 20), 1);
+
+        assert_eq!(layout.bounds.position, Position::zero());
+        assert_eq!(layout.bounds.size.width, measure_string(&font, "Hello world", 20));
+        assert_eq!(layout.bounds.size.height, font.ascent(20) + font.descent(20));
+    }
+
+    #[test]
+    fn text_layout_kerning() {
+        let font = get_sample_font();
+
+        // The second glyph of a kerned pair is moved closer to the first one.
+        let kerned = layout_string(&font, "To", 40, Alignment::Left, 1.0, None);
+        let advance = font.glyph('T', 40).advance;
+        let kerning = font.kerning('T', 'o', 40);
+        assert!(kerning < 0.0);
+
+        let o = font.glyph('o', 40);
+        let padding = (o.texture_rect.size.width as f32 - o.bounds.size.width) / 2.0;
+        assert_eq!(kerned.quads[1].bounds.left(), advance + kerning + o.bounds.left() - padding);
+
+        // Kerning doesn't apply between characters of different character sizes.
+        let styles = [style(&font, 40), style(&font, 20)];
+        let characters = vec![('T', 0), ('o', 1)];
+        let unkerned = layout(&styles, &characters, false, Alignment::Left, 1.0, None);
+
+        let o = font.glyph('o', 20);
+        let padding = (o.texture_rect.size.width as f32 - o.bounds.size.width) / 2.0;
+        assert_eq!(unkerned.quads[1].bounds.left(), advance + o.bounds.left() - padding);
+    }
+
+    #[test]
+    fn text_layout_new_lines() {
+        let font = get_sample_font();
+
+        let layout = layout_string(&font, "first\nsecond\n\nfourth", 20, Alignment::Left, 1.0, None);
+        assert_eq!(line_count(&layout, &font, 20), 4);
+
+        // A line spacing factor changes the distance between the lines.
+        let spaced_layout = layout_string(&font, "first\nsecond", 20, Alignment::Left, 2.0, None);
+        let expected_height = font.line_spacing(20) * 2.0 + font.ascent(20) + font.descent(20);
+        assert!((spaced_layout.bounds.size.height - expected_height).abs() < 0.001);
+    }
+
+    #[test]
+    fn text_layout_wrapping() {
+        let font = get_sample_font();
+
+        let string = "The quick brown fox jumps over the lazy dog";
+        let layout = layout_string(&font, string, 20, Alignment::Left, 1.0, Some(100.0));
+        assert!(line_count(&layout, &font, 20) > 1);
+
+        for quad in layout.quads.iter() {
+            assert!(quad.bounds.right() <= 100.0 + 2.0); // account for the glyph padding
+        }
+
+        // Words are kept together; no line starts with a space.
+        let lines = break_string(&font, string, 20, Some(100.0));
+        for line in lines.iter() {
+            assert_ne!(string.chars().nth(line.start).unwrap(), ' ');
+        }
+
+        // A word that doesn't fit on a line is broken.
+        let lines = break_string(&font, "Supercalifragilistic", 20, Some(50.0));
+        assert!(lines.len() > 1);
+    }
+
+    #[test]
+    fn text_layout_alignment() {
+        let font = get_sample_font();
+
+        let string = "short\nmuch longer line";
+        let short_width = measure_string(&font, "short", 20);
+        let long_width = measure_string(&font, "much longer line", 20);
+
+        let first_glyph = font.glyph('s', 20);
+        let padding = (first_glyph.texture_rect.size.width as f32 - first_glyph.bounds.size.width) / 2.0;
+        let first_left = first_glyph.bounds.left() - padding;
+
+        let left = layout_string(&font, string, 20, Alignment::Left, 1.0, None);
+        assert_eq!(left.quads[0].bounds.left(), first_left);
+
+        let right = layout_string(&font, string, 20, Alignment::Right, 1.0, None);
+        assert_eq!(right.quads[0].bounds.left(), long_width - short_width + first_left);
+
+        let center = layout_string(&font, string, 20, Alignment::Center, 1.0, None);
+        assert_eq!(center.quads[0].bounds.left(), ((long_width - short_width) / 2.0).floor() + first_left);
+
+        // All alignments have the same bounds (the widest line).
+        assert_eq!(left.bounds, right.bounds);
+        assert_eq!(left.bounds, center.bounds);
+
+        // Justified lines fill the maximum width, except the last line of a paragraph.
+        let string = "The quick brown fox jumps over the lazy dog";
+        let justified = layout_string(&font, string, 20, Alignment::Justify, 1.0, Some(150.0));
+        assert_eq!(justified.bounds.size.width, 150.0);
+
+        let first_line: Vec<&Quad> = justified.quads.iter()
+            .filter(|quad| quad.bounds.top() < font.ascent(20))
+            .collect();
+        let right_edge = first_line.last().unwrap().bounds.right();
+        assert!(right_edge > 145.0 && right_edge <= 152.0);
+    }
+
+    #[test]
+    fn text_layout_spans() {
+        let font = get_sample_font();
+
+        let mut big = style(&font, 40);
+        big.color = Color::RED;
+
+        // The line is as tall as its biggest characters, and each quad has the color of its span.
+        let styles = [style(&font, 20), big];
+        let mut characters = characters("small ", 0);
+        characters.extend(super::tests::characters("BIG", 1));
+
+        let layout = layout(&styles, &characters, false, Alignment::Left, 1.0, None);
+        assert_eq!(layout.bounds.size.height, font.ascent(40) + font.descent(40));
+        assert_eq!(layout.bounds.size.width, measure(&shape(&styles, &characters, false), 0, characters.len()));
+
+        assert_eq!(layout.quads.len(), 8);
+        assert!(layout.quads[..5].iter().all(|quad| quad.color == Color::WHITE && quad.style == 0));
+        assert!(layout.quads[5..].iter().all(|quad| quad.color == Color::RED && quad.style == 1));
+
+        // Both spans share the same baseline.
+        let baseline = layout.rows[0].baseline;
+        assert_eq!(baseline, font.ascent(40));
+        assert_eq!(layout.carets[6].x, measure(&shape(&styles, &characters, false), 0, 6));
+    }
+
+    #[test]
+    fn text_layout_decorations() {
+        let font = get_sample_font();
+
+        let mut underlined = style(&font, 20);
+        underlined.underlined = true;
+
+        let mut struck = style(&font, 20);
+        struck.strikethrough = true;
+
+        // Consecutive decorated characters of a span produce a single quad, and trailing spaces
+        // aren't decorated.
+        let styles = [underlined, struck];
+        let mut characters = characters("ab ", 0);
+        characters.extend(super::tests::characters("cd ", 1));
+
+        let layout = layout(&styles, &characters, false, Alignment::Left, 1.0, None);
+
+        let decorations: Vec<&Quad> = layout.quads.iter()
+            .filter(|quad| quad.texture_rect == font.white_rect(20))
+            .collect();
+        assert_eq!(decorations.len(), 2);
+
+        let baseline = layout.rows[0].baseline;
+
+        let underline = decorations[0];
+        assert_eq!(underline.bounds.left(), 0.0);
+        assert_eq!(underline.bounds.right(), layout.carets[3].x);
+        assert!(underline.bounds.top() > baseline);
+
+        let strikethrough = decorations[1];
+        assert_eq!(strikethrough.bounds.left(), layout.carets[3].x);
+        assert_eq!(strikethrough.bounds.right(), layout.carets[5].x);
+        assert!(strikethrough.bounds.bottom() < baseline);
+    }
+
+    #[test]
+    fn text_layout_outline() {
+        let font = get_sample_font();
+
+        let mut outlined = style(&font, 20);
+        outlined.effects.outline_thickness = 2.0;
+        outlined.effects.outline_color = Color::BLUE;
+
+        let layout = layout(&[outlined], &characters("o", 0), false, Alignment::Left, 1.0, None);
+        assert_eq!(layout.quads.len(), 2);
+
+        let outline = &layout.quads[0];
+        let fill = &layout.quads[1];
+        assert!(outline.outline);
+        assert!(!fill.outline);
+        assert_eq!(outline.color, Color::BLUE);
+
+        assert_eq!(outline.bounds.left(), fill.bounds.left() - 2.0);
+        assert_eq!(outline.bounds.right(), fill.bounds.right() + 2.0);
+    }
+
+    #[test]
+    fn text_layout_distance_field() {
+        let mut font = get_sample_font();
+        font.set_rendering(Rendering::DistanceField);
+
+        // The outline is drawn by the shader; there is no outline quad.
+        let mut outlined = style(&font, 20);
+        outlined.effects.outline_thickness = 2.0;
+
+        let layout = layout(&[outlined], &characters("o", 0), false, Alignment::Left, 1.0, None);
+        assert_eq!(layout.quads.len(), 1);
+        assert!(!layout.quads[0].outline);
+    }
+
+    #[test]
+    fn text_reorder() {
+        assert_eq!(reorder(&[]), Vec::<usize>::new());
+        assert_eq!(reorder(&[0, 0]), vec![0, 1]);
+        assert_eq!(reorder(&[0, 1, 1, 0]), vec![0, 2, 1, 3]);
+        assert_eq!(reorder(&[1, 2, 1]), vec![2, 1, 0]);
+        assert_eq!(reorder(&[1, 2, 2, 1]), vec![3, 1, 2, 0]);
+    }
+
+    #[test]
+    fn text_layout_shaping() {
+        let font = get_sample_font();
+
+        // Arabic letters take contextual forms; they're different from the isolated forms.
+        let string = "\u{633}\u{644}\u{627}\u{645}";
+        let characters = characters(string, 0);
+        let shaping = shape(&[style(&font, 20)], &characters, true);
+
+        assert_eq!(shaping.runs.len(), 1);
+        assert_eq!(shaping.runs[0].level, 1);
+
+        let isolated: Vec<u16> = string.chars().map(|character| font.glyph_index(character)).collect();
+        let first = shaping.glyphs[0].iter().find(|glyph| glyph.character == 0).unwrap();
+        assert_ne!(first.index, isolated[0]);
+
+        // Glyphs are in visual order; the first character is on the right.
+        assert_eq!(shaping.glyphs[0].last().unwrap().character, 0);
+
+        // Without shaping, the isolated forms are used in logical order.
+        let shaping = shape(&[style(&font, 20)], &characters, false);
+        let indexes: Vec<u16> = shaping.glyphs[0].iter().map(|glyph| glyph.index).collect();
+        assert_eq!(indexes, isolated);
+    }
+
+    #[test]
+    fn text_layout_bidirectional() {
+        let font = get_sample_font();
+
+        // The Hebrew word is reordered from the right to the left, after the Latin word.
+        let styles = [style(&font, 20)];
+        let string = "ab \u{5d0}\u{5d1}\u{5d2}";
+        let layout = layout(&styles, &characters(string, 0), true, Alignment::Left, 1.0, None);
+
+        let carets = &layout.carets;
+        assert!(carets[0].x < carets[1].x);
+        assert!(carets[1].x < carets[2].x);
+        assert!(carets[2].x < carets[5].x);
+        assert!(carets[5].x < carets[4].x);
+        assert!(carets[4].x < carets[3].x);
+
+        assert!(!carets[0].right_to_left);
+        assert!(carets[3].right_to_left);
+
+        // The cursor before the first Hebrew character is on its right edge; the characters are
+        // contiguous.
+        assert_eq!(carets[3].x + carets[3].width, layout.bounds.right());
+        assert_eq!(carets[4].x + carets[4].width, carets[3].x);
+
+        // Selecting across both directions gives two areas.
+        let areas = selection(carets, &layout.rows, 1..4);
+        assert_eq!(areas.len(), 2);
+        assert_eq!(areas[0].left(), carets[1].x);
+        assert_eq!(areas[0].right(), carets[2].x + carets[2].width);
+        assert_eq!(areas[1].left(), carets[3].x);
+        assert_eq!(areas[1].right(), layout.bounds.right());
+
+        // Without shaping, the characters stay in logical order.
+        let layout = super::layout(&styles, &characters(string, 0), false, Alignment::Left, 1.0, None);
+        assert!(layout.carets[3].x < layout.carets[4].x);
+    }
+
+    #[test]
+    fn text_hit_test() {
+        let font = get_sample_font();
+
+        let layout = layout_string(&font, "ab\ncd", 20, Alignment::Left, 1.0, None);
+        let carets = &layout.carets;
+        let rows = &layout.rows;
+
+        let middle = |index: usize| {
+            let caret = carets[index];
+            Position::new(caret.x + caret.width / 2.0, rows[caret.line].baseline)
+        };
+
+        assert_eq!(hit_test(carets, rows, middle(0)), Some(0));
+        assert_eq!(hit_test(carets, rows, middle(1)), Some(1));
+        assert_eq!(hit_test(carets, rows, middle(3)), Some(3));
+        assert_eq!(hit_test(carets, rows, middle(4)), Some(4));
+
+        // Positions outside of the characters don't hit anything.
+        assert_eq!(hit_test(carets, rows, Position::new(-1.0, rows[0].baseline)), None);
+        assert_eq!(hit_test(carets, rows, Position::new(1000.0, rows[0].baseline)), None);
+        assert_eq!(hit_test(carets, rows, Position::new(1.0, -1.0)), None);
+        assert_eq!(hit_test(carets, rows, Position::new(1.0, rows[1].bottom + 1.0)), None);
+    }
+
+    #[test]
+    fn text_compute_vertices() {
+        let quads = [
+            Quad {
+                bounds: Box::new(Position::new(10.0, 20.0), Size::new(4.0, 8.0)),
+                texture_rect: Box::new(Position::new(16, 32), Size::new(4, 8)),
+                color: Color::RED,
+                style: 0,
+                outline: false
+            }
+        ];
+
+        let vertices = compute_vertices(&quads.iter().collect::<Vec<&Quad>>(), Size::new(64, 64));
+        assert_eq!(vertices.len(), 6);
+
+        assert_eq!((vertices[0].x, vertices[0].y), (10.0, 20.0));
+        assert_eq!((vertices[0].u, vertices[0].v), (0.25, 0.5));
+        assert_eq!((vertices[5].x, vertices[5].y), (14.0, 28.0));
+        assert_eq!((vertices[5].u, vertices[5].v), (0.3125, 0.625));
+
+        for vertex in vertices.iter() {
+            assert_eq!((vertex.r, vertex.g, vertex.b, vertex.a), (1.0, 0.0, 0.0, 1.0));
+        }
+    }
+}
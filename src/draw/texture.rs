@@ -5,10 +5,127 @@
 //
 // Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, January 2020
 
-use crate::geometry::Size;
+use std::io;
+use std::fmt;
+use std::cmp::Ordering;
+use std::ffi::CStr;
+use std::io::Read;
+use std::fs::File;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::OnceLock;
+use crate::geometry::{Position, Size, Box};
 use crate::image::{Color, Image};
 use crate::draw::context::{get_or_create_context, make_context_current};
 use crate::draw::gl;
+use crate::draw::ktx2;
+use crate::draw::{Wrap, TextureFormat};
+use crate::draw::debug::set_object_label;
+
+// The anisotropic filtering isn't part of OpenGL ES 3.2; it's provided by the
+// EXT_texture_filter_anisotropic extension.
+const TEXTURE_MAX_ANISOTROPY: gl::types::GLenum = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY: gl::types::GLenum = 0x84FF;
+
+// The OpenGL contexts are all created by the same graphics driver (and they share their objects),
+// so they have the same extensions; the extensions are only looked up once.
+static ANISOTROPY_SUPPORTED: OnceLock<bool> = OnceLock::new();
+
+fn is_anisotropy_supported() -> bool {
+    *ANISOTROPY_SUPPORTED.get_or_init(|| unsafe {
+        let mut count = 0;
+        gl_check!(gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count));
+
+        (0..count as u32).any(|index| {
+            gl_check!(let name = gl::GetStringi(gl::EXTENSIONS, index));
+            CStr::from_ptr(name as _).to_bytes() == b"GL_EXT_texture_filter_anisotropic"
+        })
+    })
+}
+
+// Compute the minifying and magnifying filters.
+fn filters(smooth: bool, mipmap: bool) -> (gl::types::GLenum, gl::types::GLenum) {
+    match (smooth, mipmap) {
+        (false, false) => (gl::NEAREST, gl::NEAREST),
+        (true, false) => (gl::LINEAR, gl::LINEAR),
+        (false, true) => (gl::NEAREST_MIPMAP_NEAREST, gl::NEAREST),
+        (true, true) => (gl::LINEAR_MIPMAP_LINEAR, gl::LINEAR)
+    }
+}
+
+fn from_wrap(wrap: Wrap) -> gl::types::GLenum {
+    match wrap {
+        Wrap::Clamp          => gl::CLAMP_TO_EDGE,
+        Wrap::Border         => gl::CLAMP_TO_BORDER,
+        Wrap::Repeat         => gl::REPEAT,
+        Wrap::MirroredRepeat => gl::MIRRORED_REPEAT
+    }
+}
+
+fn to_wrap(wrap: gl::types::GLenum) -> Wrap {
+    match wrap {
+        gl::CLAMP_TO_BORDER => Wrap::Border,
+        gl::REPEAT          => Wrap::Repeat,
+        gl::MIRRORED_REPEAT => Wrap::MirroredRepeat,
+        _                   => Wrap::Clamp
+    }
+}
+
+// Compute the internal format, the format and the type of the pixels of a texture format (the
+// format and the type are the ones of the uploaded data).
+fn from_texture_format(format: TextureFormat) -> (gl::types::GLenum, gl::types::GLenum, gl::types::GLenum) {
+    match format {
+        TextureFormat::R8       => (gl::R8,                 gl::RED,             gl::UNSIGNED_BYTE),
+        TextureFormat::Rg8      => (gl::RG8,                gl::RG,              gl::UNSIGNED_BYTE),
+        TextureFormat::Rgba8    => (gl::RGBA8,              gl::RGBA,            gl::UNSIGNED_BYTE),
+        TextureFormat::Srgba8   => (gl::SRGB8_ALPHA8,       gl::RGBA,            gl::UNSIGNED_BYTE),
+        TextureFormat::Rgba16F  => (gl::RGBA16F,            gl::RGBA,            gl::FLOAT),
+        TextureFormat::Rgba32F  => (gl::RGBA32F,            gl::RGBA,            gl::FLOAT),
+        TextureFormat::Depth24  => (gl::DEPTH_COMPONENT24,  gl::DEPTH_COMPONENT, gl::UNSIGNED_INT),
+        TextureFormat::Depth32F => (gl::DEPTH_COMPONENT32F, gl::DEPTH_COMPONENT, gl::FLOAT),
+        TextureFormat::Compressed => unreachable!("the compressed textures have no uncompressed data")
+    }
+}
+
+// The unsized RGBA format is accepted because some drivers report it for the empty textures.
+fn to_texture_format(internal_format: gl::types::GLenum) -> TextureFormat {
+    match internal_format {
+        gl::R8                 => TextureFormat::R8,
+        gl::RG8                => TextureFormat::Rg8,
+        gl::RGBA8 | gl::RGBA   => TextureFormat::Rgba8,
+        gl::SRGB8_ALPHA8       => TextureFormat::Srgba8,
+        gl::RGBA16F            => TextureFormat::Rgba16F,
+        gl::RGBA32F            => TextureFormat::Rgba32F,
+        gl::DEPTH_COMPONENT24  => TextureFormat::Depth24,
+        gl::DEPTH_COMPONENT32F => TextureFormat::Depth32F,
+        format                 => unreachable!("the texture has an unknown format ({:#x})", format)
+    }
+}
+
+// Convert depth values to 32-bit normalized integers (the 24-bit depth textures can't be uploaded
+// from floating-point numbers).
+fn depth_to_integers(values: &[f32]) -> Vec<u32> {
+    values.iter()
+        .map(|value| (value.clamp(0.0, 1.0) as f64 * u32::MAX as f64).round() as u32)
+        .collect()
+}
+
+// Keep the first channels of RGBA pixels (the pixels are always read as RGBA pixels).
+fn keep_channels<T: Copy>(pixels: &[T], channels: usize) -> Vec<T> {
+    pixels.chunks(4)
+        .flat_map(|pixel| pixel[..channels].iter().copied())
+        .collect()
+}
+
+// Clamp an area to the bounds of an image (the area is empty if it's entirely outside).
+fn clamp_area(area: Box<i32>, size: Size<i32>) -> Box<i32> {
+    let left = area.left().max(0).min(size.width);
+    let top = area.top().max(0).min(size.height);
+    let right = area.right().max(left).min(size.width);
+    let bottom = area.bottom().max(top).min(size.height);
+
+    Box::new(Position::new(left, top), Size::new(right - left, bottom - top))
+}
 
 /// An image stored on the graphics card.
 ///
@@ -18,7 +135,7 @@ use crate::draw::gl;
 ///
 /// - Double-check implementation of the resize() method. According to SFML implementation, there
 ///   might be some hardware limitation (would need to computer the nearest power of two).
-/// - The sRGB and minimap features aren't implemented yet.
+/// - The sRGB feature isn't implemented yet.
 /// - For now, the interface of Texture is kept simple and clean. It should be understood as the
 ///   counter-part of Image. Image provides load/save methods to load the image from a file then
 ///   save the image to a file. The Texture class also provides load/save methods, but instead it
@@ -29,11 +146,36 @@ use crate::draw::gl;
 ///   texture with this image.
 /// - The set_repeated() implementation is different from SFML, double-check it. It uses the
 ///   CLAMP_TO_EDGE constant.
-/// - Investigate OpenGL sampler objects; they seem to be related to texture and add them features.
-///   See what it is and if this item can be improved.
+/// - The sampling parameters (filters, wrapping modes, border color and anisotropy) are stored in
+///   the texture object; OpenGL sampler objects would allow to sample the same texture differently
+///   but they're not needed yet.
+/// - Uploading new content discards the mipmap; it must be generated again.
+/// - The textures constructed from an image (and resized textures) are RGBA8 textures; the other
+///   pixel formats are only available with `with_format()`. The conversions to and from images
+///   only work with the 8-bit formats.
+/// - The compressed textures can't be updated, downloaded or converted to images, and their mipmap
+///   can't be generated; it's loaded from the KTX2 file. A texture loaded from a KTX2 file without
+///   a mipmap isn't smooth by default, as any other texture.
+/// - Downloading a depth texture isn't possible because OpenGL ES can't read the depth values of a
+///   framebuffer (it would have to be drawn on a floating-point texture by a shader).
+/// - The floating-point formats are color-renderable, but `Rgba32F` textures can't be smooth
+///   unless the OES_texture_float_linear extension is supported.
 ///
 pub struct Texture {
-    object: gl::types::GLuint
+    object: Rc<TextureObject> // shared with the shaders which sample the texture
+}
+
+// The OpenGL texture object of a texture; it's shared with the shaders the texture is given to
+// (see `Shader::set_uniform()`) and deleted once they're all dropped, so a shader never binds a
+// deleted (or recycled) texture.
+pub(crate) struct TextureObject(pub(crate) gl::types::GLuint);
+
+impl Drop for TextureObject {
+    fn drop(&mut self) {
+        unsafe {
+            gl_check!(gl::DeleteTextures(1, &self.0));
+        }
+    }
 }
 
 impl Texture {
@@ -56,7 +198,7 @@ impl Texture {
         };
 
         let mut texture = Texture {
-            object: object
+            object: Rc::new(TextureObject(object))
         };
 
         texture.set_smooth(false);
@@ -87,13 +229,115 @@ impl Texture {
         texture
     }
 
-    /// Brief description
+    /// Constructs a texture of a given pixel format.
     ///
-    /// The **open() function** is not documented yet. Pull requests are welcome.
+    /// This function constructs a texture of a given size and pixel format; its content is
+    /// undefined until it's updated with `update_bytes()` or `update_floats()` (or until it's drawn
+    /// on).
     ///
-    pub fn open(_filename: &str) -> Texture {
-        // To be implemented.
-        Texture::new()
+    pub fn with_format(size: Size<i32>, format: TextureFormat) -> Texture {
+        assert!(!format.is_compressed(), "the compressed textures can only be loaded from KTX2 files");
+        let (internal_format, format, kind) = from_texture_format(format);
+
+        let texture = Texture::new();
+        texture.bind();
+        unsafe {
+            gl_check!(gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                internal_format as _,
+                size.width,
+                size.height,
+                0,
+                format,
+                kind,
+                std::ptr::null()
+            ));
+        }
+
+        texture
+    }
+
+    /// Constructs a texture from a sub-rectangle of an image.
+    ///
+    /// This function constructs a texture from an area of an image (in pixels); the area is
+    /// clamped to the bounds of the image. It's useful to load a single element of a sprite sheet.
+    ///
+    pub fn with_image_area(image: &Image, area: Box<i32>) -> Texture {
+        let mut texture = Texture::new();
+        texture.upload_image(image, area);
+
+        texture
+    }
+
+    /// Constructs a texture from an image file.
+    ///
+    /// This function decodes an image file (see `Image::open()`) and uploads it to a new texture.
+    /// An error is returned if the file can't be read or decoded.
+    ///
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Texture> {
+        let image = Image::open(path)?;
+        Ok(Texture::with_image(&image))
+    }
+
+    /// Constructs a texture from a reader.
+    ///
+    /// This function decodes an image from a reader (see `Image::load()`) and uploads it to a new
+    /// texture. An error is returned if the image can't be read or decoded.
+    ///
+    pub fn load<R: Read>(reader: &mut R) -> io::Result<Texture> {
+        let mut image = Image::new();
+        image.load(reader)?;
+
+        Ok(Texture::with_image(&image))
+    }
+
+    /// Constructs a compressed texture from a KTX2 file.
+    ///
+    /// This function loads a KTX2 file holding a texture compressed with one of the formats that
+    /// are supported by all OpenGL ES 3.2 implementations (ETC2, EAC or ASTC); the mipmap stored in
+    /// the file, if any, is loaded too. An error is returned if the file can't be read, or if it's
+    /// not a valid or supported KTX2 file.
+    ///
+    pub fn open_ktx2<P: AsRef<Path>>(path: P) -> io::Result<Texture> {
+        let mut file = File::open(path)?;
+        Texture::load_ktx2(&mut file)
+    }
+
+    /// Constructs a compressed texture from a reader.
+    ///
+    /// This function reads a KTX2 file from a reader (see `open_ktx2()`).
+    ///
+    pub fn load_ktx2<R: Read>(reader: &mut R) -> io::Result<Texture> {
+        let ktx2 = ktx2::read(reader)?;
+
+        let mut texture = Texture::new();
+        texture.bind();
+
+        unsafe {
+            for (level, data) in ktx2.levels.iter().enumerate() {
+                let size = ktx2::level_size(ktx2.size, level);
+                gl_check!(gl::CompressedTexImage2D(
+                    gl::TEXTURE_2D,
+                    level as _,
+                    ktx2.format.internal_format,
+                    size.width,
+                    size.height,
+                    0,
+                    data.len() as _,
+                    data.as_ptr() as _
+                ));
+            }
+
+            // The mipmap may be incomplete (it may not go down to a single pixel).
+            gl_check!(gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAX_LEVEL, ktx2.levels.len() as i32 - 1));
+        }
+
+        if ktx2.levels.len() > 1 {
+            texture.update_filters(false, true);
+        }
+
+        Ok(texture)
     }
 
     /// Brief description
@@ -129,6 +373,110 @@ impl Texture {
         Size::new(width, height)
     }
 
+    /// Returns the pixel format of the texture.
+    ///
+    /// This function returns the pixel format of the texture; it's `TextureFormat::Rgba8` unless
+    /// the texture was constructed with `with_format()`, loaded from a KTX2 file, or it's the
+    /// texture of an sRGB surface.
+    ///
+    pub fn format(&self) -> TextureFormat {
+        self.bind();
+
+        let (compressed, internal_format) = unsafe {
+            let mut compressed = 0;
+            gl_check!(gl::GetTexLevelParameteriv(
+                gl::TEXTURE_2D,
+                0,
+                gl::TEXTURE_COMPRESSED,
+                &mut compressed
+            ));
+
+            let mut internal_format = 0;
+            gl_check!(gl::GetTexLevelParameteriv(
+                gl::TEXTURE_2D,
+                0,
+                gl::TEXTURE_INTERNAL_FORMAT,
+                &mut internal_format
+            ));
+
+            (compressed != 0, internal_format)
+        };
+
+        if compressed {
+            return TextureFormat::Compressed
+        }
+
+        to_texture_format(internal_format as gl::types::GLenum)
+    }
+
+    /// Updates the texture with bytes.
+    ///
+    /// This function updates the content of a texture of a 8-bit format (see
+    /// `TextureFormat::is_byte()`); the bytes are the channels of the pixels, row by row, and they
+    /// must cover the entire texture. The size and the format of the texture don't change.
+    ///
+    pub fn update_bytes(&mut self, bytes: &[u8]) {
+        let format = self.format();
+        assert!(format.is_byte(), "the texture doesn't have a 8-bit format");
+        assert_eq!(bytes.len(), self.pixel_count() * format.channels(), "the bytes don't cover the texture");
+
+        self.upload_data(format, bytes.as_ptr() as _);
+    }
+
+    /// Updates the texture with floating-point numbers.
+    ///
+    /// This function updates the content of a texture of a floating-point or a depth format; the
+    /// numbers are the channels of the pixels, row by row, and they must cover the entire texture.
+    /// The depth values of a `TextureFormat::Depth24` texture are clamped to the [0, 1] range. The
+    /// size and the format of the texture don't change.
+    ///
+    pub fn update_floats(&mut self, values: &[f32]) {
+        let format = self.format();
+        assert!(!format.is_byte(), "the texture has a 8-bit format");
+        assert!(!format.is_compressed(), "the content of a compressed texture can't be updated");
+        assert_eq!(values.len(), self.pixel_count() * format.channels(), "the values don't cover the texture");
+
+        if format == TextureFormat::Depth24 {
+            let integers = depth_to_integers(values);
+            self.upload_data(format, integers.as_ptr() as _);
+        }
+        else {
+            self.upload_data(format, values.as_ptr() as _);
+        }
+    }
+
+    /// Returns the content of the texture as bytes.
+    ///
+    /// This function downloads the content of a texture of a 8-bit format; the bytes are the
+    /// channels of the pixels, row by row.
+    ///
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let format = self.format();
+        assert!(format.is_byte(), "the texture doesn't have a 8-bit format");
+
+        let mut pixels = vec![0u8; self.pixel_count() * 4];
+        self.read_pixels(gl::UNSIGNED_BYTE, pixels.as_mut_ptr() as _);
+
+        keep_channels(&pixels, format.channels())
+    }
+
+    /// Returns the content of the texture as floating-point numbers.
+    ///
+    /// This function downloads the content of a texture of a floating-point format; the numbers
+    /// are the channels of the pixels, row by row. The depth textures can't be downloaded.
+    ///
+    pub fn to_floats(&self) -> Vec<f32> {
+        let format = self.format();
+        assert!(!format.is_byte(), "the texture has a 8-bit format");
+        assert!(!format.is_depth(), "the content of a depth texture can't be downloaded");
+        assert!(!format.is_compressed(), "the content of a compressed texture can't be downloaded");
+
+        let mut pixels = vec![0.0f32; self.pixel_count() * 4];
+        self.read_pixels(gl::FLOAT, pixels.as_mut_ptr() as _);
+
+        keep_channels(&pixels, format.channels())
+    }
+
     /// Brief description
     ///
     /// The **resize() function** is not documented yet. Pull requests are welcome.
@@ -147,25 +495,8 @@ impl Texture {
     /// The **update_image() function** is not documented yet. Pull requests are welcome.
     ///
     pub fn update_image(&mut self, image: &Image) {
-        // Compute height of the image.
         let size = image.size();
-        let pixels = image.pixels();
-
-        self.bind();
-
-        unsafe {
-            gl_check!(gl::TexImage2D(
-                gl::TEXTURE_2D,
-                0,
-                gl::RGBA as _,
-                size.width as _,
-                size.height as _,
-                0,
-                gl::RGBA,
-                gl::UNSIGNED_BYTE,
-                pixels.as_ptr() as _
-            ));
-        }
+        self.upload_image(image, Box::new(Position::zero(), Size::new(size.width as i32, size.height as i32)));
     }
 
     /// Brief description
@@ -173,42 +504,12 @@ impl Texture {
     /// The **to_image() function** is not documented yet. Pull requests are welcome.
     ///
     pub fn to_image(&self) -> Image {
-        // - GetTexPixels is not available, have to create a framebuffer, attach texture and use
-        // glReadPixels()
-        // - restore previous framebuffer bind ?
-        self.bind();
+        assert!(self.format().is_byte(), "the texture doesn't have a 8-bit format");
 
         let size = self.size();
 
-        let capacity = (size.width * size.height) as usize;
-        let mut pixels = Vec::<Color>::with_capacity(capacity);
-        pixels.resize(capacity, Color::BLACK); // not necessary
-
-        unsafe {
-            let mut framebuffer = 0;
-            gl_check!(gl::GenFramebuffers(1, &mut framebuffer));
-            gl_check!(gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer));
-
-            gl_check!(gl::FramebufferTexture2D(
-                gl::FRAMEBUFFER,
-                gl::COLOR_ATTACHMENT0,
-                gl::TEXTURE_2D,
-                self.object,
-                0
-            ));
-
-            gl_check!(gl::ReadPixels(
-                0,
-                0,
-                size.width,
-                size.height,
-                gl::RGBA,
-                gl::UNSIGNED_BYTE,
-                pixels.as_mut_ptr() as _
-            ));
-
-            gl_check!(gl::DeleteFramebuffers(1, &mut framebuffer));
-        }
+        let mut pixels = vec![Color::BLACK; self.pixel_count()];
+        self.read_pixels(gl::UNSIGNED_BYTE, pixels.as_mut_ptr() as _);
 
         let size_usize = Size::new(size.width as usize, size.height as usize); // should be removed
         Image::with_pixels(size_usize, pixels).unwrap()
@@ -219,63 +520,94 @@ impl Texture {
     /// The **is_smooth() function** is not documented yet. Pull requests are welcome.
     ///
     pub fn is_smooth(&self) -> bool {
-        // Make the OpenGL texture object current (so GetTexParameteri() operates on it).
-        self.bind();
+        // The smooth property is given by the magnifying filter (the minifying filter may also
+        // involve the mipmap).
+        self.parameter(gl::TEXTURE_MAG_FILTER) as gl::types::GLenum == gl::LINEAR
+    }
 
-        // Ask OpenGL the smooth property of the OpenGL texture object.
-        let smooth = unsafe {
-            let mut mag_filter: i32 = 0;
-            let mut min_filter: i32 = 0;
+    /// Brief description
+    ///
+    /// The **set_smooth() function** is not documented yet. Pull requests are welcome.
+    ///
+    /// If the texture has a mipmap, a smooth texture is filtered trilinearly (the two nearest
+    /// levels of the mipmap are interpolated).
+    ///
+    pub fn set_smooth(&mut self, smooth: bool) {
+        let mipmap = self.has_mipmap();
+        self.update_filters(smooth, mipmap);
+    }
 
-            gl_check!(gl::GetTexParameteriv(
-                gl::TEXTURE_2D,
-                gl::TEXTURE_MAG_FILTER,
-                &mut mag_filter
-            ));
+    /// Returns whether the texture has a mipmap.
+    ///
+    /// This function returns whether the mipmap of the texture was generated, and is used when
+    /// the texture is minified.
+    ///
+    pub fn has_mipmap(&self) -> bool {
+        let filter = self.parameter(gl::TEXTURE_MIN_FILTER) as gl::types::GLenum;
+        filter != gl::NEAREST && filter != gl::LINEAR
+    }
 
-            gl_check!(gl::GetTexParameteriv(
-                gl::TEXTURE_2D,
-                gl::TEXTURE_MAG_FILTER,
-                &mut min_filter
-            ));
+    /// Generates the mipmap of the texture.
+    ///
+    /// This function generates the mipmap of the texture (the successive halves of the texture,
+    /// down to a single pixel) and uses it when the texture is minified; it prevents the texture
+    /// from shimmering when it's drawn smaller than its size (for instance, in a zoomed-out view).
+    ///
+    /// The mipmap is discarded when the content of the texture is updated, it must be generated
+    /// again.
+    ///
+    pub fn generate_mipmap(&mut self) {
+        self.bind();
+        unsafe {
+            // A texture loaded from a KTX2 file may have its number of levels limited.
+            gl_check!(gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAX_LEVEL, 1000));
+            gl_check!(gl::GenerateMipmap(gl::TEXTURE_2D));
+        }
 
-            assert_eq!(mag_filter, min_filter, "buggy graphics driver");
+        let smooth = self.is_smooth();
+        self.update_filters(smooth, true);
+    }
 
-            if mag_filter as gl::types::GLenum == gl::LINEAR {
-                true
-            }
-            else if mag_filter as gl::types::GLenum == gl::NEAREST {
-                false
-            }
-            else {
-                panic!("buggy graphics driver");
-            }
-        };
+    /// Returns the anisotropy level of the texture.
+    ///
+    /// This function returns the maximum number of samples taken when the texture is filtered
+    /// (1.0 if the anisotropic filtering is disabled or not supported).
+    ///
+    pub fn anisotropy(&self) -> f32 {
+        if !is_anisotropy_supported() {
+            return 1.0
+        }
+
+        self.bind();
+        unsafe {
+            let mut value = 1.0;
+            gl_check!(gl::GetTexParameterfv(gl::TEXTURE_2D, TEXTURE_MAX_ANISOTROPY, &mut value));
 
-        smooth
+            value
+        }
     }
 
-    /// Brief description
+    /// Changes the anisotropy level of the texture.
     ///
-    /// The **set_smooth() function** is not documented yet. Pull requests are welcome.
+    /// This function changes the maximum number of samples taken when the texture is filtered; the
+    /// anisotropic filtering keeps the texture sharp when it's seen at an angle (or stretched). The
+    /// level is clamped to the maximum level supported by the graphics card, and it does nothing if
+    /// the anisotropic filtering isn't supported. The default level is 1.0 (disabled).
     ///
-    pub fn set_smooth(&mut self, smooth: bool) {
-        // Make the OpenGL texture object current (so TexParameteri() operates on it).
-        self.bind();
+    pub fn set_anisotropy(&mut self, level: f32) {
+        if !is_anisotropy_supported() {
+            return
+        }
 
+        self.bind();
         unsafe {
-            let value = if smooth { gl::LINEAR } else { gl::NEAREST };
+            let mut maximum_level = 1.0;
+            gl_check!(gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY, &mut maximum_level));
 
-            gl_check!(gl::TexParameteri(
+            gl_check!(gl::TexParameterf(
                 gl::TEXTURE_2D,
-                gl::TEXTURE_MAG_FILTER,
-                value as gl::types::GLint
-            ));
-
-            gl_check!(gl::TexParameteri(
-                gl::TEXTURE_2D,
-                gl::TEXTURE_MIN_FILTER,
-                value as gl::types::GLint
+                TEXTURE_MAX_ANISOTROPY,
+                level.max(1.0).min(maximum_level)
             ));
         }
     }
@@ -285,85 +617,260 @@ impl Texture {
     /// The **is_repeated() function** is not documented yet. Pull requests are welcome.
     ///
     pub fn is_repeated(&self) -> bool {
-        // Make the OpenGL texture object current (so GetTexParameteri() operates on it).
-        self.bind();
+        self.wrap() == (Wrap::Repeat, Wrap::Repeat)
+    }
 
-        // Ask OpenGL the smooth property of the OpenGL texture object.
-        let repeated = unsafe {
-            let mut wrap_s: i32 = 0;
-            let mut wrap_t: i32 = 0;
+    /// Brief description
+    ///
+    /// The **set_repeated() function** is not documented yet. Pull requests are welcome.
+    ///
+    pub fn set_repeated(&mut self, repeat: bool) {
+        let wrap = if repeat { Wrap::Repeat } else { Wrap::Clamp };
+        self.set_wrap(wrap, wrap);
+    }
 
-            gl_check!(gl::GetTexParameteriv(
-                gl::TEXTURE_2D,
-                gl::TEXTURE_WRAP_S,
-                &mut wrap_s
-            ));
+    /// Returns the wrapping modes of the texture.
+    ///
+    /// This function returns the wrapping modes of the horizontal axis and the vertical axis of
+    /// the texture.
+    ///
+    pub fn wrap(&self) -> (Wrap, Wrap) {
+        (
+            to_wrap(self.parameter(gl::TEXTURE_WRAP_S) as gl::types::GLenum),
+            to_wrap(self.parameter(gl::TEXTURE_WRAP_T) as gl::types::GLenum)
+        )
+    }
 
-            gl_check!(gl::GetTexParameteriv(
-                gl::TEXTURE_2D,
-                gl::TEXTURE_WRAP_T,
-                &mut wrap_t
-            ));
+    /// Changes the wrapping modes of the texture.
+    ///
+    /// This function changes how the texture is sampled outside of its bounds, on the horizontal
+    /// axis and on the vertical axis. The default wrapping modes are `Wrap::Clamp`.
+    ///
+    pub fn set_wrap(&mut self, horizontal: Wrap, vertical: Wrap) {
+        self.bind();
+        unsafe {
+            gl_check!(gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, from_wrap(horizontal) as _));
+            gl_check!(gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, from_wrap(vertical) as _));
+        }
+    }
 
-            assert_eq!(wrap_s, wrap_t, "buggy graphics driver");
+    /// Returns the border color of the texture.
+    ///
+    /// This function returns the color used outside of the bounds of the texture when the
+    /// wrapping mode is `Wrap::Border`.
+    ///
+    pub fn border_color(&self) -> Color {
+        self.bind();
 
-            if wrap_s as gl::types::GLenum == gl::REPEAT {
-                true
-            }
-            else if wrap_s as gl::types::GLenum == gl::CLAMP_TO_EDGE {
-                false
-            }
-            else {
-                panic!("buggy graphics driver");
-            }
-        };
+        let mut color = [0.0; 4];
+        unsafe {
+            gl_check!(gl::GetTexParameterfv(gl::TEXTURE_2D, gl::TEXTURE_BORDER_COLOR, color.as_mut_ptr()));
+        }
 
-        repeated
+        let [red, green, blue, alpha] = color;
+        Color::rgba(
+            (red * 255.0).round() as u8,
+            (green * 255.0).round() as u8,
+            (blue * 255.0).round() as u8,
+            (alpha * 255.0).round() as u8
+        )
     }
 
-    /// Brief description
+    /// Changes the border color of the texture.
     ///
-    /// The **set_repeated() function** is not documented yet. Pull requests are welcome.
+    /// This function changes the color used outside of the bounds of the texture when the
+    /// wrapping mode is `Wrap::Border`. The default border color is transparent.
     ///
-    pub fn set_repeated(&mut self, repeat: bool) {
+    pub fn set_border_color(&mut self, color: Color) {
+        let color = [
+            color.red as f32 / 255.0,
+            color.green as f32 / 255.0,
+            color.blue as f32 / 255.0,
+            color.alpha as f32 / 255.0
+        ];
+
+        self.bind();
+        unsafe {
+            gl_check!(gl::TexParameterfv(gl::TEXTURE_2D, gl::TEXTURE_BORDER_COLOR, color.as_ptr()));
+        }
+    }
+
+    /// Changes the label of the texture.
+    ///
+    /// This function attaches a label to the underlying OpenGL texture object; it identifies the
+    /// texture in the debug messages (see the `debug` module) and in the debugging tools.
+    ///
+    pub fn set_label(&mut self, label: &str) {
+        set_object_label(gl::TEXTURE, self.object.0, label);
+    }
+
+    fn parameter(&self, parameter: gl::types::GLenum) -> i32 {
+        // Make the OpenGL texture object current (so GetTexParameteriv() operates on it).
         self.bind();
 
         unsafe {
-            let value = if repeat { gl::REPEAT } else { gl::CLAMP_TO_EDGE };
+            let mut value = 0;
+            gl_check!(gl::GetTexParameteriv(gl::TEXTURE_2D, parameter, &mut value));
 
-            gl_check!(gl::TexParameteri(
+            value
+        }
+    }
+
+    fn update_filters(&mut self, smooth: bool, mipmap: bool) {
+        let (minifying_filter, magnifying_filter) = filters(smooth, mipmap);
+
+        self.bind();
+        unsafe {
+            gl_check!(gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, minifying_filter as _));
+            gl_check!(gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, magnifying_filter as _));
+        }
+    }
+
+    fn pixel_count(&self) -> usize {
+        let size = self.size();
+        (size.width * size.height) as usize
+    }
+
+    fn upload_data(&mut self, format: TextureFormat, data: *const std::ffi::c_void) {
+        let (_, format, kind) = from_texture_format(format);
+        let size = self.size();
+
+        self.bind();
+        unsafe {
+            gl_check!(gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1));
+            gl_check!(gl::TexSubImage2D(
                 gl::TEXTURE_2D,
-                gl::TEXTURE_WRAP_S,
-                value as gl::types::GLint
+                0,
+                0,
+                0,
+                size.width,
+                size.height,
+                format,
+                kind,
+                data
             ));
+        }
+    }
+
+    // Read the pixels of the texture as RGBA pixels of a given type (GetTexImage() isn't available
+    // so the texture is attached to a temporary framebuffer); the framebuffers that were bound
+    // (typically, the one of the current surface) are bound again afterward.
+    fn read_pixels(&self, kind: gl::types::GLenum, pixels: *mut std::ffi::c_void) {
+        let size = self.size();
 
-            gl_check!(gl::TexParameteri(
+        unsafe {
+            let mut draw_framebuffer = 0;
+            let mut read_framebuffer = 0;
+            gl_check!(gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut draw_framebuffer));
+            gl_check!(gl::GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut read_framebuffer));
+
+            let mut framebuffer = 0;
+            gl_check!(gl::GenFramebuffers(1, &mut framebuffer));
+            gl_check!(gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer));
+
+            gl_check!(gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
                 gl::TEXTURE_2D,
-                gl::TEXTURE_WRAP_T,
-                value as gl::types::GLint
+                self.object.0,
+                0
+            ));
+
+            gl_check!(gl::ReadPixels(
+                0,
+                0,
+                size.width,
+                size.height,
+                gl::RGBA,
+                kind,
+                pixels
             ));
+
+            gl_check!(gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, draw_framebuffer as _));
+            gl_check!(gl::BindFramebuffer(gl::READ_FRAMEBUFFER, read_framebuffer as _));
+            gl_check!(gl::DeleteFramebuffers(1, &framebuffer));
         }
     }
 
-    pub(crate) fn bind(&self) {
+    fn upload_image(&mut self, image: &Image, area: Box<i32>) {
+        let size = image.size();
+        let area = clamp_area(area, Size::new(size.width as i32, size.height as i32));
+
+        self.bind();
+
+        // The area is selected with the unpacking parameters, the pixels aren't copied.
         unsafe {
-            gl_check!(gl::BindTexture(gl::TEXTURE_2D, self.object));
+            gl_check!(gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1));
+            gl_check!(gl::PixelStorei(gl::UNPACK_ROW_LENGTH, size.width as _));
+            gl_check!(gl::PixelStorei(gl::UNPACK_SKIP_PIXELS, area.left()));
+            gl_check!(gl::PixelStorei(gl::UNPACK_SKIP_ROWS, area.top()));
+
+            gl_check!(gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA8 as _,
+                area.size.width,
+                area.size.height,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                image.pixels().as_ptr() as _
+            ));
+
+            gl_check!(gl::PixelStorei(gl::UNPACK_ROW_LENGTH, 0));
+            gl_check!(gl::PixelStorei(gl::UNPACK_SKIP_PIXELS, 0));
+            gl_check!(gl::PixelStorei(gl::UNPACK_SKIP_ROWS, 0));
+        }
+
+        // The other levels of the mipmap are outdated (the texture would be incomplete).
+        if self.has_mipmap() {
+            let smooth = self.is_smooth();
+            self.update_filters(smooth, false);
         }
     }
-}
 
-impl Drop for Texture {
-    fn drop(&mut self) {
+    pub(crate) fn object(&self) -> gl::types::GLuint {
+        self.object.0
+    }
+
+    pub(crate) fn shared_object(&self) -> Rc<TextureObject> {
+        self.object.clone()
+    }
+
+    pub(crate) fn bind(&self) {
         unsafe {
-            gl_check!(gl::DeleteTextures(1, &self.object));
+            gl_check!(gl::BindTexture(gl::TEXTURE_2D, self.object.0));
         }
     }
 }
 
+// A texture is identified by its OpenGL texture object; two textures are equal only if they're the
+// same texture (it makes the uniforms comparable, see `Uniform::Texture`).
+impl PartialEq for Texture {
+    fn eq(&self, other: &Texture) -> bool {
+        Rc::ptr_eq(&self.object, &other.object)
+    }
+}
+
+impl PartialOrd for Texture {
+    fn partial_cmp(&self, other: &Texture) -> Option<Ordering> {
+        self.object.0.partial_cmp(&other.object.0)
+    }
+}
+
+impl fmt::Debug for Texture {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Texture").field("object", &self.object.0).finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::draw::{Vertex, VertexArray, Primitive, Usage, RenderStates};
+    use crate::draw::testing::{lock_rendering, render, pixel};
+
     fn make_image() -> Image {
         let pixels = vec![
             Color::BLACK,
@@ -377,8 +884,64 @@ mod tests {
         Image::with_pixels(size, pixels).unwrap()
     }
 
+    #[test]
+    fn texture_filters() {
+        assert_eq!(filters(false, false), (gl::NEAREST, gl::NEAREST));
+        assert_eq!(filters(true, false), (gl::LINEAR, gl::LINEAR));
+        assert_eq!(filters(false, true), (gl::NEAREST_MIPMAP_NEAREST, gl::NEAREST));
+        assert_eq!(filters(true, true), (gl::LINEAR_MIPMAP_LINEAR, gl::LINEAR));
+    }
+
+    #[test]
+    fn texture_wrap_conversions() {
+        for wrap in [Wrap::Clamp, Wrap::Border, Wrap::Repeat, Wrap::MirroredRepeat].iter() {
+            assert_eq!(to_wrap(from_wrap(*wrap)), *wrap);
+        }
+    }
+
+    #[test]
+    fn texture_format_conversions() {
+        for format in [
+            TextureFormat::R8, TextureFormat::Rg8, TextureFormat::Rgba8, TextureFormat::Srgba8,
+            TextureFormat::Rgba16F, TextureFormat::Rgba32F, TextureFormat::Depth24,
+            TextureFormat::Depth32F
+        ].iter() {
+            let (internal_format, _, _) = from_texture_format(*format);
+            assert_eq!(to_texture_format(internal_format), *format);
+        }
+
+        assert_eq!(to_texture_format(gl::RGBA), TextureFormat::Rgba8);
+    }
+
+    #[test]
+    fn texture_depth_to_integers() {
+        assert_eq!(depth_to_integers(&[0.0, 1.0, -1.0, 2.0]), vec![0, u32::MAX, 0, u32::MAX]);
+        assert_eq!(depth_to_integers(&[0.5]), vec![2_147_483_648]);
+    }
+
+    #[test]
+    fn texture_keep_channels() {
+        let pixels = [1, 2, 3, 4, 5, 6, 7, 8];
+        assert_eq!(keep_channels(&pixels, 1), vec![1, 5]);
+        assert_eq!(keep_channels(&pixels, 2), vec![1, 2, 5, 6]);
+        assert_eq!(keep_channels(&pixels, 4), pixels.to_vec());
+    }
+
+    #[test]
+    fn texture_clamp_area() {
+        let size = Size::new(64, 32);
+        let area = |x, y, width, height| Box::new(Position::new(x, y), Size::new(width, height));
+
+        assert_eq!(clamp_area(area(8, 8, 16, 16), size), area(8, 8, 16, 16));
+        assert_eq!(clamp_area(area(-8, -8, 16, 16), size), area(0, 0, 8, 8));
+        assert_eq!(clamp_area(area(56, 24, 16, 16), size), area(56, 24, 8, 8));
+        assert_eq!(clamp_area(area(100, 100, 16, 16), size), area(64, 32, 0, 0));
+    }
+
     #[test]
     fn texture_new() {
+        let _lock = lock_rendering();
+
         let texture = Texture::new();
         assert_eq!(texture.size(), Size::new(0, 0));
         assert_eq!(texture.is_smooth(), false);
@@ -387,6 +950,8 @@ mod tests {
 
     #[test]
     fn texture_with_size() {
+        let _lock = lock_rendering();
+
         let texture = Texture::with_size(Size::new(25, 50), Color::RED);
         assert_eq!(texture.size(), Size::new(25, 50));
         assert_eq!(texture.is_smooth(), false);
@@ -401,6 +966,8 @@ mod tests {
 
     #[test]
     fn texture_with_image() {
+        let _lock = lock_rendering();
+
         let image = make_image();
 
         let texture = Texture::with_image(&image);
@@ -409,9 +976,31 @@ mod tests {
         assert_eq!(texture.is_repeated(), false);
     }
 
+    #[test]
+    fn texture_with_image_area() {
+        let _lock = lock_rendering();
+
+        let image = make_image();
+
+        let area = Box::new(Position::new(1, 1), Size::new(4, 4));
+        let texture = Texture::with_image_area(&image, area);
+        assert_eq!(texture.size(), Size::new(2, 1));
+        assert_eq!(texture.to_image().pixels(), &image.pixels()[4..6].to_vec());
+    }
+
     #[test]
     fn texture_open() {
-        // To be implemented.
+        let _lock = lock_rendering();
+
+        let mut filename = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        filename.push("resources/samples.png");
+
+        let image = Image::open(&filename).unwrap();
+        let texture = Texture::open(&filename).unwrap();
+        assert_eq!(texture.to_image(), image);
+
+        filename.set_file_name("missing.png");
+        assert!(Texture::open(&filename).is_err());
     }
 
     #[test]
@@ -426,6 +1015,8 @@ mod tests {
 
     #[test]
     fn texture_to_image() {
+        let _lock = lock_rendering();
+
         let image = make_image();
 
         let texture = Texture::with_image(&image);
@@ -434,6 +1025,8 @@ mod tests {
 
     #[test]
     fn texture_smooth() {
+        let _lock = lock_rendering();
+
         let mut texture = Texture::new();
         assert_eq!(texture.is_smooth(), false);
 
@@ -446,6 +1039,8 @@ mod tests {
 
     #[test]
     fn texture_repeated() {
+        let _lock = lock_rendering();
+
         let mut texture = Texture::new();
         assert_eq!(texture.is_repeated(), false);
 
@@ -456,9 +1051,104 @@ mod tests {
         assert_eq!(texture.is_repeated(), false);
     }
 
+    #[test]
+    fn texture_mipmap() {
+        let _lock = lock_rendering();
+
+        let mut texture = Texture::with_image(&make_image());
+        texture.set_smooth(true);
+        assert_eq!(texture.has_mipmap(), false);
+
+        texture.generate_mipmap();
+        assert_eq!(texture.has_mipmap(), true);
+        assert_eq!(texture.is_smooth(), true);
+
+        texture.update_image(&make_image());
+        assert_eq!(texture.has_mipmap(), false);
+        assert_eq!(texture.is_smooth(), true);
+    }
+
+    #[test]
+    fn texture_wrap() {
+        let _lock = lock_rendering();
+
+        let mut texture = Texture::new();
+        assert_eq!(texture.wrap(), (Wrap::Clamp, Wrap::Clamp));
+
+        texture.set_wrap(Wrap::MirroredRepeat, Wrap::Border);
+        assert_eq!(texture.wrap(), (Wrap::MirroredRepeat, Wrap::Border));
+        assert_eq!(texture.is_repeated(), false);
+
+        texture.set_border_color(Color::RED);
+        assert_eq!(texture.border_color(), Color::RED);
+    }
+
+    #[test]
+    fn texture_with_format() {
+        let _lock = lock_rendering();
+
+        let mut texture = Texture::with_format(Size::new(2, 1), TextureFormat::R8);
+        assert_eq!(texture.format(), TextureFormat::R8);
+        assert_eq!(texture.size(), Size::new(2, 1));
+
+        texture.update_bytes(&[10, 20]);
+        assert_eq!(texture.to_bytes(), vec![10, 20]);
+        assert_eq!(texture.to_image().pixels(), &[Color::rgb(10, 0, 0), Color::rgb(20, 0, 0)]);
+
+        let mut texture = Texture::with_format(Size::new(1, 1), TextureFormat::Rgba32F);
+        texture.update_floats(&[-1.0, 0.5, 2.0, 1.0]);
+        assert_eq!(texture.to_floats(), vec![-1.0, 0.5, 2.0, 1.0]);
+
+        assert_eq!(Texture::with_image(&make_image()).format(), TextureFormat::Rgba8);
+        assert_eq!(Texture::with_format(Size::new(1, 1), TextureFormat::Srgba8).format(), TextureFormat::Srgba8);
+    }
+
+    #[test]
+    fn texture_to_image_keeps_framebuffer() {
+        let framebuffer_binding = || unsafe {
+            let mut binding = 0;
+            gl::GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut binding);
+
+            binding
+        };
+
+        // Reading a texture doesn't unbind the framebuffer of the current surface.
+        render(Size::new(2, 2), |_| {
+            let binding = framebuffer_binding();
+            assert_ne!(binding, 0);
+
+            Texture::with_image(&make_image()).to_image();
+            assert_eq!(framebuffer_binding(), binding);
+        });
+    }
+
     #[test]
     fn texture_draw() {
-        // To be implemented.
+        // Each texel of the texture covers 2x2 pixels.
+        let image = render(Size::new(6, 4), |surface| {
+            let texture = Texture::with_image(&make_image());
+
+            let corner = |x: f32, y: f32| Vertex {
+                x: x * 6.0, y: y * 4.0,
+                r: 1.0, g: 1.0, b: 1.0, a: 1.0,
+                u: x, v: y
+            };
+            let quad = vec![corner(0.0, 0.0), corner(1.0, 0.0), corner(0.0, 1.0), corner(1.0, 1.0)];
+            let vertex_array = VertexArray::with_vertices(&quad, Primitive::TriangleStrips, Usage::Static);
+
+            let states = RenderStates {
+                texture: Some(&texture),
+                ..RenderStates::default()
+            };
+            surface.erase(Color::BLACK);
+            surface.draw_vertices(&vertex_array, &states);
+        });
+
+        assert_eq!(pixel(&image, 0, 0), Color::BLACK);
+        assert_eq!(pixel(&image, 3, 1), Color::WHITE);
+        assert_eq!(pixel(&image, 5, 0), Color::RED);
+        assert_eq!(pixel(&image, 1, 3), Color::GREEN);
+        assert_eq!(pixel(&image, 2, 2), Color::BLUE);
     }
 
     #[test]
@@ -0,0 +1,75 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+/// The pixel formats of a texture
+///
+/// A pixel format specifies the channels of the pixels of a texture and how they're stored on the
+/// graphics card. The 8-bit formats store normalized values (in the [0, 1] range once sampled),
+/// the floating-point formats store arbitrary values, and the depth formats store the depth
+/// values of an offscreen rendering. The compressed textures are loaded from KTX2 files (see
+/// `Texture::open_ktx2()`); their content can't be updated or downloaded.
+///
+/// When a texture which has less than four channels is sampled by a shader, the missing color
+/// channels are zero and the alpha channel is one.
+///
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum TextureFormat {
+    /// One 8-bit channel (red).
+    R8,
+    /// Two 8-bit channels (red and green).
+    Rg8,
+    /// Four 8-bit channels (the format of `Image`).
+    Rgba8,
+    /// Four 8-bit channels, the color channels being in the sRGB color space (the format of the
+    /// texture of an sRGB surface, see `Options`); they're converted to linear values when the
+    /// texture is sampled.
+    Srgba8,
+    /// Four 16-bit floating-point channels.
+    Rgba16F,
+    /// Four 32-bit floating-point channels.
+    Rgba32F,
+    /// A 24-bit normalized depth value.
+    Depth24,
+    /// A 32-bit floating-point depth value.
+    Depth32F,
+    /// A compressed format (ETC2, EAC or ASTC).
+    Compressed
+}
+
+impl TextureFormat {
+    /// Returns the number of channels of the format.
+    pub fn channels(&self) -> usize {
+        match self {
+            TextureFormat::R8 => 1,
+            TextureFormat::Rg8 => 2,
+            TextureFormat::Rgba8 | TextureFormat::Srgba8 => 4,
+            TextureFormat::Rgba16F | TextureFormat::Rgba32F => 4,
+            TextureFormat::Compressed => 4,
+            TextureFormat::Depth24 | TextureFormat::Depth32F => 1
+        }
+    }
+
+    /// Returns whether the format stores 8-bit channels.
+    ///
+    /// The content of the textures of this format is uploaded and downloaded as bytes (see
+    /// `Texture::update_bytes()` and `Texture::to_bytes()`); the others are uploaded and
+    /// downloaded as floating-point numbers.
+    ///
+    pub fn is_byte(&self) -> bool {
+        matches!(self, TextureFormat::R8 | TextureFormat::Rg8 | TextureFormat::Rgba8 | TextureFormat::Srgba8)
+    }
+
+    /// Returns whether the format is a compressed format.
+    pub fn is_compressed(&self) -> bool {
+        *self == TextureFormat::Compressed
+    }
+
+    /// Returns whether the format stores depth values.
+    pub fn is_depth(&self) -> bool {
+        matches!(self, TextureFormat::Depth24 | TextureFormat::Depth32F)
+    }
+}
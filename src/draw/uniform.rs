@@ -5,18 +5,60 @@
 //
 // Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
 
-/// Brief description
+use crate::image::Color;
+use crate::draw::Texture;
+
+/// A value of a shader uniform.
+///
+/// A uniform is a variable of a shader program which has the same value for all the vertices and
+/// fragments of a drawing call (see `Shader::set_uniform()`); each variant corresponds to a GLSL
+/// type.
+///
+/// ```no_run
+/// # use byteplug::image::Color;
+/// # use byteplug::draw::{Shader, Texture, Uniform};
+/// # let mut shader = Shader::new(b"", b"").unwrap();
+/// # let normal_map = Texture::new();
+/// # let lights = vec![[0.0; 3]; 4];
+/// # let elapsed = 0.0;
+/// shader.set_uniform("time", Uniform::Float(elapsed));
+/// shader.set_uniform("tint", Uniform::Color(Color::RED));
+/// shader.set_uniform("lights", Uniform::Vector3Array(&lights));
+/// shader.set_uniform("normal_map", Uniform::Texture(&normal_map));
+/// ```
+///
+/// **Implementation notes**
 ///
-/// Long description.
+/// - The arrays are borrowed slices so uniforms stay cheap to copy; they're set starting at the
+///   first element of the GLSL array (for instance, `lights[0]`).
+/// - A color is passed as a `vec4` of normalized values.
+/// - A texture is passed to a `sampler2D`; the shader picks its texture unit (see `Shader`).
 ///
 #[derive(Copy, Clone, PartialEq, PartialOrd, Debug)]
-pub enum Uniform {
+pub enum Uniform<'a> {
+    Boolean(bool),
     Integer(i32),
     UnsignedInteger(u32),
     Float(f32),
     Vector2(f32, f32),
     Vector3(f32, f32, f32),
+    Vector4(f32, f32, f32, f32),
+    IntegerVector2(i32, i32),
+    IntegerVector3(i32, i32, i32),
+    IntegerVector4(i32, i32, i32, i32),
+    UnsignedIntegerVector2(u32, u32),
+    UnsignedIntegerVector3(u32, u32, u32),
+    UnsignedIntegerVector4(u32, u32, u32, u32),
     Matrix2([f32; 4]),
     Matrix3([f32; 9]),
-    Matrix4([f32; 16])
+    Matrix4([f32; 16]),
+    Color(Color),
+    IntegerArray(&'a [i32]),
+    FloatArray(&'a [f32]),
+    Vector2Array(&'a [[f32; 2]]),
+    Vector3Array(&'a [[f32; 3]]),
+    Vector4Array(&'a [[f32; 4]]),
+    Matrix3Array(&'a [[f32; 9]]),
+    Matrix4Array(&'a [[f32; 16]]),
+    Texture(&'a Texture)
 }
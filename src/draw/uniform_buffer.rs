@@ -0,0 +1,134 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+use std::rc::Rc;
+use crate::draw::gl;
+use crate::draw::debug::set_object_label;
+use crate::draw::context::{get_or_create_context, make_context_current};
+
+/// A block of uniforms stored on the graphics card.
+///
+/// A uniform buffer holds the values of a uniform block (a GLSL `uniform` interface block); the
+/// same buffer can be given to several shaders (see `Shader::set_uniform_buffer()`), so values
+/// which are common to them (for instance, a camera or lights) are uploaded once instead of once
+/// per shader.
+///
+/// ```no_run
+/// # use byteplug::draw::{Shader, UniformBuffer};
+/// # let mut sprite_shader = Shader::new(b"", b"").unwrap();
+/// # let mut tile_shader = Shader::new(b"", b"").unwrap();
+/// #[repr(C)]
+/// #[derive(Copy, Clone)]
+/// struct Lighting {
+///     ambient: [f32; 4],
+///     direction: [f32; 4]
+/// }
+///
+/// let mut lighting = UniformBuffer::new(std::mem::size_of::<Lighting>());
+/// lighting.update(0, &[Lighting { ambient: [0.2; 4], direction: [0.0, 1.0, 0.0, 0.0] }]);
+///
+/// sprite_shader.set_uniform_buffer("Lighting", &lighting);
+/// tile_shader.set_uniform_buffer("Lighting", &lighting);
+/// ```
+///
+/// **Implementation notes**
+///
+/// - The data are copied as they are; the types must follow the `std140` layout of the block (for
+///   instance, a `vec3` takes the space of a `vec4`).
+/// - The OpenGL object buffer has the dynamic usage since uniforms are typically updated often.
+///
+pub struct UniformBuffer {
+    buffer: Rc<BufferObject>, // shared with the shaders which use the buffer
+    size: usize
+}
+
+// The OpenGL object buffer of a uniform buffer; like the texture objects, it's shared with the
+// shaders the buffer is given to (see `Shader::set_uniform_buffer()`) and deleted once they're all
+// dropped.
+pub(crate) struct BufferObject(pub(crate) gl::types::GLuint);
+
+impl Drop for BufferObject {
+    fn drop(&mut self) {
+        unsafe {
+            gl_check!(gl::DeleteBuffers(1, &self.0));
+        }
+    }
+}
+
+impl UniformBuffer {
+    /// Constructs a uniform buffer.
+    ///
+    /// This function constructs a uniform buffer of a given size in bytes, filled with zeroes.
+    ///
+    pub fn new(size: usize) -> UniformBuffer {
+        assert!(size > 0, "the size of a uniform buffer must not be zero");
+
+        let _context = get_or_create_context();
+        make_context_current();
+
+        let zeroes = vec![0u8; size];
+
+        let mut buffer: u32 = 0;
+        unsafe {
+            gl_check!(gl::GenBuffers(1, &mut buffer));
+            gl_check!(gl::BindBuffer(gl::UNIFORM_BUFFER, buffer));
+            gl_check!(gl::BufferData(
+                gl::UNIFORM_BUFFER,
+                size as gl::types::GLsizeiptr,
+                zeroes.as_ptr() as *const _,
+                gl::DYNAMIC_DRAW
+            ));
+        }
+
+        UniformBuffer {
+            buffer: Rc::new(BufferObject(buffer)),
+            size: size
+        }
+    }
+
+    /// Returns the size of the buffer in bytes.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Updates a part of the buffer.
+    ///
+    /// This function copies values into the buffer, starting at a given offset in bytes; it panics
+    /// if they go beyond the size of the buffer.
+    ///
+    pub fn update<T: Copy>(&mut self, offset: usize, values: &[T]) {
+        let length = std::mem::size_of_val(values);
+        assert!(offset + length <= self.size, "the values are out of the buffer");
+
+        if length == 0 {
+            return
+        }
+
+        unsafe {
+            gl_check!(gl::BindBuffer(gl::UNIFORM_BUFFER, self.buffer.0));
+            gl_check!(gl::BufferSubData(
+                gl::UNIFORM_BUFFER,
+                offset as gl::types::GLintptr,
+                length as gl::types::GLsizeiptr,
+                values.as_ptr() as *const _
+            ));
+        }
+    }
+
+    /// Changes the label of the uniform buffer.
+    ///
+    /// This function attaches a label to the underlying OpenGL object buffer; it identifies the
+    /// uniform buffer in the debug messages (see the `debug` module) and in the debugging tools.
+    ///
+    pub fn set_label(&mut self, label: &str) {
+        set_object_label(gl::BUFFER, self.buffer.0, label);
+    }
+
+    pub(crate) fn shared_object(&self) -> Rc<BufferObject> {
+        self.buffer.clone()
+    }
+}
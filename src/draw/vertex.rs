@@ -7,6 +7,13 @@
 
 use crate::geometry::Position;
 use crate::image::Color;
+use crate::draw::{Attribute, AttributeFormat, VertexLayout};
+
+const ATTRIBUTES: [Attribute; 3] = [
+    Attribute::new(0, AttributeFormat::Vector2, 0),
+    Attribute::new(1, AttributeFormat::Vector4, 8),
+    Attribute::new(2, AttributeFormat::Vector2, 24)
+];
 
 /// A 2D point with a color and a texture coordinate.
 ///
@@ -85,3 +92,15 @@ impl Default for Vertex {
         Self::new()
     }
 }
+
+// The vertex is made of floats only, and it's `#[repr(C)]`.
+unsafe impl VertexLayout for Vertex {
+    fn attributes() -> &'static [Attribute] {
+        // The position, the color and the texture coordinate (see the default shader).
+        &ATTRIBUTES
+    }
+
+    fn position(&self) -> Option<Position<f32>> {
+        Some(Position::new(self.x, self.y))
+    }
+}
@@ -5,16 +5,22 @@
 //
 // Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, May 2020
 
+use std::ops::Range;
+use std::cell::Cell;
+use std::ptr::NonNull;
+use std::marker::PhantomData;
 use crate::geometry::{Position, Size};
 use crate::geometry::Box;
 use crate::draw::gl;
+use crate::draw::debug::set_object_label;
 use crate::draw::context::{get_or_create_context, make_context_current};
-use crate::draw::{Vertex, Primitive, Usage};
+use crate::draw::{Vertex, VertexLayout, VertexMapping, AttributeFormat, Index, Instance, Primitive, Usage};
 use crate::draw::Surface;
 
-const VERTEX_POSITION: u32 = 0;
-const VERTEX_COLOR: u32 = 1;
-const VERTEX_TEXTURE: u32 = 2;
+const INSTANCE_MATRIX_X: u32 = 3;
+const INSTANCE_MATRIX_Y: u32 = 4;
+const INSTANCE_COLOR: u32 = 5;
+const INSTANCE_TEXTURE_RECT: u32 = 6;
 
 fn from_usage(usage: Usage) -> gl::types::GLenum {
     match usage {
@@ -33,6 +39,92 @@ fn to_usage(usage: gl::types::GLenum) -> Usage {
     }
 }
 
+// Returns the number of components, the type, whether it's normalized and whether it's an
+// integer (passed with VertexAttribIPointer()) of an attribute format.
+fn from_attribute_format(format: AttributeFormat) -> (i32, gl::types::GLenum, bool, bool) {
+    match format {
+        AttributeFormat::Float           => (1, gl::FLOAT,         false, false),
+        AttributeFormat::Vector2         => (2, gl::FLOAT,         false, false),
+        AttributeFormat::Vector3         => (3, gl::FLOAT,         false, false),
+        AttributeFormat::Vector4         => (4, gl::FLOAT,         false, false),
+        AttributeFormat::NormalizedBytes => (4, gl::UNSIGNED_BYTE, true,  false),
+        AttributeFormat::Bytes           => (4, gl::UNSIGNED_BYTE, false, true),
+        AttributeFormat::Integer         => (1, gl::INT,           false, true)
+    }
+}
+
+fn index_size(index_type: gl::types::GLenum) -> usize {
+    match index_type {
+        gl::UNSIGNED_SHORT => std::mem::size_of::<u16>(),
+        _                  => std::mem::size_of::<u32>()
+    }
+}
+
+// Returns the capacity an OpenGL object buffer grows to so it can hold a required number of elements
+// (it's at least doubled so that growing it one element at a time isn't quadratic).
+fn grown_capacity(capacity: usize, required: usize) -> usize {
+    required.max(capacity * 2)
+}
+
+// Returns the size of an OpenGL object buffer in bytes.
+fn buffer_size(buffer: u32) -> usize {
+    unsafe {
+        let mut size: i32 = 0;
+        gl_check!(gl::BindBuffer(gl::COPY_READ_BUFFER, buffer));
+        gl_check!(gl::GetBufferParameteriv(gl::COPY_READ_BUFFER, gl::BUFFER_SIZE, &mut size));
+
+        size as usize
+    }
+}
+
+// Creates an OpenGL object buffer of a given size and usage, then copies the beginning of another
+// one into it; the data never leave the graphics memory.
+fn copy_buffer(source: u32, size: usize, copied: usize, usage: gl::types::GLenum) -> u32 {
+    let mut buffer: u32 = 0;
+    unsafe {
+        gl_check!(gl::GenBuffers(1, &mut buffer));
+        gl_check!(gl::BindBuffer(gl::COPY_WRITE_BUFFER, buffer));
+        gl_check!(gl::BufferData(gl::COPY_WRITE_BUFFER, size as _, std::ptr::null(), usage));
+
+        if copied > 0 {
+            gl_check!(gl::BindBuffer(gl::COPY_READ_BUFFER, source));
+            gl_check!(gl::CopyBufferSubData(gl::COPY_READ_BUFFER, gl::COPY_WRITE_BUFFER, 0, 0, copied as _));
+        }
+    }
+
+    buffer
+}
+
+// Writes data at the beginning of an OpenGL object buffer, creating it if needed; like the one of
+// the vertices, it's only re-allocated when the data exceed its capacity (in bytes), and it's
+// orphaned first when the usage is `Stream`.
+fn write_buffer<T: Copy>(target: gl::types::GLenum, buffer: &mut u32, capacity: &mut usize, data: &[T], usage: Usage, label: Option<&str>) {
+    let size = std::mem::size_of_val(data);
+
+    unsafe {
+        if *buffer == 0 {
+            gl_check!(gl::GenBuffers(1, buffer));
+
+            if let Some(label) = label {
+                set_object_label(gl::BUFFER, *buffer, label);
+            }
+        }
+
+        gl_check!(gl::BindBuffer(target, *buffer));
+
+        let grown = size > *capacity;
+        if grown {
+            *capacity = grown_capacity(*capacity, size);
+        }
+
+        if grown || usage == Usage::Stream {
+            gl_check!(gl::BufferData(target, *capacity as _, std::ptr::null(), from_usage(usage)));
+        }
+
+        gl_check!(gl::BufferSubData(target, 0, size as _, data.as_ptr() as *const _));
+    }
+}
+
 fn from_primitive(primitive: Primitive) -> gl::types::GLenum {
     match primitive {
         Primitive::Points          => gl::POINTS,
@@ -61,11 +153,61 @@ fn from_primitive(primitive: Primitive) -> gl::types::GLenum {
 /// arrays directly.
 ///
 /// To update the set of vertices, use the `update_vertices()` method which will dynamically
-/// increase or decrease the size of the array. The drawing primitive specifies how to use the
-/// vertices to form shapes on the surface, and the usage specifier gives the graphics card a hint
-/// about memory usage (for instance, if you hardly ever change the vertices, giving `Static` as
-/// hint will allow the graphics card to perform better). The default primitive is `Points` and the
-/// default usage is `Stream`.
+/// increase or decrease the size of the array. Like a `Vec<T>`, the vertex array has a capacity;
+/// the graphics memory is only re-allocated when it grows beyond it, so updating the vertices every
+/// frame is cheap. A part of the vertices can be updated with `update_vertex_range()`, vertices can
+/// be appended with `extend_vertices()`, and they can be modified in place with `map_vertices()`.
+/// The drawing primitive specifies how to use the vertices to form shapes on the surface, and the
+/// usage specifier gives the graphics card a hint about memory usage (for instance, if you hardly
+/// ever change the vertices, giving `Static` as hint will allow the graphics card to perform
+/// better). The default primitive is `Points` and the default usage is `Stream`.
+///
+/// Optionally, the vertex array can have indices (see `update_indices()`); the shapes are then
+/// formed by the vertices the indices refer to, in the order of the indices, and a vertex can be
+/// shared by several shapes instead of being duplicated. For instance, a quad made of two
+/// triangles takes four vertices and six indices instead of six vertices.
+///
+/// ```no_run
+/// # use byteplug::draw::{VertexArray, Vertex, Primitive, Usage};
+/// # let quad = vec![Vertex::default(); 4];
+/// let mut vertex_array = VertexArray::with_vertices(&quad, Primitive::Triangles, Usage::Static);
+/// vertex_array.update_indices(&[0u16, 1, 2, 2, 1, 3]);
+/// ```
+///
+/// The vertex array can also have instances (see `Instance`); the vertices are then drawn once per
+/// instance, with a single drawing call.
+///
+/// By default, a vertex array stores vertices of the `Vertex` type, which the default shaders
+/// understand. A vertex array of a custom vertex type can be constructed with `with_layout()` for
+/// the custom shaders which need other attributes; the type describes its attributes by
+/// implementing `VertexLayout`.
+///
+/// ```no_run
+/// # use byteplug::geometry::Size;
+/// # use byteplug::draw::{Surface, Options, Shader, RenderStates};
+/// # use byteplug::draw::{VertexArray, VertexLayout, Attribute, AttributeFormat};
+/// # #[repr(C)]
+/// # #[derive(Copy, Clone)]
+/// # struct LitVertex { position: [f32; 2], normal: [f32; 3] }
+/// # const LIT_ATTRIBUTES: [Attribute; 2] = [
+/// #     Attribute::new(0, AttributeFormat::Vector2, 0),
+/// #     Attribute::new(1, AttributeFormat::Vector3, 8)
+/// # ];
+/// # unsafe impl VertexLayout for LitVertex {
+/// #     fn attributes() -> &'static [Attribute] { &LIT_ATTRIBUTES }
+/// # }
+/// # let mut surface = Surface::new(Size::new(640, 480), Options::default()).unwrap();
+/// # let lighting_shader = Shader::new(b"", b"").unwrap();
+/// # let vertices: Vec<LitVertex> = Vec::new();
+/// let mut vertex_array = VertexArray::<LitVertex>::with_layout();
+/// vertex_array.update_vertices(&vertices);
+///
+/// let states = RenderStates {
+///     shader: Some(&lighting_shader),
+///     ..RenderStates::default()
+/// };
+/// surface.draw_vertices(&vertex_array, &states);
+/// ```
 ///
 /// **Implementation notes**
 ///
@@ -77,51 +219,84 @@ fn from_primitive(primitive: Primitive) -> gl::types::GLenum {
 ///   are stored on the graphics memory and it's costly. Plus, it's best to keep it simple; fancy
 ///   operations can be done using regular vectors on system memory, and then the vertex array
 ///   updated once with the `update_vertices()` method.
-/// - From what I understood, there may be a way to map the graphics memory to system memory and
-///   drastically change the implementation (and interface!). Instead of providing a 'copy API'
-///   where vertices are copied around (the vertices() method returns a copy, the update_vertices()
-///   method implementation does copy), we could implement a vector-like API returning sliced and
-///   the standard 'array operations' because the internal `Vec<Vertex>` would directly be acting on
-///   the mapped memory. There would be a need to 'flush' the mapped memory so OpenGL knows that it
-///   has to synchronize it with the graphics memory; it could be either done with an update()
-///   method or systematically right before it's used for drawing. The advantage of this
-///   implementation is that the user wouldn't have to make a separate copy of the vertices, make
-///   transformations on it, then re-update the vertex array. This is to be investigated.
-/// - Post-implementation note: The idea of above seems to be totally applicable. In fact, it would
-///   be much better and the implementation of the shapes would benefit from them (since they're
-///   using vertex arrays internally).
+/// - The graphics memory can be mapped to the system memory (see `VertexMapping`); the mapping is
+///   a guard that unmaps it when it's dropped, so the vertices are modified in place without
+///   making a separate copy of them.
 /// - I could not find a way to change the 'usage' of the OpenGL buffer objects; it seems like the
 ///   only way is to re-create it. Therefore, changing the usage property is marked as slow
-///   operation, and to be avoided after the vertex array contains vertices.
+///   operation, and to be avoided after the vertex array contains vertices. The vertices are
+///   copied to the new OpenGL object buffer within the graphics memory (with
+///   CopyBufferSubData()), not through the system memory.
 /// - Regarding the implementation, it's important to understand that a vertex array with no data
 ///   doesn't have an OpenGL object buffer created just yet. One consequence of that is that we must
 ///   keep an 'extra' usage field so when the user is updating the vertex array with actual data, we
 ///   know which usage to set (usage can be set before vertices are updated). When the user queries
 ///   the usage, we rely on the actual usage (as returned by OpenGL) unless there's no buffer yet in
 ///   which case we really on our local one.
-/// - Post-implementation note: I removed the with_size() constructor and the resize() method because
-///   they make the implementation more complicated. To resize the array, the user use the
-///   update_vertices() method for now. The user will have more control over the memory after it's
-///   updated to use the 'mapped memory' technique (see other notes).
-/// - Post-implementation note: I drastically simplified the implementation of update_vertices()
-///   because the whole implementation will likely be reworked to used the 'mapped object buffer'
-///   technique. Thus, for now, it simply re-creates an entire object buffer, discarding the
-///   existing one.
+/// - The number of vertices and the capacity are kept locally because the size of the OpenGL
+///   object buffer is the capacity. The OpenGL object buffer is never shrunk; an empty list of
+///   vertices only sets the size to zero.
+/// - When the usage is `Stream`, updating all the vertices 'orphans' the OpenGL object buffer
+///   first (its storage is re-specified without data); the graphics driver can then give it new
+///   memory instead of waiting for the drawing calls which still use the previous vertices.
+/// - The bounds are re-computed when all the vertices are updated. After a partial update, they
+///   are re-computed from the vertices the next time they're requested (which is a heavy
+///   operation).
+/// - The indices are stored in a second OpenGL object buffer which follows the same rules (it's
+///   only re-allocated when they exceed its capacity, it's orphaned when the usage is `Stream`, and
+///   it has the same usage specifier); its capacity is kept in bytes because the type of the
+///   indices can change. The number of indices is kept locally too, so drawing doesn't query the
+///   OpenGL object buffer. It's bound before each drawing call because the framework doesn't use
+///   OpenGL vertex array objects.
+/// - The instances are stored in a third OpenGL object buffer, following the same rules (including
+///   the number of instances, which is kept locally). Their attributes are only enabled during the drawing call, so they don't leak into the drawing of
+///   the other vertex arrays.
+/// - The attributes of the vertex layout are pointed to the vertices and enabled before each
+///   drawing call, and disabled after it, for the same reason.
+/// - The `new()` constructor is only implemented for the default vertex type, so the type of the
+///   vertex array can be inferred (default type parameters don't participate in type inference).
 ///
-pub struct VertexArray {
+pub struct VertexArray<V: VertexLayout = Vertex> {
     buffer: u32,
+    size: usize, // the number of vertices
+    capacity: usize, // the number of vertices the OpenGL object buffer can hold (see notes)
+    index_buffer: u32, // zero if the vertex array never had indices
+    index_count: usize, // zero if the vertex array has no indices
+    index_capacity: usize, // the size of the OpenGL object buffer of the indices in bytes
+    index_type: gl::types::GLenum,
+    instance_buffer: u32, // zero if the vertex array never had instances
+    instance_count: usize, // zero if the vertex array has no instances
+    instance_capacity: usize, // the size of the OpenGL object buffer of the instances in bytes
     primitive: Primitive,
     usage: Usage, // see notes
-    bounds: Box<f32> // will be removed after mapped buffer technique is used
+    bounds: Cell<Option<Box<f32>>>, // none if it must be re-computed (see notes)
+    label: Option<String>, // applied again when the OpenGL object buffer is re-created
+    layout: PhantomData<V>
 }
 
-impl VertexArray {
+impl VertexArray<Vertex> {
     /// Constructs an empty vertex array.
     ///
     /// This function is the default constructor. It creates a vertex array with no vertices, using
     /// the points primitive and the stream usage specifier.
     ///
     pub fn new() -> VertexArray {
+        Self::with_layout()
+    }
+}
+
+impl<V: VertexLayout> VertexArray<V> {
+    /// Constructs an empty vertex array of a custom vertex type.
+    ///
+    /// This function is the same as `new()` except that the vertex array stores vertices of a
+    /// custom vertex type (see `VertexLayout`).
+    ///
+    pub fn with_layout() -> VertexArray<V> {
+        for attribute in V::attributes() {
+            assert!(attribute.offset + attribute.format.size() <= std::mem::size_of::<V>(),
+                    "the attribute at location {} doesn't fit in the vertex", attribute.location);
+        }
+
         // Make sure the shared OpenGL context is created (it doesn't matter which context is
         // active; the OpenGL object buffer will be shared).
         let _context = get_or_create_context();
@@ -129,9 +304,20 @@ impl VertexArray {
 
         VertexArray {
             buffer: 0,
+            size: 0,
+            capacity: 0,
+            index_buffer: 0,
+            index_count: 0,
+            index_capacity: 0,
+            index_type: gl::UNSIGNED_SHORT,
+            instance_buffer: 0,
+            instance_count: 0,
+            instance_capacity: 0,
             primitive: Primitive::Points,
             usage: Usage::Stream,
-            bounds: Box::default()
+            bounds: Cell::new(Some(Box::default())),
+            label: None,
+            layout: PhantomData
         }
     }
 
@@ -140,9 +326,9 @@ impl VertexArray {
     /// This function is a constructor that constructs a vertex array from a list of vertices, their
     /// drawing primitive and an usage specifier.
     ///
-    pub fn with_vertices(vertices: &Vec<Vertex>, primitive: Primitive, usage: Usage) -> VertexArray {
+    pub fn with_vertices(vertices: &Vec<V>, primitive: Primitive, usage: Usage) -> VertexArray<V> {
 
-        let mut vertex_array = Self::new();
+        let mut vertex_array = Self::with_layout();
 
         vertex_array.set_primitive(primitive);
         vertex_array.set_usage(usage); // This one should be done before updating the vertices (see implementation detail)
@@ -156,30 +342,27 @@ impl VertexArray {
     /// This function returns the number of vertices in the array.
     ///
     pub fn size(&self) -> usize {
+        self.size
+    }
 
-        // The vertex array may not have an OpenGL object buffer yet, in this case, the size
-        // obviously is zero.
-        if self.buffer == 0 {
-            return 0
-        }
-
-        // Make the OpenGL object buffer current (so GetBufferParameteriv() operates on it).
-        self.bind();
-        unsafe {
-            // Ask OpenGL the size of the OpenGL object buffer in bytes (size isn't expected to be
-            // zero otherwise there wouldn't be any OpenGL object buffer).
-            let mut buffer_size: i32 = 0;
-            gl_check!(gl::GetBufferParameteriv(
-                gl::ARRAY_BUFFER,
-                gl::BUFFER_SIZE,
-                &mut buffer_size
-            ));
-            assert_ne!(buffer_size, 0);
+    /// Returns the capacity of the array.
+    ///
+    /// This function returns the number of vertices the array can hold without re-allocating its
+    /// graphics memory.
+    ///
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
 
-            // Convert the size in bytes to size 'in Vertex' (todo; should it panic if the size
-            // isn't perfectly dividable ?).
-            let size = buffer_size / std::mem::size_of::<Vertex>() as i32;
-            size as usize
+    /// Reserves capacity for more vertices.
+    ///
+    /// This function makes sure the array can hold at least `additional` more vertices without
+    /// re-allocating its graphics memory; the existing vertices are preserved.
+    ///
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self.size + additional;
+        if required > self.capacity {
+            self.reallocate(grown_capacity(self.capacity, required), self.usage);
         }
     }
 
@@ -189,23 +372,26 @@ impl VertexArray {
     /// memory to system memory, which is a heavy operation, therefore you should not use it
     /// adequately.
     ///
-    pub fn vertices(&self) -> Vec<Vertex> {
+    pub fn vertices(&self) -> Vec<V> {
 
         // The vertex array may not have an OpenGL object buffer yet, in this case, it's an empty
         // vector.
         if self.buffer == 0 {
-            return Vec::<Vertex>::new()
+            return Vec::<V>::new()
         }
 
         // We could have used GetBufferSubData() but it's not available in OpenGL ES 3.2, therefore,
         // we must map the data (on graphics memory) to the system memory with MapBufferRange()
-        // then copy it into a `Vec<Vertex>`.
-        let size = self.size();
+        // then copy it into a `Vec<V>`.
+        let size = self.size;
+        if size == 0 {
+            return Vec::<V>::new()
+        }
 
         // Make the OpenGL object buffer current (so MapBufferRange() operates on it).
         self.bind();
         let vertices = unsafe {
-            let length = size * std::mem::size_of::<Vertex>();
+            let length = size * std::mem::size_of::<V>();
             gl_check!(let pointer = gl::MapBufferRange(
                 gl::ARRAY_BUFFER,
                 0,
@@ -213,16 +399,9 @@ impl VertexArray {
                 gl::MAP_READ_BIT
             ));
 
-            // The strategy is to make a `Vec<Vertex>` from the mapped data (which we don't own), so
-            // we can easily make a copy of it. We manually ask not to drop the data as we don't own
-            // it.
-            let mapped_vertices = Vec::<Vertex>::from_raw_parts(
-                pointer as _,
-                size,
-                size
-            );
-            let vertices = mapped_vertices.clone();
-            std::mem::ManuallyDrop::new(mapped_vertices);
+            // The mapped data isn't owned, so it's copied from a slice, then unmapped.
+            let vertices = std::slice::from_raw_parts(pointer as *const V, size).to_vec();
+            gl_check!(let _ = gl::UnmapBuffer(gl::ARRAY_BUFFER));
 
             vertices
         };
@@ -233,47 +412,238 @@ impl VertexArray {
     /// Update the vertices in the array.
     ///
     /// This function updates the vertices in the array, discarding the previous vertices and
-    /// possibly resizing the array. It's a heavy operation that consists of copying system memory
-    /// to graphics memory, and therefore should be used adequately.
+    /// possibly resizing the array. It's an operation that consists of copying system memory to
+    /// graphics memory; the graphics memory is only re-allocated if the vertices exceed the
+    /// capacity.
     ///
-    pub fn update_vertices(&mut self, vertices: &Vec<Vertex>) {
-        // This function was heavily simplified because the entire implementation will be changed
-        // later to use the 'mapped memory' technique. Instead of intelligently checking and
-        // possibly resizing the buffer, it just destroys it and re-creates one with the new
-        // vertices.
-
-        // Delete the existing OpenGL object buffer if there is one.
-        if self.buffer != 0 {
-            self.delete_buffer();
+    pub fn update_vertices(&mut self, vertices: &Vec<V>) {
+        // The previous vertices are entirely replaced, so there's no need to preserve them when
+        // the OpenGL object buffer is grown (or orphaned).
+        if vertices.len() > self.capacity {
+            self.allocate(grown_capacity(self.capacity, vertices.len()));
+        }
+        else if self.usage == Usage::Stream && !vertices.is_empty() {
+            self.allocate(self.capacity);
         }
 
-        // If the list of vertices provided by the user is empty, there actually is nothing to do
-        // other than resetting the bounding box.
-        if vertices.is_empty() {
-            self.bounds = Box::default();
+        self.size = vertices.len();
+        self.write_vertices(0, vertices);
 
-            return
+        // Re-compute the bounding box.
+        self.bounds.set(Some(Self::compute_bounds(vertices)));
+    }
+
+    /// Update a range of the vertices in the array.
+    ///
+    /// This function updates the vertices starting at a given index, leaving the other vertices
+    /// untouched; it panics if the vertices go beyond the size of the array.
+    ///
+    pub fn update_vertex_range(&mut self, start: usize, vertices: &[V]) {
+        assert!(start + vertices.len() <= self.size, "the vertices are out of the array");
+
+        self.write_vertices(start, vertices);
+        self.bounds.set(None);
+    }
+
+    /// Appends vertices to the array.
+    ///
+    /// This function appends vertices at the end of the array; it grows the capacity of the array
+    /// if needed (see `reserve()`).
+    ///
+    pub fn extend_vertices(&mut self, vertices: &[V]) {
+        self.reserve(vertices.len());
+
+        let start = self.size;
+        self.size += vertices.len();
+
+        self.write_vertices(start, vertices);
+        self.bounds.set(None);
+    }
+
+    /// Maps a range of the vertices to the system memory.
+    ///
+    /// This function returns a mapping of the vertices within a range which can be read and
+    /// modified in place (see `VertexMapping`); the changes are applied when the mapping is
+    /// dropped. It panics if the range goes beyond the size of the array.
+    ///
+    pub fn map_vertices(&mut self, range: Range<usize>) -> VertexMapping<'_, V> {
+        assert!(range.start <= range.end && range.end <= self.size, "the range is out of the array");
+        self.bounds.set(None);
+
+        // Mapping an empty range is an error in OpenGL; there's simply nothing to map.
+        if range.start == range.end {
+            return VertexMapping::new(self, NonNull::dangling().as_ptr(), 0)
         }
 
-        // If the list is not empty, we generate the OpenGL object buffer, make it current, and
-        // upload the vertices to it.
-        self.generate_buffer();
         self.bind();
-        unsafe {
-            gl_check!(gl::BufferData(
+        let pointer = unsafe {
+            gl_check!(let pointer = gl::MapBufferRange(
                 gl::ARRAY_BUFFER,
-                (vertices.len() * std::mem::size_of::<Vertex>()) as gl::types::GLsizeiptr,
-                vertices.as_ptr() as *const _,
-                from_usage(self.usage),
+                (range.start * std::mem::size_of::<V>()) as _,
+                (range.len() * std::mem::size_of::<V>()) as _,
+                gl::MAP_READ_BIT | gl::MAP_WRITE_BIT
             ));
+
+            pointer
         };
 
-        // Re-compute the bounding box.
-        self.bounds = Self::compute_bounds(&vertices);
+        VertexMapping::new(self, pointer as *mut V, range.len())
     }
 
+    /// Returns the bounds of the vertices.
+    ///
+    /// This function returns the bounding box of the positions of the vertices; it's empty if the
+    /// vertex type has no position (see `VertexLayout::position()`).
+    ///
     pub fn bounds(&self) -> Box<f32> {
-        self.bounds
+        if let Some(bounds) = self.bounds.get() {
+            return bounds
+        }
+
+        let bounds = Self::compute_bounds(&self.vertices());
+        self.bounds.set(Some(bounds));
+
+        bounds
+    }
+
+    /// Returns whether the vertex array has indices.
+    pub fn is_indexed(&self) -> bool {
+        self.index_count > 0
+    }
+
+    /// Returns the number of indices in the array.
+    ///
+    /// This function returns the number of indices in the array (zero if the vertex array has no
+    /// indices).
+    ///
+    pub fn index_count(&self) -> usize {
+        self.index_count
+    }
+
+    /// Returns a copy of the indices in the array.
+    ///
+    /// This function returns a copy of the indices in the array, as 32-bit integers whatever their
+    /// actual type is. Like `vertices()`, it's a heavy operation.
+    ///
+    pub fn indices(&self) -> Vec<u32> {
+        let count = self.index_count();
+        if count == 0 {
+            return Vec::new()
+        }
+
+        self.bind_indices();
+        unsafe {
+            let length = count * index_size(self.index_type);
+            gl_check!(let pointer = gl::MapBufferRange(
+                gl::ELEMENT_ARRAY_BUFFER,
+                0,
+                length as _,
+                gl::MAP_READ_BIT
+            ));
+
+            let indices = match self.index_type {
+                gl::UNSIGNED_SHORT => std::slice::from_raw_parts(pointer as *const u16, count)
+                    .iter()
+                    .map(|index| *index as u32)
+                    .collect(),
+                _ => std::slice::from_raw_parts(pointer as *const u32, count).to_vec()
+            };
+
+            gl_check!(let _ = gl::UnmapBuffer(gl::ELEMENT_ARRAY_BUFFER));
+
+            indices
+        }
+    }
+
+    /// Update the indices in the array.
+    ///
+    /// This function updates the indices in the array, discarding the previous indices. The
+    /// vertices are then drawn in the order of the indices; an empty list of indices removes them,
+    /// and the vertices are drawn in their order again. The indices aren't checked against the
+    /// number of vertices. Like the vertices, the graphics memory is only re-allocated if the
+    /// indices exceed its capacity.
+    ///
+    pub fn update_indices<I: Index>(&mut self, indices: &[I]) {
+        self.index_count = indices.len();
+        self.index_type = I::GL_TYPE;
+
+        if indices.is_empty() {
+            return
+        }
+
+        write_buffer(
+            gl::ELEMENT_ARRAY_BUFFER,
+            &mut self.index_buffer,
+            &mut self.index_capacity,
+            indices,
+            self.usage,
+            self.label.as_deref()
+        );
+    }
+
+    /// Returns whether the vertex array has instances.
+    pub fn is_instanced(&self) -> bool {
+        self.instance_count > 0
+    }
+
+    /// Returns the number of instances in the array.
+    ///
+    /// This function returns the number of instances in the array (zero if the vertex array has no
+    /// instances).
+    ///
+    pub fn instance_count(&self) -> usize {
+        self.instance_count
+    }
+
+    /// Returns a copy of the instances in the array.
+    ///
+    /// This function returns a copy of the instances in the array. Like `vertices()`, it's a heavy
+    /// operation.
+    ///
+    pub fn instances(&self) -> Vec<Instance> {
+        let count = self.instance_count();
+        if count == 0 {
+            return Vec::new()
+        }
+
+        self.bind_instances();
+        unsafe {
+            gl_check!(let pointer = gl::MapBufferRange(
+                gl::ARRAY_BUFFER,
+                0,
+                (count * std::mem::size_of::<Instance>()) as _,
+                gl::MAP_READ_BIT
+            ));
+
+            let instances = std::slice::from_raw_parts(pointer as *const Instance, count).to_vec();
+            gl_check!(let _ = gl::UnmapBuffer(gl::ARRAY_BUFFER));
+
+            instances
+        }
+    }
+
+    /// Update the instances in the array.
+    ///
+    /// This function updates the instances in the array, discarding the previous instances. The
+    /// vertices are then drawn once per instance; an empty list of instances removes them, and the
+    /// vertices are drawn once again. Like the vertices, the graphics memory is only re-allocated if
+    /// the instances exceed its capacity.
+    ///
+    pub fn update_instances(&mut self, instances: &[Instance]) {
+        self.instance_count = instances.len();
+
+        if instances.is_empty() {
+            return
+        }
+
+        write_buffer(
+            gl::ARRAY_BUFFER,
+            &mut self.instance_buffer,
+            &mut self.instance_capacity,
+            instances,
+            self.usage,
+            self.label.as_deref()
+        );
     }
 
     /// Returns the drawing primitive.
@@ -329,55 +699,127 @@ impl VertexArray {
     /// it's an expensive operation because the vertices need to be duplicated in graphics memory.
     ///
     pub fn set_usage(&mut self, usage: Usage) {
-        // There may be a better way to implement this function. There doesn't seem to be a way to
-        // change the usage specifier without re-creating the OpenGL object buffer. That being said,
-        // the current implementation makes it even worst as it copies vertices back to system
-        // memory to re-copy them back to graphics memory. The existing graphics memory could simply
-        // be copied into a new OpenGL object buffer that has the new usage specifier before the old
-        // one is deleted (that would involve no transfer system/graphics memory).
+        // There doesn't seem to be a way to change the usage specifier without re-creating the
+        // OpenGL object buffers; their data are copied to the new ones within the graphics memory.
         self.usage = usage;
-        self.update_vertices(&self.vertices());
+
+        if self.buffer != 0 {
+            self.reallocate(self.capacity, usage);
+        }
+
+        for buffer in [&mut self.index_buffer, &mut self.instance_buffer].iter_mut() {
+            if **buffer == 0 {
+                continue
+            }
+
+            let size = buffer_size(**buffer);
+            let copy = copy_buffer(**buffer, size, size, from_usage(usage));
+            unsafe {
+                gl_check!(gl::DeleteBuffers(1, &**buffer));
+            }
+            **buffer = copy;
+
+            if let Some(label) = &self.label {
+                set_object_label(gl::BUFFER, copy, label);
+            }
+        }
+    }
+
+    /// Changes the label of the vertex array.
+    ///
+    /// This function attaches a label to the underlying OpenGL object buffer; it identifies the
+    /// vertex array in the debug messages (see the `debug` module) and in the debugging tools.
+    /// The object buffers of the indices and the instances have the same label.
+    ///
+    pub fn set_label(&mut self, label: &str) {
+        self.label = Some(String::from(label));
+
+        if self.buffer != 0 {
+            set_object_label(gl::BUFFER, self.buffer, label);
+        }
+
+        if self.index_buffer != 0 {
+            set_object_label(gl::BUFFER, self.index_buffer, label);
+        }
+
+        if self.instance_buffer != 0 {
+            set_object_label(gl::BUFFER, self.instance_buffer, label);
+        }
     }
 
     /// Draws the vertex array on a surface.
     ///
     /// This functions draws the vertex array on a surface according to its drawing primitive.
     ///
-    pub(crate) fn draw(&self, _surface: &mut Surface) {
+    pub(crate) fn draw(&self, surface: &mut Surface) {
+        let count = if self.is_indexed() { self.index_count() } else { self.size() };
+        self.draw_range(surface, 0..count);
+    }
+
+    /// Draws a range of the vertex array on a surface.
+    ///
+    /// This function is the same as `draw()` except that only the vertices within the range are
+    /// drawn; it's used to draw several parts of the same array with different states. If the
+    /// vertex array has indices, the range is a range of indices.
+    ///
+    pub(crate) fn draw_range(&self, _surface: &mut Surface, range: Range<usize>) {
         // Make the OpenGL object buffer current (so VertexAttribPointer() and
         // EnableVertexAttribArray() operate on it).
         self.bind();
         unsafe {
+            // Point the attributes of the vertex layout to the vertices.
+            let stride = std::mem::size_of::<V>() as gl::types::GLsizei;
+            for attribute in V::attributes() {
+                let (size, kind, normalized, integer) = from_attribute_format(attribute.format);
+                let offset = attribute.offset as *const () as *const _;
+
+                if integer {
+                    gl_check!(gl::VertexAttribIPointer(attribute.location, size, kind, stride, offset));
+                }
+                else {
+                    gl_check!(gl::VertexAttribPointer(attribute.location, size, kind, normalized as _, stride, offset));
+                }
+                gl_check!(gl::EnableVertexAttribArray(attribute.location));
+            }
+
             // Draw the vertices by using the correct OpenGL drawing primitive.
-            gl_check!(gl::VertexAttribPointer(
-                VERTEX_POSITION,
-                2,
-                gl::FLOAT,
-                0,
-                std::mem::size_of::<Vertex>() as gl::types::GLsizei,
-                std::ptr::null(),
-            ));
-            gl_check!(gl::VertexAttribPointer(
-                VERTEX_COLOR,
-                4,
-                gl::FLOAT,
-                0,
-                std::mem::size_of::<Vertex>() as gl::types::GLsizei,
-                (2 * std::mem::size_of::<f32>()) as *const () as *const _,
-            ));
-            gl_check!(gl::VertexAttribPointer(
-                VERTEX_TEXTURE,
-                2,
-                gl::FLOAT,
-                0,
-                std::mem::size_of::<Vertex>() as gl::types::GLsizei,
-                (6 * std::mem::size_of::<f32>()) as *const () as *const _,
-            ));
-            gl_check!(gl::EnableVertexAttribArray(VERTEX_POSITION));
-            gl_check!(gl::EnableVertexAttribArray(VERTEX_COLOR));
-            gl_check!(gl::EnableVertexAttribArray(VERTEX_TEXTURE));
+            let instance_count = self.instance_count();
+            if instance_count > 0 {
+                self.enable_instance_attributes();
+            }
 
-            gl_check!(gl::DrawArrays(from_primitive(self.primitive), 0, self.size() as _));
+            let primitive = from_primitive(self.primitive);
+            let offset = (range.start * index_size(self.index_type)) as *const _;
+
+            match (self.is_indexed(), instance_count > 0) {
+                (false, false) => {
+                    gl_check!(gl::DrawArrays(primitive, range.start as _, range.len() as _));
+                },
+                (false, true) => {
+                    gl_check!(gl::DrawArraysInstanced(primitive, range.start as _, range.len() as _, instance_count as _));
+                },
+                (true, false) => {
+                    self.bind_indices();
+                    gl_check!(gl::DrawElements(primitive, range.len() as _, self.index_type, offset));
+                },
+                (true, true) => {
+                    self.bind_indices();
+                    gl_check!(gl::DrawElementsInstanced(primitive, range.len() as _, self.index_type, offset, instance_count as _));
+                }
+            }
+
+            // The divisors are reset too; they would otherwise apply to the attributes of the
+            // custom vertex layouts at the same locations.
+            if instance_count > 0 {
+                for attribute in INSTANCE_MATRIX_X..=INSTANCE_TEXTURE_RECT {
+                    gl_check!(gl::VertexAttribDivisor(attribute, 0));
+                    gl_check!(gl::DisableVertexAttribArray(attribute));
+                }
+            }
+
+            for attribute in V::attributes() {
+                gl_check!(gl::DisableVertexAttribArray(attribute.location));
+            }
         }
     }
 
@@ -399,6 +841,104 @@ impl VertexArray {
         }
     }
 
+    // Point the instance attributes to the instances (they advance once per instance).
+    fn enable_instance_attributes(&self) {
+        let attributes = [
+            (INSTANCE_MATRIX_X,     3, 0),
+            (INSTANCE_MATRIX_Y,     3, 3),
+            (INSTANCE_COLOR,        4, 6),
+            (INSTANCE_TEXTURE_RECT, 4, 10)
+        ];
+
+        self.bind_instances();
+        unsafe {
+            for (attribute, size, offset) in attributes.iter() {
+                gl_check!(gl::VertexAttribPointer(
+                    *attribute,
+                    *size,
+                    gl::FLOAT,
+                    0,
+                    std::mem::size_of::<Instance>() as gl::types::GLsizei,
+                    (offset * std::mem::size_of::<f32>()) as *const () as *const _,
+                ));
+                gl_check!(gl::VertexAttribDivisor(*attribute, 1));
+                gl_check!(gl::EnableVertexAttribArray(*attribute));
+            }
+        }
+    }
+
+    fn bind_instances(&self) {
+        unsafe {
+            gl_check!(gl::BindBuffer(gl::ARRAY_BUFFER, self.instance_buffer));
+        }
+    }
+
+    fn bind_indices(&self) {
+        unsafe {
+            gl_check!(gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.index_buffer));
+        }
+    }
+
+    // Re-allocate the OpenGL object buffer with a given capacity, discarding the vertices (it's
+    // also how it's orphaned).
+    fn allocate(&mut self, capacity: usize) {
+        if self.buffer == 0 {
+            self.generate_buffer();
+
+            if let Some(label) = &self.label {
+                set_object_label(gl::BUFFER, self.buffer, label);
+            }
+        }
+
+        self.bind();
+        unsafe {
+            gl_check!(gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (capacity * std::mem::size_of::<V>()) as gl::types::GLsizeiptr,
+                std::ptr::null(),
+                from_usage(self.usage),
+            ));
+        }
+
+        self.capacity = capacity;
+    }
+
+    // Re-create the OpenGL object buffer with a given capacity and usage, preserving the vertices.
+    fn reallocate(&mut self, capacity: usize, usage: Usage) {
+        if self.buffer == 0 {
+            self.allocate(capacity);
+            return
+        }
+
+        let size = capacity * std::mem::size_of::<V>();
+        let copied = self.size * std::mem::size_of::<V>();
+        let buffer = copy_buffer(self.buffer, size, copied, from_usage(usage));
+
+        self.delete_buffer();
+        self.buffer = buffer;
+        self.capacity = capacity;
+
+        if let Some(label) = &self.label {
+            set_object_label(gl::BUFFER, self.buffer, label);
+        }
+    }
+
+    fn write_vertices(&self, start: usize, vertices: &[V]) {
+        if vertices.is_empty() {
+            return
+        }
+
+        self.bind();
+        unsafe {
+            gl_check!(gl::BufferSubData(
+                gl::ARRAY_BUFFER,
+                (start * std::mem::size_of::<V>()) as gl::types::GLintptr,
+                std::mem::size_of_val(vertices) as gl::types::GLsizeiptr,
+                vertices.as_ptr() as *const _
+            ));
+        }
+    }
+
     fn generate_buffer(&mut self) {
         assert_eq!(self.buffer, 0);
         let buffer = unsafe {
@@ -420,31 +960,33 @@ impl VertexArray {
         self.buffer = 0;
     }
 
-    fn compute_bounds(vertices: &Vec<Vertex>) -> Box<f32> {
+    fn compute_bounds(vertices: &[V]) -> Box<f32> {
         // There could be a smarter way to implement this and merge it with the code of
         // `compute_bounds()` but for now it's unnecessarily complicated.
-        if vertices.is_empty() {
-            return Box::default()
-        }
+        let mut positions = vertices.iter().filter_map(|vertex| vertex.position());
+        let first = match positions.next() {
+            Some(position) => position,
+            None => return Box::default()
+        };
 
-        let mut left   = vertices[0].x;
-        let mut top    = vertices[0].y;
-        let mut right  = vertices[0].x;
-        let mut bottom = vertices[0].y;
+        let mut left   = first.x;
+        let mut top    = first.y;
+        let mut right  = first.x;
+        let mut bottom = first.y;
 
-        for vertex in vertices.iter() {
-            if vertex.x < left {
-                left = vertex.x;
+        for position in positions {
+            if position.x < left {
+                left = position.x;
             }
-            else if vertex.x > right {
-                right = vertex.x;
+            else if position.x > right {
+                right = position.x;
             }
 
-            if vertex.y < top {
-                top = vertex.y;
+            if position.y < top {
+                top = position.y;
             }
-            else if vertex.y > bottom {
-                bottom = vertex.y;
+            else if position.y > bottom {
+                bottom = position.y;
             }
         };
 
@@ -452,8 +994,20 @@ impl VertexArray {
     }
 }
 
-impl Drop for VertexArray {
+impl<V: VertexLayout> Drop for VertexArray<V> {
     fn drop(&mut self) {
+        if self.index_buffer != 0 {
+            unsafe {
+                gl_check!(gl::DeleteBuffers(1, &self.index_buffer));
+            }
+        }
+
+        if self.instance_buffer != 0 {
+            unsafe {
+                gl_check!(gl::DeleteBuffers(1, &self.instance_buffer));
+            }
+        }
+
         // If the vertex array never had any vertices, no OpenGL object buffer was created, in this
         // case there's nothing to do.
         if self.buffer == 0 {
@@ -469,9 +1023,111 @@ impl Drop for VertexArray {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::geometry::Matrix;
+    use crate::image::Color;
+    use crate::draw::{Attribute, RenderStates, Shader};
+    use crate::draw::testing::{lock_rendering, render, pixel};
+
+    #[repr(C)]
+    #[derive(Copy, Clone, PartialEq, Debug)]
+    struct CustomVertex {
+        position: [f32; 2],
+        color: [u8; 4],
+        flags: i32
+    }
+
+    const CUSTOM_ATTRIBUTES: [Attribute; 3] = [
+        Attribute::new(0, AttributeFormat::Vector2, 0),
+        Attribute::new(1, AttributeFormat::NormalizedBytes, 8),
+        Attribute::new(2, AttributeFormat::Integer, 12)
+    ];
+
+    unsafe impl VertexLayout for CustomVertex {
+        fn attributes() -> &'static [Attribute] {
+            &CUSTOM_ATTRIBUTES
+        }
+
+        fn position(&self) -> Option<Position<f32>> {
+            Some(Position::new(self.position[0], self.position[1]))
+        }
+    }
+
+    #[test]
+    fn vertex_array_attribute_formats() {
+        assert_eq!(from_attribute_format(AttributeFormat::Vector3), (3, gl::FLOAT, false, false));
+        assert_eq!(from_attribute_format(AttributeFormat::NormalizedBytes), (4, gl::UNSIGNED_BYTE, true, false));
+        assert_eq!(from_attribute_format(AttributeFormat::Bytes), (4, gl::UNSIGNED_BYTE, false, true));
+        assert_eq!(from_attribute_format(AttributeFormat::Integer), (1, gl::INT, false, true));
+        assert_eq!(AttributeFormat::Vector3.size(), 12);
+        assert_eq!(AttributeFormat::Bytes.size(), 4);
+
+        // The layout of the default vertex type must match its fields.
+        let vertex = Vertex::new();
+        let base = &vertex as *const Vertex as usize;
+        let offsets: Vec<usize> = Vertex::attributes().iter().map(|attribute| attribute.offset).collect();
+        assert_eq!(offsets, vec![
+            &vertex.x as *const f32 as usize - base,
+            &vertex.r as *const f32 as usize - base,
+            &vertex.u as *const f32 as usize - base
+        ]);
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    struct ShortVertex {
+        position: [f32; 2]
+    }
+
+    const SHORT_ATTRIBUTES: [Attribute; 1] = [
+        Attribute::new(0, AttributeFormat::Vector4, 0)
+    ];
+
+    unsafe impl VertexLayout for ShortVertex {
+        fn attributes() -> &'static [Attribute] {
+            &SHORT_ATTRIBUTES
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't fit in the vertex")]
+    fn vertex_array_with_layout_overflow() {
+        VertexArray::<ShortVertex>::with_layout();
+    }
+
+    #[test]
+    fn vertex_array_compute_bounds() {
+        let vertices = vec![
+            CustomVertex { position: [1.0, 2.0], color: [0; 4], flags: 0 },
+            CustomVertex { position: [-1.0, 4.0], color: [0; 4], flags: 0 }
+        ];
+
+        let bounds = VertexArray::<CustomVertex>::compute_bounds(&vertices);
+        assert_eq!(bounds, Box::new(Position::new(-1.0, 2.0), Size::new(2.0, 2.0)));
+        assert_eq!(VertexArray::<CustomVertex>::compute_bounds(&[]), Box::default());
+    }
+
+    #[test]
+    fn vertex_array_with_layout() {
+        let _lock = lock_rendering();
+
+        let vertices = vec![
+            CustomVertex { position: [0.0, 0.0], color: [255, 0, 0, 255], flags: 1 },
+            CustomVertex { position: [4.0, 2.0], color: [0, 255, 0, 255], flags: 2 }
+        ];
+
+        let mut vertex_array = VertexArray::<CustomVertex>::with_layout();
+        assert_eq!(vertex_array.size(), 0);
+
+        vertex_array.update_vertices(&vertices);
+        assert_eq!(vertex_array.size(), 2);
+        assert_eq!(vertex_array.vertices(), vertices);
+        assert_eq!(vertex_array.bounds(), Box::new(Position::new(0.0, 0.0), Size::new(4.0, 2.0)));
+    }
 
     #[test]
     fn vertex_array_new() {
+        let _lock = lock_rendering();
+
         let vertex_array = VertexArray::new();
         assert_eq!(vertex_array.primitive(), Primitive::Points);
         assert_eq!(vertex_array.usage(), Usage::Stream);
@@ -481,6 +1137,8 @@ mod tests {
 
     #[test]
     fn vertex_array_with_vertices() {
+        let _lock = lock_rendering();
+
         let vertices = vec![
             Vertex { x: -0.5, y: -0.5, r: 1.0, g: 0.0, b: 0.0, a: 0.0, u: 0.0, v: 0.0 },
             Vertex { x:  0.0, y:  0.5, r: 0.0, g: 1.0, b: 0.0, a: 0.0, u: 0.0, v: 0.0 },
@@ -496,6 +1154,8 @@ mod tests {
 
     #[test]
     fn vertex_array_primitive() {
+        let _lock = lock_rendering();
+
         let mut vertex_array = VertexArray::new();
         assert_eq!(vertex_array.primitive(), Primitive::Points);
 
@@ -508,6 +1168,8 @@ mod tests {
 
     #[test]
     fn vertex_array_usage() {
+        let _lock = lock_rendering();
+
         // The implementation of set_usage() is a bit complicated because the 'usage' can't be
         // changed without re-creating the OpenGL object buffer. Therefore, the validity of the
         // vertices must be tested as well.
@@ -532,6 +1194,8 @@ mod tests {
 
     #[test]
     fn vertex_array_vertices() {
+        let _lock = lock_rendering();
+
 
         let vertices = vec![
             Vertex { x: -0.5, y: -0.5, r: 1.0, g: 0.0, b: 0.0, a: 0.0, u: 0.0, v: 0.0 },
@@ -546,6 +1210,293 @@ mod tests {
         assert_eq!(vertex_array.size(), 3);
     }
 
+    #[test]
+    fn vertex_array_grown_capacity() {
+        assert_eq!(grown_capacity(0, 3), 3);
+        assert_eq!(grown_capacity(4, 5), 8);
+        assert_eq!(grown_capacity(4, 12), 12);
+    }
+
+    #[test]
+    fn vertex_array_capacity() {
+        let _lock = lock_rendering();
+
+        let vertex = |x: f32| Vertex::with_position_and_color(Position::new(x, 0.0), Color::WHITE);
+
+        let mut vertex_array = VertexArray::new();
+        assert_eq!(vertex_array.capacity(), 0);
+
+        vertex_array.update_vertices(&vec![vertex(0.0), vertex(1.0), vertex(2.0)]);
+        assert_eq!(vertex_array.capacity(), 3);
+
+        // Updating with fewer vertices keeps the capacity.
+        vertex_array.update_vertices(&vec![vertex(4.0)]);
+        assert_eq!(vertex_array.size(), 1);
+        assert_eq!(vertex_array.capacity(), 3);
+        assert_eq!(vertex_array.vertices(), vec![vertex(4.0)]);
+
+        vertex_array.extend_vertices(&[vertex(5.0), vertex(6.0), vertex(7.0)]);
+        assert_eq!(vertex_array.size(), 4);
+        assert_eq!(vertex_array.capacity(), 6);
+        assert_eq!(vertex_array.vertices(), vec![vertex(4.0), vertex(5.0), vertex(6.0), vertex(7.0)]);
+
+        vertex_array.reserve(10);
+        assert_eq!(vertex_array.capacity(), 14);
+        assert_eq!(vertex_array.size(), 4);
+
+        vertex_array.set_usage(Usage::Static);
+        assert_eq!(vertex_array.capacity(), 14);
+        assert_eq!(vertex_array.vertices(), vec![vertex(4.0), vertex(5.0), vertex(6.0), vertex(7.0)]);
+
+        vertex_array.update_vertices(&vec![]);
+        assert_eq!(vertex_array.size(), 0);
+        assert_eq!(vertex_array.capacity(), 14);
+    }
+
+    #[test]
+    fn vertex_array_update_vertex_range() {
+        let _lock = lock_rendering();
+
+        let vertex = |x: f32| Vertex::with_position_and_color(Position::new(x, 0.0), Color::WHITE);
+
+        let mut vertex_array = VertexArray::with_vertices(&vec![vertex(0.0), vertex(1.0), vertex(2.0)], Primitive::Points, Usage::Dynamic);
+        vertex_array.update_vertex_range(1, &[vertex(8.0), vertex(-2.0)]);
+
+        assert_eq!(vertex_array.vertices(), vec![vertex(0.0), vertex(8.0), vertex(-2.0)]);
+        assert_eq!(vertex_array.bounds(), Box::new(Position::new(-2.0, 0.0), Size::new(10.0, 0.0)));
+    }
+
+    #[test]
+    fn vertex_array_map_vertices() {
+        let _lock = lock_rendering();
+
+        let vertex = |x: f32| Vertex::with_position_and_color(Position::new(x, 0.0), Color::WHITE);
+
+        let mut vertex_array = VertexArray::with_vertices(&vec![vertex(0.0), vertex(1.0), vertex(2.0)], Primitive::Points, Usage::Dynamic);
+        {
+            let mut vertices = vertex_array.map_vertices(1..3);
+            assert_eq!(vertices.len(), 2);
+            assert_eq!(vertices[0], vertex(1.0));
+
+            for vertex in vertices.iter_mut() {
+                vertex.x *= 4.0;
+            }
+        }
+
+        assert_eq!(vertex_array.vertices(), vec![vertex(0.0), vertex(4.0), vertex(8.0)]);
+        assert_eq!(vertex_array.bounds(), Box::new(Position::new(0.0, 0.0), Size::new(8.0, 0.0)));
+        assert_eq!(vertex_array.map_vertices(3..3).len(), 0);
+    }
+
+    #[test]
+    fn vertex_array_indices() {
+        let _lock = lock_rendering();
+
+        let vertices = vec![
+            Vertex { x: 0.0, y: 0.0, r: 1.0, g: 1.0, b: 1.0, a: 1.0, u: 0.0, v: 0.0 },
+            Vertex { x: 1.0, y: 0.0, r: 1.0, g: 1.0, b: 1.0, a: 1.0, u: 0.0, v: 0.0 },
+            Vertex { x: 0.0, y: 1.0, r: 1.0, g: 1.0, b: 1.0, a: 1.0, u: 0.0, v: 0.0 },
+            Vertex { x: 1.0, y: 1.0, r: 1.0, g: 1.0, b: 1.0, a: 1.0, u: 0.0, v: 0.0 },
+        ];
+
+        let mut vertex_array = VertexArray::with_vertices(&vertices, Primitive::Triangles, Usage::Dynamic);
+        assert!(!vertex_array.is_indexed());
+        assert_eq!(vertex_array.index_count(), 0);
+
+        vertex_array.update_indices(&[0u16, 1, 2, 2, 1, 3]);
+        assert!(vertex_array.is_indexed());
+        assert_eq!(vertex_array.indices(), vec![0, 1, 2, 2, 1, 3]);
+
+        vertex_array.set_usage(Usage::Static);
+        assert_eq!(vertex_array.indices(), vec![0, 1, 2, 2, 1, 3]);
+
+        vertex_array.update_indices(&[3u32, 2, 1]);
+        assert_eq!(vertex_array.index_count(), 3);
+        assert_eq!(vertex_array.indices(), vec![3, 2, 1]);
+
+        vertex_array.update_indices::<u32>(&[]);
+        assert!(!vertex_array.is_indexed());
+        assert!(vertex_array.indices().is_empty());
+    }
+
+    #[test]
+    fn vertex_array_index_capacity() {
+        let _lock = lock_rendering();
+
+        // The OpenGL object buffer of the indices is kept when they're updated.
+        let mut vertex_array = VertexArray::new();
+        vertex_array.set_usage(Usage::Dynamic);
+
+        vertex_array.update_indices(&[0u16, 1, 2, 3]);
+        let index_buffer = vertex_array.index_buffer;
+        assert_eq!(vertex_array.index_capacity, 8);
+
+        vertex_array.update_indices(&[3u16, 2]);
+        assert_eq!(vertex_array.index_buffer, index_buffer);
+        assert_eq!(vertex_array.index_capacity, 8);
+        assert_eq!(vertex_array.indices(), vec![3, 2]);
+
+        vertex_array.update_indices(&[0u32, 1, 2, 3]);
+        assert_eq!(vertex_array.index_buffer, index_buffer);
+        assert_eq!(vertex_array.index_capacity, 16);
+        assert_eq!(vertex_array.indices(), vec![0, 1, 2, 3]);
+
+        vertex_array.update_indices::<u16>(&[]);
+        vertex_array.update_indices(&[1u16]);
+        assert_eq!(vertex_array.index_buffer, index_buffer);
+        assert_eq!(vertex_array.index_count(), 1);
+    }
+
+    #[test]
+    fn vertex_array_instances() {
+        let _lock = lock_rendering();
+
+        let mut vertex_array = VertexArray::new();
+        assert!(!vertex_array.is_instanced());
+
+        let instances = vec![
+            Instance::new(),
+            Instance::with_matrix_and_color(Matrix::with_elements([1.0, 0.0, 8.0, 0.0, 1.0, 0.0]), Color::RED)
+        ];
+        vertex_array.update_instances(&instances);
+        assert!(vertex_array.is_instanced());
+        assert_eq!(vertex_array.instance_count(), 2);
+        assert_eq!(vertex_array.instances(), instances);
+
+        // The OpenGL object buffer of the instances is kept when they're updated.
+        let instance_buffer = vertex_array.instance_buffer;
+        let instance_capacity = vertex_array.instance_capacity;
+
+        vertex_array.update_instances(&instances[1..]);
+        assert_eq!(vertex_array.instance_buffer, instance_buffer);
+        assert_eq!(vertex_array.instance_capacity, instance_capacity);
+        assert_eq!(vertex_array.instances(), &instances[1..]);
+
+        vertex_array.update_instances(&[]);
+        assert!(!vertex_array.is_instanced());
+        assert_eq!(vertex_array.instance_count(), 0);
+
+        vertex_array.update_instances(&instances);
+        assert_eq!(vertex_array.instance_buffer, instance_buffer);
+        assert_eq!(vertex_array.instances(), instances);
+    }
+
+    #[test]
+    fn vertex_array_draw_instances() {
+        let image = render(Size::new(16, 8), |surface| {
+            let white = Color::WHITE;
+            let quad = vec![
+                Vertex::with_position_and_color(Position::new(0.0, 0.0), white),
+                Vertex::with_position_and_color(Position::new(4.0, 0.0), white),
+                Vertex::with_position_and_color(Position::new(0.0, 4.0), white),
+                Vertex::with_position_and_color(Position::new(4.0, 4.0), white)
+            ];
+
+            let mut vertex_array = VertexArray::with_vertices(&quad, Primitive::TriangleStrips, Usage::Static);
+            vertex_array.update_instances(&[
+                Instance::with_matrix_and_color(Matrix::new(), Color::RED),
+                Instance::with_matrix_and_color(Matrix::with_elements([1.0, 0.0, 8.0, 0.0, 1.0, 4.0]), Color::BLUE)
+            ]);
+
+            surface.erase(Color::BLACK);
+            surface.draw_vertices(&vertex_array, &RenderStates::default());
+        });
+
+        assert_eq!(pixel(&image, 1, 1), Color::RED);
+        assert_eq!(pixel(&image, 9, 5), Color::BLUE);
+        assert_eq!(pixel(&image, 5, 1), Color::BLACK);
+        assert_eq!(pixel(&image, 1, 5), Color::BLACK);
+    }
+
+    #[repr(C)]
+    #[derive(Copy, Clone)]
+    struct ColoredVertex {
+        position: [f32; 2],
+        color: [f32; 4]
+    }
+
+    // The color is at the location of the first row of the matrix of the instances.
+    const COLORED_ATTRIBUTES: [Attribute; 2] = [
+        Attribute::new(0, AttributeFormat::Vector2, 0),
+        Attribute::new(3, AttributeFormat::Vector4, 8)
+    ];
+
+    unsafe impl VertexLayout for ColoredVertex {
+        fn attributes() -> &'static [Attribute] {
+            &COLORED_ATTRIBUTES
+        }
+    }
+
+    const COLORED_VERTEX_SHADER_SRC: &[u8] = b"
+#version 300 es
+precision mediump float;
+
+layout(location = 0) in vec2 vertex_position;
+layout(location = 3) in vec4 vertex_color;
+
+out vec4 fragment_color;
+
+uniform mat3 model;
+uniform mat4 viewport;
+
+void main() {
+    gl_Position = viewport * vec4((model * vec3(vertex_position, 1.0)), 1.0);
+    fragment_color = vertex_color;
+}
+\0";
+
+    const COLORED_FRAGMENT_SHADER_SRC: &[u8] = b"
+#version 300 es
+precision mediump float;
+
+in vec4 fragment_color;
+out vec4 out_color;
+
+void main() {
+    out_color = fragment_color;
+}
+\0";
+
+    #[test]
+    fn vertex_array_draw_after_instances() {
+        let image = render(Size::new(16, 8), |surface| {
+            let quad = vec![
+                Vertex::with_position_and_color(Position::new(0.0, 0.0), Color::WHITE),
+                Vertex::with_position_and_color(Position::new(4.0, 0.0), Color::WHITE),
+                Vertex::with_position_and_color(Position::new(0.0, 4.0), Color::WHITE),
+                Vertex::with_position_and_color(Position::new(4.0, 4.0), Color::WHITE)
+            ];
+
+            let mut instanced = VertexArray::with_vertices(&quad, Primitive::TriangleStrips, Usage::Static);
+            instanced.update_instances(&[Instance::with_matrix_and_color(Matrix::new(), Color::RED)]);
+
+            // Each vertex has its own color; if the divisor of the location 3 was left to one,
+            // they would all have the color of the first one.
+            let colored = vec![
+                ColoredVertex { position: [8.0, 0.0], color: [0.0, 0.0, 1.0, 1.0] },
+                ColoredVertex { position: [16.0, 0.0], color: [0.0, 0.0, 1.0, 1.0] },
+                ColoredVertex { position: [8.0, 8.0], color: [0.0, 1.0, 0.0, 1.0] },
+                ColoredVertex { position: [16.0, 8.0], color: [0.0, 1.0, 0.0, 1.0] }
+            ];
+            let colored = VertexArray::with_vertices(&colored, Primitive::TriangleStrips, Usage::Static);
+            let shader = Shader::new(COLORED_VERTEX_SHADER_SRC, COLORED_FRAGMENT_SHADER_SRC).unwrap();
+
+            surface.erase(Color::BLACK);
+            surface.draw_vertices(&instanced, &RenderStates::default());
+            surface.draw_vertices(&colored, &RenderStates {
+                shader: Some(&shader),
+                ..RenderStates::default()
+            });
+        });
+
+        assert_eq!(pixel(&image, 1, 1), Color::RED);
+
+        let top = pixel(&image, 12, 0);
+        let bottom = pixel(&image, 12, 7);
+        assert!(top.blue > 200 && top.green < 50);
+        assert!(bottom.green > 200 && bottom.blue < 50);
+    }
+
     #[test]
     fn vertex_array_bounds() {
         // To be implemented.
@@ -553,7 +1504,23 @@ mod tests {
 
     #[test]
     fn vertex_array_draw() {
-        // To be implemented.
+        let image = render(Size::new(8, 8), |surface| {
+            let vertices = vec![
+                Vertex::with_position_and_color(Position::new(2.0, 2.0), Color::GREEN),
+                Vertex::with_position_and_color(Position::new(6.0, 2.0), Color::GREEN),
+                Vertex::with_position_and_color(Position::new(2.0, 6.0), Color::GREEN),
+                Vertex::with_position_and_color(Position::new(6.0, 6.0), Color::GREEN)
+            ];
+            let vertex_array = VertexArray::with_vertices(&vertices, Primitive::TriangleStrips, Usage::Static);
+
+            surface.erase(Color::BLACK);
+            surface.draw_vertices(&vertex_array, &RenderStates::default());
+        });
+
+        assert_eq!(pixel(&image, 1, 1), Color::BLACK);
+        assert_eq!(pixel(&image, 2, 2), Color::GREEN);
+        assert_eq!(pixel(&image, 5, 5), Color::GREEN);
+        assert_eq!(pixel(&image, 6, 6), Color::BLACK);
     }
 
     #[test]
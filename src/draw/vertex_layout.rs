@@ -0,0 +1,79 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+use crate::geometry::Position;
+use crate::draw::Attribute;
+
+/// The layout of a vertex type.
+///
+/// A vertex layout describes the attributes of a vertex type, so a vertex array of this type (see
+/// `VertexArray`) can pass them to the shaders. `Vertex` is the vertex type of the framework, but
+/// custom shaders may need other attributes (normals, extra texture coordinates, bone weights,
+/// etc.); they're declared by implementing this trait for a custom vertex type.
+///
+/// ```no_run
+/// # use byteplug::draw::{VertexArray, VertexLayout, Attribute, AttributeFormat};
+/// #[repr(C)]
+/// #[derive(Copy, Clone)]
+/// struct SkinnedVertex {
+///     position: [f32; 2],
+///     texture: [f32; 2],
+///     bones: [u8; 4],
+///     weights: [u8; 4]
+/// }
+///
+/// const SKINNED_ATTRIBUTES: [Attribute; 4] = [
+///     Attribute::new(0, AttributeFormat::Vector2, 0),
+///     Attribute::new(1, AttributeFormat::Vector2, 8),
+///     Attribute::new(2, AttributeFormat::Bytes, 16),
+///     Attribute::new(3, AttributeFormat::NormalizedBytes, 20)
+/// ];
+///
+/// unsafe impl VertexLayout for SkinnedVertex {
+///     fn attributes() -> &'static [Attribute] {
+///         &SKINNED_ATTRIBUTES
+///     }
+/// }
+///
+/// # let vertices = vec![SkinnedVertex {
+/// #     position: [0.0; 2], texture: [0.0; 2], bones: [0; 4], weights: [0; 4]
+/// # }];
+/// let mut vertex_array = VertexArray::<SkinnedVertex>::with_layout();
+/// vertex_array.update_vertices(&vertices);
+/// ```
+///
+/// # Safety
+///
+/// The vertices are copied to and from the graphics memory as raw bytes, so any bytes must be a
+/// valid vertex; the vertex type must be 'plain old data'.
+///
+/// - It must be `#[repr(C)]` so the offsets of its fields are well-defined.
+/// - Its fields must be numbers (or arrays and `#[repr(C)]` structs of numbers); references,
+///   pointers, `bool`, `char` and enums aren't allowed.
+/// - It must not have padding bytes.
+///
+/// **Implementation notes**
+///
+/// - The attributes are checked against the size of the vertex type when a vertex array is
+///   constructed; an attribute that doesn't fit in it panics.
+/// - The locations 3 to 6 are used by the attributes of the instances (see `Instance`); a vertex
+///   type that is drawn with instances can't use them.
+/// - The position of a vertex is optional; it's only used to compute the bounds of a vertex array.
+///
+pub unsafe trait VertexLayout: Copy {
+    /// Returns the attributes of the vertex type.
+    fn attributes() -> &'static [Attribute];
+
+    /// Returns the position of a vertex.
+    ///
+    /// This function returns the position of the vertex in the 2D space, if it has one; the
+    /// default implementation returns `None`.
+    ///
+    fn position(&self) -> Option<Position<f32>> {
+        None
+    }
+}
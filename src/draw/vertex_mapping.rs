@@ -0,0 +1,77 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+use std::ops::{Deref, DerefMut};
+use crate::draw::gl;
+use crate::draw::{Vertex, VertexLayout, VertexArray};
+
+/// A range of vertices mapped to the system memory.
+///
+/// A vertex mapping gives a direct access to a range of the vertices of a vertex array (see
+/// `VertexArray::map_vertices()`); it dereferences to a slice of vertices which can be read and
+/// modified in place, without copying them around. The changes are sent to the graphics memory
+/// when the mapping is dropped.
+///
+/// ```no_run
+/// # use byteplug::draw::{VertexArray, Vertex, Primitive, Usage};
+/// # let vertices = vec![Vertex::default(); 4];
+/// # let mut vertex_array = VertexArray::with_vertices(&vertices, Primitive::Points, Usage::Dynamic);
+/// let mut vertices = vertex_array.map_vertices(0..4);
+/// for vertex in vertices.iter_mut() {
+///     vertex.y += 1.0;
+/// }
+/// ```
+///
+/// **Implementation notes**
+///
+/// - The mapping borrows the vertex array mutably, so it can't be drawn (or mapped again) until
+///   the mapping is dropped; OpenGL doesn't allow drawing with a mapped object buffer.
+/// - Dropping the mapping binds the object buffer of the vertex array again before unmapping it,
+///   in case another object buffer was bound in the meantime.
+///
+pub struct VertexMapping<'a, V: VertexLayout = Vertex> {
+    vertex_array: &'a mut VertexArray<V>,
+    pointer: *mut V, // dangling if the range is empty (nothing is mapped)
+    length: usize
+}
+
+impl<'a, V: VertexLayout> VertexMapping<'a, V> {
+    pub(crate) fn new(vertex_array: &'a mut VertexArray<V>, pointer: *mut V, length: usize) -> VertexMapping<'a, V> {
+        VertexMapping {
+            vertex_array: vertex_array,
+            pointer: pointer,
+            length: length
+        }
+    }
+}
+
+impl<'a, V: VertexLayout> Deref for VertexMapping<'a, V> {
+    type Target = [V];
+
+    fn deref(&self) -> &[V] {
+        unsafe { std::slice::from_raw_parts(self.pointer, self.length) }
+    }
+}
+
+impl<'a, V: VertexLayout> DerefMut for VertexMapping<'a, V> {
+    fn deref_mut(&mut self) -> &mut [V] {
+        unsafe { std::slice::from_raw_parts_mut(self.pointer, self.length) }
+    }
+}
+
+impl<'a, V: VertexLayout> Drop for VertexMapping<'a, V> {
+    fn drop(&mut self) {
+        if self.length == 0 {
+            return
+        }
+
+        self.vertex_array.bind();
+        unsafe {
+            gl_check!(let _ = gl::UnmapBuffer(gl::ARRAY_BUFFER));
+        }
+    }
+}
@@ -0,0 +1,24 @@
+// Copyright (c) 2020 - Jonathan De Wachter
+//
+// This source file is part of the Byteplug framework which is released under the MIT license.
+// Please refer to the LICENSE file that can be found at the root of the project directory.
+//
+// Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, June 2020
+
+/// The wrapping modes of a texture
+///
+/// A wrapping mode specifies how a texture is sampled outside of its bounds, that is, when the
+/// texture coordinates are lower than 0 or greater than 1. Each axis of a texture has its own
+/// wrapping mode.
+///
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Wrap {
+    /// The pixels at the edge of the texture are repeated.
+    Clamp,
+    /// The border color of the texture is used.
+    Border,
+    /// The texture is repeated.
+    Repeat,
+    /// The texture is repeated, and mirrored every other time.
+    MirroredRepeat
+}
@@ -5,8 +5,442 @@
 //
 // Written by Jonathan De Wachter <dewachter.jonathan@gmail.com>, May 2020
 
-/// A drawable set of images.
+use crate::geometry::{Position, Size, Box};
+use crate::geometry::Matrix;
+use crate::geometry::{Transformable, Transformer};
+use crate::image::Color;
+use crate::draw::{Surface, Texture};
+use crate::draw::{Primitive, Usage};
+use crate::draw::{Vertex, VertexArray};
+use crate::draw::{Drawable, RenderStates, Batch};
+
+fn compute_vertices(texture_rect: Box<i32>, texture_size: Size<i32>, color: Color, flip_horizontally: bool, flip_vertically: bool) -> [Vertex; 4] {
+    let width = texture_rect.size.width as f32;
+    let height = texture_rect.size.height as f32;
+
+    // Compute the normalized texture coordinates; flipping the sprite is done by swapping them.
+    let (mut u1, mut v1, mut u2, mut v2) = (0.0, 0.0, 0.0, 0.0);
+    if texture_size.width > 0 && texture_size.height > 0 {
+        u1 = texture_rect.left() as f32 / texture_size.width as f32;
+        v1 = texture_rect.top() as f32 / texture_size.height as f32;
+        u2 = texture_rect.right() as f32 / texture_size.width as f32;
+        v2 = texture_rect.bottom() as f32 / texture_size.height as f32;
+    }
+
+    if flip_horizontally {
+        std::mem::swap(&mut u1, &mut u2);
+    }
+
+    if flip_vertically {
+        std::mem::swap(&mut v1, &mut v2);
+    }
+
+    let corners = [
+        (Position::new(0.0, 0.0), u1, v1),
+        (Position::new(width, 0.0), u2, v1),
+        (Position::new(0.0, height), u1, v2),
+        (Position::new(width, height), u2, v2)
+    ];
+
+    let mut vertices = [Vertex::default(); 4];
+    for (vertex, (position, u, v)) in vertices.iter_mut().zip(corners.iter()) {
+        *vertex = Vertex::with_position_and_color(*position, color);
+        vertex.u = *u;
+        vertex.v = *v;
+    }
+
+    vertices
+}
+
+/// A drawable textured quad.
 ///
-/// The **Sprite struct** is not documented yet. Pull requests are welcome.
+/// A sprite is the most common drawable entity of a game; it's a rectangle that displays a
+/// texture, or a part of it (the texture rectangle). It can be tinted with a color, flipped
+/// horizontally and vertically, and it's transformable around its origin.
 ///
-pub struct Sprite {}
+/// Use `new()` to construct a sprite with no texture and `with_texture()` to construct a sprite
+/// that displays an entire texture.
+///
+/// ```no_run
+/// # use byteplug::geometry::{Position, Size, Box, Transformable};
+/// # use byteplug::draw::{Surface, Options, Texture};
+/// # use byteplug::game::Sprite;
+/// # let mut surface = Surface::new(Size::new(640, 480), Options::default()).unwrap();
+/// let texture = Texture::open("resources/characters.png").unwrap();
+///
+/// let mut sprite = Sprite::with_texture(&texture);
+/// sprite.set_texture_rect(Some(Box::new(Position::new(32, 0), Size::new(32, 32))));
+/// sprite.set_origin(Position::new(16.0, 16.0));
+/// sprite.set_position(Position::new(100.0, 100.0));
+/// sprite.set_flipped_horizontally(true);
+/// sprite.update();
+///
+/// surface.draw(&sprite);
+/// ```
+///
+/// The local bounds of a sprite are the size of its texture rectangle, with the top-left corner at
+/// the origin of the local coordinate system. The origin of the sprite is the point (in local
+/// coordinates) which is placed at the position of the sprite, and around which it's rotated and
+/// magnified. The bounds (the global bounds) are computed from the transformation of the sprite.
+///
+/// **Implementation notes**
+///
+/// - Just like `Shape` and `Text`, the vertices are not updated immediately; they're updated with
+///   the `update()` method. Properties changes are only visible after it's called.
+/// - The size of the texture is retrieved when it's set. If the texture is resized afterward, it
+///   must be set again.
+/// - If the sprite has no texture rectangle, the entire texture is displayed.
+/// - If the sprite has no texture, nothing is drawn.
+/// - The vertex array is created on the first update, therefore, sprites can be constructed (and
+///   transformed) without an OpenGL context.
+///
+pub struct Sprite<'a> {
+    texture: Option<&'a Texture>,
+    texture_size: Size<i32>,
+    texture_rect: Option<Box<i32>>,
+    color: Color,
+    flipped_horizontally: bool,
+    flipped_vertically: bool,
+    origin: Position,
+    position: Position,
+    angle: f32,
+    magnifier: f32,
+    vertices: Option<VertexArray>,
+    update: bool // indicate if vertices need to be re-computed
+}
+
+impl<'a> Sprite<'a> {
+    /// Constructs an empty sprite.
+    ///
+    /// This function is the default constructor. It creates a sprite with no texture; nothing is
+    /// drawn until a texture is set.
+    ///
+    pub fn new() -> Sprite<'a> {
+        Sprite {
+            texture: None,
+            texture_size: Size::default(),
+            texture_rect: None,
+            color: Color::WHITE,
+            flipped_horizontally: false,
+            flipped_vertically: false,
+            origin: Position::zero(),
+            position: Position::zero(),
+            angle: 0.0,
+            magnifier: 1.0,
+            vertices: None,
+            update: true
+        }
+    }
+
+    /// Constructs a sprite from a texture.
+    ///
+    /// This function constructs a sprite that displays an entire texture.
+    ///
+    pub fn with_texture(texture: &'a Texture) -> Sprite<'a> {
+        let mut sprite = Sprite::new();
+        sprite.set_texture(texture);
+
+        sprite
+    }
+
+    /// Returns the texture of the sprite.
+    ///
+    /// This function returns the texture displayed by the sprite, if any.
+    ///
+    pub fn texture(&self) -> Option<&'a Texture> {
+        self.texture
+    }
+
+    /// Changes the texture of the sprite.
+    ///
+    /// This function changes the texture displayed by the sprite; the texture rectangle is
+    /// unchanged. Note that the texture must outlive the sprite.
+    ///
+    pub fn set_texture(&mut self, texture: &'a Texture) {
+        self.texture = Some(texture);
+        self.texture_size = texture.size();
+        self.update = true;
+    }
+
+    /// Removes the texture of the sprite.
+    ///
+    /// This function removes the texture of the sprite; nothing is drawn until a new texture is
+    /// set.
+    ///
+    pub fn unset_texture(&mut self) {
+        self.texture = None;
+        self.texture_size = Size::default();
+        self.update = true;
+    }
+
+    /// Returns the texture rectangle.
+    ///
+    /// This function returns the area of the texture (in pixels) which is displayed, if any.
+    ///
+    pub fn texture_rect(&self) -> Option<Box<i32>> {
+        self.texture_rect
+    }
+
+    /// Changes the texture rectangle.
+    ///
+    /// This function changes the area of the texture (in pixels) which is displayed. The entire
+    /// texture is displayed if there is no texture rectangle (which is the default).
+    ///
+    pub fn set_texture_rect(&mut self, texture_rect: Option<Box<i32>>) {
+        self.texture_rect = texture_rect;
+        self.update = true;
+    }
+
+    /// Returns the color of the sprite.
+    ///
+    /// This function returns the color the texture is multiplied with.
+    ///
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Changes the color of the sprite.
+    ///
+    /// This function changes the color the texture is multiplied with; it tints the sprite, and
+    /// its alpha channel changes the opacity of the sprite. The default color is white (the
+    /// texture is unchanged).
+    ///
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+        self.update = true;
+    }
+
+    /// Returns whether the sprite is flipped horizontally.
+    ///
+    /// This function returns whether the texture is mirrored along the vertical axis.
+    ///
+    pub fn is_flipped_horizontally(&self) -> bool {
+        self.flipped_horizontally
+    }
+
+    /// Flips the sprite horizontally.
+    ///
+    /// This function changes whether the texture is mirrored along the vertical axis. The sprite
+    /// isn't flipped by default.
+    ///
+    pub fn set_flipped_horizontally(&mut self, flipped: bool) {
+        self.flipped_horizontally = flipped;
+        self.update = true;
+    }
+
+    /// Returns whether the sprite is flipped vertically.
+    ///
+    /// This function returns whether the texture is mirrored along the horizontal axis.
+    ///
+    pub fn is_flipped_vertically(&self) -> bool {
+        self.flipped_vertically
+    }
+
+    /// Flips the sprite vertically.
+    ///
+    /// This function changes whether the texture is mirrored along the horizontal axis. The sprite
+    /// isn't flipped by default.
+    ///
+    pub fn set_flipped_vertically(&mut self, flipped: bool) {
+        self.flipped_vertically = flipped;
+        self.update = true;
+    }
+
+    /// Returns the origin of the sprite.
+    ///
+    /// This function returns the point (in local coordinates) which is placed at the position of
+    /// the sprite.
+    ///
+    pub fn origin(&self) -> Position {
+        self.origin
+    }
+
+    /// Changes the origin of the sprite.
+    ///
+    /// This function changes the point (in local coordinates) which is placed at the position of
+    /// the sprite, and around which it's rotated and magnified. The default origin is the top-left
+    /// corner of the sprite.
+    ///
+    pub fn set_origin(&mut self, origin: Position) {
+        self.origin = origin;
+    }
+
+    /// Returns the local bounds of the sprite.
+    ///
+    /// This function returns the bounding box of the sprite, ignoring its transformation.
+    ///
+    pub fn local_bounds(&self) -> Box<f32> {
+        let size = self.effective_texture_rect().size;
+        Box::new(Position::zero(), Size::new(size.width as f32, size.height as f32))
+    }
+
+    /// Returns the bounds of the sprite.
+    ///
+    /// This function returns the bounding box of the sprite, after its transformation (origin,
+    /// position, angle and magnifier) is applied.
+    ///
+    pub fn bounds(&self) -> Box<f32> {
+        Transformer::with_matrix(self.matrix()).transform_box(&self.local_bounds())
+    }
+
+    /// Updates the vertices of the sprite.
+    ///
+    /// This function updates the vertices on the graphics memory if the sprite was modified since
+    /// the last update.
+    ///
+    pub fn update(&mut self) {
+        if !self.update {
+            return
+        }
+
+        let vertices = compute_vertices(
+            self.effective_texture_rect(),
+            self.texture_size,
+            self.color,
+            self.flipped_horizontally,
+            self.flipped_vertically
+        );
+
+        let vertex_array = self.vertices.get_or_insert_with(|| {
+            let mut vertex_array = VertexArray::new();
+            vertex_array.set_primitive(Primitive::TriangleStrips);
+            vertex_array.set_usage(Usage::Stream);
+
+            vertex_array
+        });
+        vertex_array.update_vertices(&vertices.to_vec());
+
+        // Mark the vertices as updated.
+        self.update = false;
+    }
+
+    /// Adds the sprite to a batch.
+    ///
+    /// This function adds the sprite, with its current properties, to a batch in a given layer;
+    /// it doesn't need to be updated first. Nothing is added if the sprite has no texture.
+    ///
+    pub fn add_to_batch(&self, batch: &mut Batch<'a>, layer: i32) {
+        if let Some(texture) = self.texture {
+            let vertices = compute_vertices(
+                self.effective_texture_rect(),
+                self.texture_size,
+                self.color,
+                self.flipped_horizontally,
+                self.flipped_vertically
+            );
+
+            batch.add_quad(&vertices, Some(texture), Some(self.matrix()), layer);
+        }
+    }
+
+    fn effective_texture_rect(&self) -> Box<i32> {
+        match self.texture_rect {
+            Some(texture_rect) => texture_rect,
+            None => Box::new(Position::zero(), self.texture_size)
+        }
+    }
+}
+
+impl<'a> Transformable for Sprite<'a> {
+    fn position(&self) -> Position {
+        self.position
+    }
+
+    fn set_position(&mut self, position: Position) {
+        self.position = position;
+    }
+
+    fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    fn set_angle(&mut self, angle: f32) {
+        self.angle = angle;
+    }
+
+    fn magnifier(&self) -> f32 {
+        self.magnifier
+    }
+
+    fn set_magnifier(&mut self, magnifier: f32) {
+        self.magnifier = magnifier;
+    }
+
+    fn matrix(&self) -> Matrix {
+        // The origin is moved to the position, and the sprite is rotated and magnified around it.
+        let transformer = Transformer::new()
+            .translate(self.position)
+            .rotate(self.angle, None)
+            .magnify(self.magnifier, None)
+            .translate(-self.origin);
+
+        transformer.matrix
+    }
+}
+
+impl<'a> Drawable for Sprite<'a> {
+    fn draw(&self, surface: &mut Surface, states: &RenderStates) {
+        if let (Some(texture), Some(vertices)) = (self.texture, &self.vertices) {
+            let states = RenderStates {
+                texture: Some(texture),
+                ..states.transformed(self.matrix())
+            };
+            surface.draw_vertices(vertices, &states);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sprite_compute_vertices() {
+        let rect = Box::new(Position::new(16, 32), Size::new(16, 8));
+        let size = Size::new(64, 64);
+
+        let vertices = compute_vertices(rect, size, Color::RED, false, false);
+        assert_eq!((vertices[0].x, vertices[0].y), (0.0, 0.0));
+        assert_eq!((vertices[3].x, vertices[3].y), (16.0, 8.0));
+        assert_eq!((vertices[0].u, vertices[0].v), (0.25, 0.5));
+        assert_eq!((vertices[3].u, vertices[3].v), (0.5, 0.625));
+
+        for vertex in vertices.iter() {
+            assert_eq!((vertex.r, vertex.g, vertex.b, vertex.a), (1.0, 0.0, 0.0, 1.0));
+        }
+
+        // Flipping swaps the texture coordinates, but not the positions.
+        let flipped = compute_vertices(rect, size, Color::WHITE, true, true);
+        assert_eq!((flipped[0].x, flipped[0].y), (0.0, 0.0));
+        assert_eq!((flipped[0].u, flipped[0].v), (0.5, 0.625));
+        assert_eq!((flipped[3].u, flipped[3].v), (0.25, 0.5));
+    }
+
+    #[test]
+    fn sprite_bounds() {
+        let mut sprite = Sprite::new();
+        assert_eq!(sprite.local_bounds(), Box::default());
+
+        sprite.set_texture_rect(Some(Box::new(Position::new(10, 10), Size::new(20, 10))));
+        assert_eq!(sprite.local_bounds(), Box::new(Position::zero(), Size::new(20.0, 10.0)));
+
+        // The origin is placed at the position.
+        sprite.set_origin(Position::new(10.0, 5.0));
+        sprite.set_position(Position::new(100.0, 50.0));
+        assert_eq!(sprite.bounds(), Box::new(Position::new(90.0, 45.0), Size::new(20.0, 10.0)));
+
+        // The sprite is magnified around its origin.
+        sprite.set_magnifier(2.0);
+        assert_eq!(sprite.bounds(), Box::new(Position::new(80.0, 40.0), Size::new(40.0, 20.0)));
+
+        // The sprite is rotated around its origin.
+        sprite.set_magnifier(1.0);
+        sprite.set_angle(90.0);
+
+        let bounds = sprite.bounds();
+        assert!((bounds.left() - 95.0).abs() < 0.001);
+        assert!((bounds.top() - 40.0).abs() < 0.001);
+        assert!((bounds.size.width - 10.0).abs() < 0.001);
+        assert!((bounds.size.height - 20.0).abs() < 0.001);
+    }
+}
@@ -20,6 +20,31 @@ pub enum Error {
     CorruptedData
 }
 
+fn decoding_error(error: png::DecodingError) -> io::Error {
+    match error {
+        png::DecodingError::IoError(error) => error,
+        error => io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+    }
+}
+
+// Convert the decoded bytes of an image with 8 bits per channel to pixels.
+fn bytes_to_pixels(color_type: ColorType, bytes: &[u8]) -> Vec<Color> {
+    match color_type {
+        ColorType::Grayscale => bytes.iter()
+            .map(|gray| Color::rgba(*gray, *gray, *gray, 255))
+            .collect(),
+        ColorType::GrayscaleAlpha => bytes.chunks_exact(2)
+            .map(|pixel| Color::rgba(pixel[0], pixel[0], pixel[0], pixel[1]))
+            .collect(),
+        ColorType::RGB | ColorType::Indexed => bytes.chunks_exact(3)
+            .map(|pixel| Color::rgba(pixel[0], pixel[1], pixel[2], 255))
+            .collect(),
+        ColorType::RGBA => bytes.chunks_exact(4)
+            .map(|pixel| Color::rgba(pixel[0], pixel[1], pixel[2], pixel[3]))
+            .collect()
+    }
+}
+
 fn position_to_index(position: &Position<isize>, width: usize) -> usize {
     (position.y * width as isize + position.x) as usize
 }
@@ -42,7 +67,8 @@ fn position_to_index(position: &Position<isize>, width: usize) -> usize {
 ///   allows common image operations (before they're loaded into texture). But I preferred to keep
 ///   with something simple. Also see `ingrid` crate.
 /// - Supports loading and saving PNG only (for now); will evolve into supporting the most common
-///   image format indeed.
+///   image format indeed. All the PNG color types and bit depths are loaded (they're converted to
+///   RGBA with 8 bits per channel).
 /// - I haven't figured out how should be for default numeric types. In the meantimes, the
 ///   implementation is using usize for sizes and isize for positions. But it might change in the
 ///   future.
@@ -132,30 +158,21 @@ impl Image {
     /// The **load() function** is not documented yet. Pull requests are welcome.
     ///
     pub fn load<R: Read>(&mut self, reader: &mut R) -> io::Result<()> {
-        // Read the size and pixels of the image.
-        let decoder = png::Decoder::new(reader);
-        let (info, mut reader) = decoder.read_info().unwrap();
+        // Read the size and pixels of the image; the palettes, the low bit depths and the high bit
+        // depths are converted to 8 bits per channel.
+        let mut decoder = png::Decoder::new(reader);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
 
-        let length = info.buffer_size();
-        let mut pixels = Vec::<u8>::with_capacity(length);
-        pixels.resize(length, 0);
+        let (info, mut reader) = decoder.read_info().map_err(decoding_error)?;
 
-        reader.next_frame(&mut pixels).unwrap();
+        let mut bytes = vec![0; info.buffer_size()];
+        reader.next_frame(&mut bytes).map_err(decoding_error)?;
 
         // Update the image with the size and pixels.
         assert_eq!(info.bit_depth, BitDepth::Eight);
-        assert_eq!(info.color_type, ColorType::RGBA);
         self.size.width = info.width as usize;
         self.size.height = info.height as usize;
-
-        let pixels = unsafe {
-            // Ensure the original vector is not dropped.
-            let mut pixels_clone = std::mem::ManuallyDrop::new(pixels);
-            Vec::from_raw_parts(pixels_clone.as_mut_ptr() as *mut Color,
-                                pixels_clone.len() / 4,
-                                pixels_clone.capacity() / 4)
-        };
-        self.pixels = pixels;
+        self.pixels = bytes_to_pixels(info.color_type, &bytes);
 
         Ok(())
     }
@@ -286,6 +303,32 @@ mod tests {
         assert_eq!(result.unwrap_err(), Error::InvalidPixels(2 * 3));
     }
 
+    #[test]
+    fn image_bytes_to_pixels() {
+        assert_eq!(bytes_to_pixels(ColorType::Grayscale, &[10, 20]), vec![
+            Color::rgba(10, 10, 10, 255),
+            Color::rgba(20, 20, 20, 255)
+        ]);
+        assert_eq!(bytes_to_pixels(ColorType::GrayscaleAlpha, &[10, 20]), vec![
+            Color::rgba(10, 10, 10, 20)
+        ]);
+        assert_eq!(bytes_to_pixels(ColorType::RGB, &[1, 2, 3, 4, 5, 6]), vec![
+            Color::rgba(1, 2, 3, 255),
+            Color::rgba(4, 5, 6, 255)
+        ]);
+        assert_eq!(bytes_to_pixels(ColorType::RGBA, &[1, 2, 3, 4]), vec![
+            Color::rgba(1, 2, 3, 4)
+        ]);
+    }
+
+    #[test]
+    fn image_load_invalid() {
+        let mut image = Image::new();
+
+        let result = image.load(&mut Cursor::new(b"not an image".to_vec()));
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
     #[test]
     fn image_open() {
         // Creating an image with open() is the same as creating an empty image and loading an image